@@ -0,0 +1,256 @@
+use crate::events::{self, Event, EventLog};
+use crate::recorder::LatencyHistogram;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Width of the rolling window each condition is evaluated over
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// What an `--abort-if` condition watches
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Metric {
+    Average,
+    P50,
+    P95,
+    P99,
+    Max,
+    ErrorRate,
+}
+
+/// Stop the run when `metric` stays above `threshold` for `sustain`, e.g.
+/// `p99>500ms for 30s` or `error_rate>5% for 10s`
+#[derive(Debug, Clone, Copy)]
+pub struct AbortCondition {
+    metric: Metric,
+    /// Milliseconds for latency metrics, percent for the error rate
+    threshold: f64,
+    sustain: Duration,
+}
+
+/// Parse `<metric>><threshold>[ for <duration>]`. Latency metrics (avg, p50, p95,
+/// p99, max) take a duration threshold, `error_rate` a percentage.
+pub fn parse_abort_condition(value: &str) -> Result<AbortCondition, String> {
+    let (condition, sustain) = match value.split_once(" for ") {
+        Some((condition, sustain)) => (condition, crate::parse_duration(sustain)?),
+        None => (value, Duration::ZERO),
+    };
+    let (metric, threshold) = condition.split_once('>').ok_or_else(|| {
+        format!(
+            "invalid abort condition '{}', expected e.g. \"p99>500ms for 30s\"",
+            value
+        )
+    })?;
+
+    let metric = match metric.trim() {
+        "avg" => Metric::Average,
+        "p50" => Metric::P50,
+        "p95" => Metric::P95,
+        "p99" => Metric::P99,
+        "max" => Metric::Max,
+        "error_rate" => Metric::ErrorRate,
+        other => {
+            return Err(format!(
+                "unknown abort metric '{}', expected avg, p50, p95, p99, max or error_rate",
+                other
+            ))
+        }
+    };
+    let threshold = match metric {
+        Metric::ErrorRate => {
+            let percent: f64 = threshold
+                .trim()
+                .trim_end_matches('%')
+                .parse()
+                .map_err(|_| format!("invalid error rate in '{}'", value))?;
+            if !(0.0..100.0).contains(&percent) {
+                return Err(format!(
+                    "error rate threshold must be at least 0 and below 100%, got {}",
+                    percent
+                ));
+            }
+            percent
+        }
+        _ => crate::parse_duration(threshold)?.as_secs_f64() * 1000.0,
+    };
+
+    Ok(AbortCondition {
+        metric,
+        threshold,
+        sustain,
+    })
+}
+
+impl fmt::Display for AbortCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let metric = match self.metric {
+            Metric::Average => "avg",
+            Metric::P50 => "p50",
+            Metric::P95 => "p95",
+            Metric::P99 => "p99",
+            Metric::Max => "max",
+            Metric::ErrorRate => "error_rate",
+        };
+        match self.metric {
+            Metric::ErrorRate => write!(f, "{}>{}%", metric, self.threshold)?,
+            _ => write!(f, "{}>{}ms", metric, self.threshold)?,
+        }
+        if !self.sustain.is_zero() {
+            write!(f, " for {}s", self.sustain.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+/// Queries completed in the current window
+#[derive(Debug, Default)]
+struct Window {
+    latency: LatencyHistogram,
+    failures: u64,
+}
+
+impl Window {
+    fn value(&self, metric: Metric) -> f64 {
+        let summary = self.latency.summary();
+        match metric {
+            Metric::Average => summary.average_ms,
+            Metric::P50 => summary.p50_ms,
+            Metric::P95 => summary.p95_ms,
+            Metric::P99 => summary.p99_ms,
+            Metric::Max => summary.max_ms,
+            Metric::ErrorRate => self.failures as f64 / summary.count.max(1) as f64 * 100.0,
+        }
+    }
+}
+
+/// Watches rolling latency and error rate against the `--abort-if` conditions and
+/// trips once one of them has held for its whole sustain window
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    conditions: Vec<AbortCondition>,
+    window: Mutex<Window>,
+    tripped: OnceLock<AbortReport>,
+}
+
+/// Why the run stopped before its configured duration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AbortReport {
+    /// The `--abort-if` condition that tripped
+    pub condition: String,
+    /// Value in the last window before tripping (ms, or percent for error_rate)
+    pub observed: f64,
+    /// Seconds into the measured run
+    pub after_seconds: f64,
+}
+
+impl CircuitBreaker {
+    pub fn new(conditions: Vec<AbortCondition>) -> Self {
+        Self {
+            conditions,
+            window: Mutex::new(Window::default()),
+            tripped: OnceLock::new(),
+        }
+    }
+
+    /// Record a completed query; failed queries count toward latency as well
+    pub fn record(&self, latency: Duration, success: bool) {
+        let mut window = self.window.lock().unwrap();
+        window.latency.record(latency);
+        if !success {
+            window.failures += 1;
+        }
+    }
+
+    /// Whether the run should stop submitting queries
+    pub fn tripped(&self) -> bool {
+        self.tripped.get().is_some()
+    }
+
+    pub fn report(&self) -> Option<AbortReport> {
+        self.tripped.get().cloned()
+    }
+}
+
+/// Evaluate the conditions once per window until one trips. A window without any
+/// completed queries neither extends nor breaks a streak.
+pub async fn run_circuit_breaker(breaker: Arc<CircuitBreaker>, events: Option<Arc<EventLog>>) {
+    let start = Instant::now();
+    let mut breached_since: Vec<Option<Instant>> = vec![None; breaker.conditions.len()];
+    let mut interval = tokio::time::interval(WINDOW);
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        let window = std::mem::take(&mut *breaker.window.lock().unwrap());
+        if window.latency.summary().count == 0 {
+            continue;
+        }
+
+        let now = Instant::now();
+        for (condition, since) in breaker.conditions.iter().zip(&mut breached_since) {
+            let observed = window.value(condition.metric);
+            if observed <= condition.threshold {
+                *since = None;
+                continue;
+            }
+
+            // The breach started with the window that just closed
+            let since = *since.get_or_insert(now - WINDOW);
+            if now - since >= condition.sustain {
+                let report = AbortReport {
+                    condition: condition.to_string(),
+                    observed,
+                    after_seconds: start.elapsed().as_secs_f64(),
+                };
+                warn!(
+                    "🛑 Aborting: {} (observed {:.2}) after {:.1}s",
+                    report.condition, report.observed, report.after_seconds
+                );
+                events::emit(events.as_deref(), || Event::ThresholdBreached {
+                    threshold: report.condition.clone(),
+                    observed: report.observed,
+                    action: "abort",
+                });
+                let _ = breaker.tripped.set(report);
+                return;
+            }
+        }
+    }
+}
+
+/// Keep whichever shard tripped first
+pub fn merge(into: &mut Option<AbortReport>, other: &Option<AbortReport>) {
+    if let Some(other) = other {
+        if into
+            .as_ref()
+            .is_none_or(|report| other.after_seconds < report.after_seconds)
+        {
+            *into = Some(other.clone());
+        }
+    }
+}
+
+/// Fail the run once its results are out if a condition tripped
+pub fn fail_if_aborted(report: Option<&AbortReport>) -> anyhow::Result<()> {
+    match report {
+        Some(report) => Err(anyhow::anyhow!(
+            "Run aborted after {:.1}s: {}",
+            report.after_seconds,
+            report.condition
+        )),
+        None => Ok(()),
+    }
+}
+
+pub fn display_abort(report: &AbortReport) {
+    println!("\n🛑 Aborted Early:");
+    println!("   Condition:              {}", report.condition);
+    println!("   Observed:               {:.2}", report.observed);
+    println!(
+        "   After:                  {:.1}s (results cover the run up to here)",
+        report.after_seconds
+    );
+}
@@ -0,0 +1,110 @@
+use crate::shard::Shard;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Sorted, de-duplicated CPU core ids
+#[derive(Debug, Clone)]
+pub struct CoreList(pub Vec<usize>);
+
+/// Parse a core list such as `0-15` or `0-3,8,10-11`
+pub fn parse_core_list(value: &str) -> Result<CoreList, String> {
+    let mut cores = Vec::new();
+    for part in value.split(',').map(str::trim) {
+        let parse = |core: &str| {
+            core.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid core '{}' in '{}'", core, value))
+        };
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse(first)?, parse(last)?);
+                if first > last {
+                    return Err(format!("invalid core range '{}'", part));
+                }
+                cores.extend(first..=last);
+            }
+            None => cores.push(parse(part)?),
+        }
+    }
+    cores.sort_unstable();
+    cores.dedup();
+    Ok(CoreList(cores))
+}
+
+/// The cores a `--processes` shard runs on: every `count`-th core starting at its
+/// index, or a single shared core when there are more shards than cores
+pub fn shard_cores(cores: &CoreList, shard: Option<Shard>) -> Vec<usize> {
+    let cores = &cores.0;
+    let Some(shard) = shard else {
+        return cores.to_vec();
+    };
+    let assigned: Vec<usize> = cores
+        .iter()
+        .copied()
+        .skip(shard.index)
+        .step_by(shard.count)
+        .collect();
+    if assigned.is_empty() {
+        vec![cores[shard.index % cores.len()]]
+    } else {
+        assigned
+    }
+}
+
+/// Build the Tokio runtime. With `cores`, there is one worker thread per core and
+/// each worker is pinned to its own core, so scheduling is predictable and the
+/// client's share of measured latency is the same from run to run.
+pub fn build_runtime(cores: Option<Vec<usize>>) -> anyhow::Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all();
+
+    if let Some(cores) = cores {
+        // Threads inherit the mask, so anything not pinned below (the main thread,
+        // blocking-pool threads) stays within the same cores
+        pin_current_thread(&cores)
+            .map_err(|e| anyhow::anyhow!("Failed to pin to cores {:?}: {}", cores, e))?;
+
+        let cores = Arc::new(cores);
+        let next = Arc::new(AtomicUsize::new(0));
+        let worker_cores = Arc::clone(&cores);
+        builder
+            .worker_threads(cores.len())
+            .on_thread_start(move || {
+                let core = worker_cores[next.fetch_add(1, Ordering::Relaxed) % worker_cores.len()];
+                let _ = pin_current_thread(&[core]);
+            });
+    }
+
+    Ok(builder.build()?)
+}
+
+#[cfg(target_os = "linux")]
+fn pin_current_thread(cores: &[usize]) -> io::Result<()> {
+    // SAFETY: cpu_set_t is a plain bitmask for which all-zero is the empty set,
+    // and CPU_SET only writes within it for indices below CPU_SETSIZE
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &core in cores {
+            if core >= libc::CPU_SETSIZE as usize {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("core {} is out of range", core),
+                ));
+            }
+            libc::CPU_SET(core, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_current_thread(_cores: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "core pinning is only supported on Linux",
+    ))
+}
@@ -0,0 +1,266 @@
+use crate::export::{self, value_name};
+use crate::{config, Args, QueryType, ShapeKind, SimulationResult, TrafficPattern, Workload};
+use std::ffi::OsString;
+use std::time::Duration;
+use tracing::info;
+
+/// What the simulated clients run
+#[derive(Debug, Clone)]
+pub enum WorkloadSpec {
+    /// The orders data model, issuing requests of one query type (`--query-type`)
+    Orders(QueryType),
+    /// A benchmark preset such as pgbench or a YCSB core workload (`--workload`)
+    Preset(Workload),
+    /// Queries from a WebAssembly generator module (`--plugin`)
+    Plugin(String),
+    /// Queries from a Rhai generator script (`--script`)
+    Script(String),
+}
+
+/// A configured simulation that can be run from other programs, e.g. an
+/// integration test harness:
+///
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use traffic_simulator::{QueryType, Simulator, WorkloadSpec};
+///
+/// let result = Simulator::builder()
+///     .database_url("postgresql://postgres@localhost/demo")
+///     .connections(20)
+///     .duration(std::time::Duration::from_secs(30))
+///     .workload(WorkloadSpec::Orders(QueryType::Mixed))
+///     .build()?
+///     .run()
+///     .await?;
+/// assert!(result.p99_latency_ms() < 50.0);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Simulator {
+    args: Args,
+}
+
+/// Builds a [`Simulator`] from the same settings as the command line. Typed setters
+/// cover the common ones; [`SimulatorBuilder::option`] and [`SimulatorBuilder::flag`]
+/// reach every other flag. Settings are validated together by [`SimulatorBuilder::build`],
+/// with the binary's rules for conflicting and dependent flags.
+#[derive(Debug, Clone)]
+pub struct SimulatorBuilder {
+    argv: Vec<OsString>,
+    /// A setting the command line cannot express, reported by `build`
+    invalid: Option<String>,
+}
+
+impl Simulator {
+    pub fn builder() -> SimulatorBuilder {
+        SimulatorBuilder {
+            argv: vec![OsString::from(env!("CARGO_PKG_NAME"))],
+            invalid: None,
+        }
+    }
+
+    /// Connect, warm up and run the workload on the caller's Tokio runtime. The result
+    /// is returned rather than printed; a run stopped by --abort-if returns normally
+    /// with [`SimulationResult::aborted`] set. Output files (--output-json,
+    /// --timeline-output, --queue-depth-output) are still written when configured.
+    pub async fn run(&self) -> anyhow::Result<SimulationResult> {
+        // Each run gets its own run id, as each invocation of the binary does
+        let mut args = Args::from_argv(self.args.argv.clone())?;
+        crate::prepare_args(&mut args);
+        let args = &args;
+        let run = crate::execute_run(args).await?;
+        crate::write_time_series(args, &run)?;
+        if let Some(path) = &args.output_json {
+            let span = export::RunSpan {
+                started_at: run.started_at,
+                finished_at: run.finished_at,
+                server_version: run.server_version,
+            };
+            export::write_run_file(path, args, &span, &run.result)?;
+            if !args.disable_logging {
+                info!("📝 Results written to {}", path);
+            }
+        }
+        Ok(run.result)
+    }
+}
+
+impl SimulatorBuilder {
+    /// Set `--NAME VALUE`, e.g. `.option("retry", "serialization:5:10ms")`
+    pub fn option(mut self, name: &str, value: impl ToString) -> Self {
+        self.argv.push(format!("--{}", name).into());
+        self.argv.push(value.to_string().into());
+        self
+    }
+
+    /// Set a switch, e.g. `.flag("prepared")`
+    pub fn flag(mut self, name: &str) -> Self {
+        self.argv.push(format!("--{}", name).into());
+        self
+    }
+
+    pub fn database_url(self, url: &str) -> Self {
+        self.option("database-url", url)
+    }
+
+    pub fn connections(self, connections: usize) -> Self {
+        self.option("connections", connections)
+    }
+
+    /// How long the measured run lasts, in whole seconds
+    pub fn duration(self, duration: Duration) -> Self {
+        self.whole_seconds("duration", duration)
+    }
+
+    /// Stop after this many queries, or at the end of the duration if that comes first
+    pub fn total_queries(self, total_queries: usize) -> Self {
+        self.option("total-queries", total_queries)
+    }
+
+    /// Warmup before the measured run, in whole seconds; zero skips it
+    pub fn warmup(self, warmup: Duration) -> Self {
+        self.whole_seconds("warmup", warmup)
+    }
+
+    /// Set `--NAME SECONDS`, which takes whole seconds only; a fraction is an error
+    /// from `build` rather than silently dropped
+    fn whole_seconds(mut self, name: &str, value: Duration) -> Self {
+        if value.subsec_nanos() != 0 {
+            self.invalid = Some(format!("--{} takes whole seconds, got {:?}", name, value));
+        }
+        self.option(name, value.as_secs())
+    }
+
+    pub fn workload(self, workload: WorkloadSpec) -> Self {
+        match workload {
+            WorkloadSpec::Orders(query_type) => self.option("query-type", value_name(&query_type)),
+            WorkloadSpec::Preset(workload) => self.option("workload", value_name(&workload)),
+            WorkloadSpec::Plugin(path) => self.option("plugin", path),
+            WorkloadSpec::Script(path) => self.option("script", path),
+        }
+    }
+
+    /// Vary the load over the run along a built-in traffic pattern
+    pub fn pattern(self, pattern: TrafficPattern) -> Self {
+        self.option("pattern", value_name(&pattern))
+    }
+
+    /// Vary the load over the run along phases from a YAML or JSON file
+    pub fn pattern_file(self, path: &str) -> Self {
+        self.option("pattern-file", path)
+    }
+
+    /// Offer a fixed rate of queries, whether or not earlier ones have completed
+    pub fn target_qps(self, target_qps: f64) -> Self {
+        self.option("target-qps", target_qps)
+    }
+
+    /// Vary the target QPS continuously between `min_qps` and `max_qps` along
+    /// `shape`, one cycle every `period`
+    pub fn shape(self, shape: ShapeKind, period: Duration, min_qps: f64, max_qps: f64) -> Self {
+        self.option("shape", value_name(&shape))
+            .option("period", format!("{}ms", period.as_millis()))
+            .option("min-qps", min_qps)
+            .option("max-qps", max_qps)
+    }
+
+    pub fn label(self, key: &str, value: &str) -> Self {
+        self.option("label", format!("{}={}", key, value))
+    }
+
+    /// Start from a `--config` profile; settings made on the builder override it
+    pub fn config(mut self, path: &str) -> Self {
+        self.argv.insert(1, "--config".into());
+        self.argv.insert(2, path.into());
+        self
+    }
+
+    /// No log output of the simulator's own (the caller's tracing subscriber, if any,
+    /// otherwise receives it)
+    pub fn quiet(self) -> Self {
+        self.flag("disable-logging")
+    }
+
+    pub fn build(self) -> anyhow::Result<Simulator> {
+        if let Some(invalid) = self.invalid {
+            return Err(anyhow::anyhow!(invalid));
+        }
+        let args = Args::from_argv(config::expand_args(self.argv)?)?;
+
+        let cli_only = [
+            (args.processes > 1, "--processes"),
+            (args.database_urls.len() > 1, "more than one --database-url"),
+            (args.coordinator || args.join.is_some(), "distributed runs"),
+            (args.pin_cores.is_some(), "--pin-cores"),
+            (args.tui, "--tui"),
+            (args.baseline.is_some(), "--baseline"),
+            (args.publish_databricks.is_some(), "--publish-databricks"),
+        ];
+        if let Some((_, setting)) = cli_only.iter().find(|(set, _)| *set) {
+            return Err(anyhow::anyhow!(
+                "{} is only supported by the command-line simulator",
+                setting
+            ));
+        }
+
+        Ok(Simulator { args })
+    }
+}
+
+/// Headline numbers; the whole result serializes to the same JSON as `--output-json`'s
+/// `result`
+impl SimulationResult {
+    pub fn total_queries(&self) -> usize {
+        self.total_queries
+    }
+
+    pub fn successful_queries(&self) -> usize {
+        self.successful_queries
+    }
+
+    pub fn failed_queries(&self) -> usize {
+        self.failed_queries
+    }
+
+    pub fn duration_seconds(&self) -> f64 {
+        self.duration_seconds
+    }
+
+    pub fn queries_per_second(&self) -> f64 {
+        self.queries_per_second
+    }
+
+    pub fn average_latency_ms(&self) -> f64 {
+        self.average_latency_ms
+    }
+
+    pub fn p50_latency_ms(&self) -> f64 {
+        self.p50_latency_ms
+    }
+
+    pub fn p95_latency_ms(&self) -> f64 {
+        self.p95_latency_ms
+    }
+
+    pub fn p99_latency_ms(&self) -> f64 {
+        self.p99_latency_ms
+    }
+
+    pub fn p999_latency_ms(&self) -> f64 {
+        self.p999_latency_ms
+    }
+
+    pub fn latency_std_dev_ms(&self) -> f64 {
+        self.latency_std_dev_ms
+    }
+
+    pub fn latency_coefficient_of_variation(&self) -> f64 {
+        self.latency_coefficient_of_variation
+    }
+
+    /// Whether --abort-if stopped the run early
+    pub fn aborted(&self) -> bool {
+        self.aborted.is_some()
+    }
+}
@@ -0,0 +1,44 @@
+use rand::Rng;
+use rand_distr::{Distribution, Exp};
+use std::time::Duration;
+
+/// Mean number of queries that arrive together in a `burst` cluster
+const BURST_SIZE: f64 = 8.0;
+
+/// How the real-world simulation spaces queries around the current target QPS
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Arrival {
+    /// Evenly spaced, one query every 1/QPS seconds
+    Uniform,
+    /// Independent arrivals with exponentially distributed gaps (a Poisson process)
+    Poisson,
+    /// Clusters of queries arriving back to back, with exponential gaps between clusters
+    Burst,
+}
+
+impl Arrival {
+    /// Time to wait before the next query; averages 1/`qps` for every process
+    pub fn next_interval(self, qps: f64, rng: &mut impl Rng) -> Duration {
+        let mean = 1.0 / qps;
+        match self {
+            Arrival::Uniform => Duration::from_secs_f64(mean),
+            Arrival::Poisson => exponential(mean, rng),
+            // A query continues the current cluster with probability 1 - 1/BURST_SIZE,
+            // so cluster sizes are geometric with mean BURST_SIZE and the gaps between
+            // clusters stretch by the same factor to keep the average rate
+            Arrival::Burst => {
+                if rng.gen_bool(1.0 - 1.0 / BURST_SIZE) {
+                    Duration::ZERO
+                } else {
+                    exponential(mean * BURST_SIZE, rng)
+                }
+            }
+        }
+    }
+}
+
+fn exponential(mean: f64, rng: &mut impl Rng) -> Duration {
+    Exp::new(1.0 / mean)
+        .map(|exp| Duration::from_secs_f64(exp.sample(rng)))
+        .unwrap_or(Duration::from_secs_f64(mean))
+}
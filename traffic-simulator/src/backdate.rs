@@ -0,0 +1,93 @@
+use chrono::{DateTime, Datelike, Local, Timelike, Utc};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::time::Duration;
+
+const HOUR_SECONDS: i64 = 3600;
+
+/// Relative order volume by local hour of day: a store-operations day with a
+/// lunch dip and a quiet night
+const HOUR_WEIGHTS: [f64; 24] = [
+    0.05, 0.03, 0.03, 0.03, 0.05, 0.1, 0.3, 0.6, 0.9, 1.1, 1.2, 1.15, 0.8, 1.0, 1.1, 1.05, 0.95,
+    0.7, 0.5, 0.35, 0.25, 0.2, 0.12, 0.08,
+];
+
+/// Relative order volume by day of week, Monday first; weekends are quiet but
+/// not empty
+const WEEKDAY_WEIGHTS: [f64; 7] = [1.0, 1.05, 1.05, 1.0, 0.9, 0.4, 0.3];
+
+/// Extra volume around month end, when stores restock
+const MONTH_END_BOOST: f64 = 1.3;
+
+/// Month-over-month volume growth, as in the demo data generator
+const MONTHLY_GROWTH: f64 = 0.15;
+const DAYS_PER_MONTH: f64 = 30.44;
+
+/// Spreads inserted order timestamps over a window that ends when the run starts,
+/// weighted by hour of day, day of week, month-end restocking and steady growth
+#[derive(Debug)]
+pub struct Backdate {
+    now: i64,
+    window_start: i64,
+    /// Start of the hour containing `now`; slot `i` starts `i` hours earlier
+    latest_slot: i64,
+    slots: WeightedIndex<f64>,
+}
+
+impl Backdate {
+    pub fn new(window: Duration) -> Self {
+        let now = Utc::now().timestamp();
+        let window_start = now - window.as_secs().max(1) as i64;
+        let latest_slot = now - now.rem_euclid(HOUR_SECONDS);
+
+        let slot_count =
+            (latest_slot - window_start + HOUR_SECONDS - 1).div_euclid(HOUR_SECONDS) + 1;
+        let weights: Vec<f64> = (0..slot_count)
+            .map(|i| {
+                let start = latest_slot - i * HOUR_SECONDS;
+                let end = start + HOUR_SECONDS;
+                // Slots cut by either end of the window only get their share
+                let covered =
+                    (end.min(now) - start.max(window_start)).max(0) as f64 / HOUR_SECONDS as f64;
+                covered * slot_weight(start, now)
+            })
+            .collect();
+
+        Self {
+            now,
+            window_start,
+            latest_slot,
+            slots: WeightedIndex::new(weights).expect("backdate window has no hours"),
+        }
+    }
+
+    /// Pick an order timestamp, as Unix seconds
+    pub fn sample(&self, rng: &mut StdRng) -> f64 {
+        let slot = self.slots.sample(rng) as i64;
+        let start = (self.latest_slot - slot * HOUR_SECONDS).max(self.window_start);
+        let end = (self.latest_slot - slot * HOUR_SECONDS + HOUR_SECONDS).min(self.now);
+        if end > start {
+            rng.gen_range(start as f64..end as f64)
+        } else {
+            start as f64
+        }
+    }
+}
+
+fn slot_weight(start: i64, now: i64) -> f64 {
+    let local = DateTime::from_timestamp(start, 0)
+        .unwrap_or_default()
+        .with_timezone(&Local);
+    let month_end = if local.day() >= 25 || local.day() <= 5 {
+        MONTH_END_BOOST
+    } else {
+        1.0
+    };
+    let months_back = (now - start) as f64 / 86400.0 / DAYS_PER_MONTH;
+
+    HOUR_WEIGHTS[local.hour() as usize]
+        * WEEKDAY_WEIGHTS[local.weekday().num_days_from_monday() as usize]
+        * month_end
+        / (1.0 + MONTHLY_GROWTH).powf(months_back)
+}
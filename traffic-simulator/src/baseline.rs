@@ -0,0 +1,141 @@
+use crate::report::{self, RunFileResult};
+use crate::{Args, SimulationResult};
+use std::path::Path;
+
+/// Parse a regression threshold, e.g. `5` or `2.5`
+pub fn parse_threshold(value: &str) -> Result<f64, String> {
+    let threshold: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid threshold '{}'", value))?;
+    if !threshold.is_finite() || threshold < 0.0 {
+        return Err(format!("threshold must be zero or more, got '{}'", value));
+    }
+    Ok(threshold)
+}
+
+/// A previously exported run (--output-json) that this run is compared against
+#[derive(Debug)]
+pub struct Baseline {
+    path: String,
+    result: RunFileResult,
+}
+
+impl Baseline {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let run = report::load_run(Path::new(path))
+            .map_err(|e| anyhow::anyhow!("Failed to load baseline '{}': {}", path, e))?;
+        Ok(Baseline {
+            path: path.to_string(),
+            result: run.result,
+        })
+    }
+
+    /// Print the run's change against the baseline; an error when any change
+    /// exceeds its --max-* threshold
+    pub fn compare(&self, result: &SimulationResult, args: &Args) -> anyhow::Result<()> {
+        let baseline = &self.result;
+        let rows = [
+            Row {
+                name: "Queries/Second",
+                baseline: baseline.queries_per_second,
+                current: result.queries_per_second,
+                change: Change::Percent,
+                regressed: percent_change(baseline.queries_per_second, result.queries_per_second)
+                    < -args.max_qps_drop,
+            },
+            Row {
+                name: "P95 Latency (ms)",
+                baseline: baseline.p95_latency_ms,
+                current: result.p95_latency_ms,
+                change: Change::Percent,
+                regressed: percent_change(baseline.p95_latency_ms, result.p95_latency_ms)
+                    > args.max_latency_rise,
+            },
+            Row {
+                name: "P99 Latency (ms)",
+                baseline: baseline.p99_latency_ms,
+                current: result.p99_latency_ms,
+                change: Change::Percent,
+                regressed: percent_change(baseline.p99_latency_ms, result.p99_latency_ms)
+                    > args.max_latency_rise,
+            },
+            Row {
+                name: "Error Rate (%)",
+                baseline: error_rate(baseline.failed_queries, baseline.total_queries),
+                current: error_rate(result.failed_queries, result.total_queries),
+                change: Change::Points,
+                regressed: error_rate(result.failed_queries, result.total_queries)
+                    - error_rate(baseline.failed_queries, baseline.total_queries)
+                    > args.max_error_rate_rise,
+            },
+        ];
+
+        println!("\n⚖️  Comparison with Baseline ({}):", self.path);
+        println!("   Metric                baseline    current      change");
+        for row in &rows {
+            let change = match row.change {
+                Change::Percent => format!("{:>+9.1}%", percent_change(row.baseline, row.current)),
+                Change::Points => format!("{:>+8.2}pp", row.current - row.baseline),
+            };
+            println!(
+                "   {:<20}{:>10.2}{:>11.2}  {}  {}",
+                row.name,
+                row.baseline,
+                row.current,
+                change,
+                if row.regressed { "❌" } else { "✅" }
+            );
+        }
+        println!(
+            "   Thresholds: QPS drop {}%, latency rise {}%, error rate rise {}pp",
+            args.max_qps_drop, args.max_latency_rise, args.max_error_rate_rise
+        );
+
+        let regressed: Vec<&str> = rows
+            .iter()
+            .filter(|row| row.regressed)
+            .map(|row| row.name)
+            .collect();
+        if regressed.is_empty() {
+            println!("   ✅ No regression against the baseline");
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Regression against baseline '{}': {}",
+                self.path,
+                regressed.join(", ")
+            ))
+        }
+    }
+}
+
+struct Row {
+    name: &'static str,
+    baseline: f64,
+    current: f64,
+    change: Change,
+    regressed: bool,
+}
+
+/// Rates are compared in percentage points, everything else relative to the baseline
+enum Change {
+    Percent,
+    Points,
+}
+
+/// Relative change in percent; zero when the baseline gives nothing to compare against
+pub fn percent_change(baseline: f64, current: f64) -> f64 {
+    if baseline > 0.0 {
+        (current - baseline) / baseline * 100.0
+    } else {
+        0.0
+    }
+}
+
+pub fn error_rate(failed: usize, total: usize) -> f64 {
+    if total > 0 {
+        failed as f64 / total as f64 * 100.0
+    } else {
+        0.0
+    }
+}
@@ -0,0 +1,102 @@
+use crate::skew;
+use crate::stores::{self, StoreWeights};
+use crate::writes::{WriteCounts, WriteKind};
+use crate::QueryContext;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio_postgres::Row;
+
+/// Largest stock correction of an inventory adjustment, in cases
+const MAX_SHRINK_CASES: i32 = 10;
+
+/// Largest delivery an inventory adjustment books, in cases
+const MAX_DELIVERY_CASES: i32 = 20;
+
+/// `--query-type inventory`: book a delivery or a stock correction for one product
+/// at one store. Stock never goes below zero, and `version` is bumped like the
+/// demo app's optimistic locking does.
+pub async fn execute_inventory_adjustment(
+    client: &deadpool_postgres::Client,
+    ctx: &QueryContext,
+    seed: u64,
+    stores: Option<&StoreWeights>,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<Vec<Row>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let store_id = stores::choose_store(stores, ctx.skew, &mut rng);
+    let product_id = ctx.skew.pick_i32(skew::PRODUCT_COUNT, &mut rng);
+    let delta = rng.gen_range(-MAX_SHRINK_CASES..=MAX_DELIVERY_CASES);
+    let rows = ctx
+        .statements
+        .query(
+            client,
+            "UPDATE inventory
+             SET quantity_cases = GREATEST(quantity_cases + $3, 0), version = version + 1,
+                 last_updated = CURRENT_TIMESTAMP
+             WHERE store_id = $1 AND product_id = $2
+             RETURNING inventory_id, quantity_cases, version",
+            &[&store_id, &product_id, &delta],
+        )
+        .await?;
+    writes.record(WriteKind::Update, rows.len() as u64);
+
+    Ok(rows)
+}
+
+/// `--query-type catalog`: one category of a store's product catalog with the cases
+/// on hand, as the ordering screen lists it
+pub async fn execute_catalog_read(
+    client: &deadpool_postgres::Client,
+    ctx: &QueryContext,
+    seed: u64,
+    stores: Option<&StoreWeights>,
+) -> anyhow::Result<Vec<Row>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let store_id = stores::choose_store(stores, ctx.skew, &mut rng);
+    // The category of a zipfian-picked product, so --skew makes hot categories too
+    let product_id = ctx.skew.pick_i32(skew::PRODUCT_COUNT, &mut rng);
+    let rows = ctx
+        .statements
+        .query(
+            client,
+            "SELECT p.product_id, p.product_name, p.brand, p.package_size, p.unit_price,
+                    COALESCE(i.quantity_cases - i.reserved_cases, 0) AS available_cases
+             FROM products p
+             LEFT JOIN inventory i ON i.product_id = p.product_id AND i.store_id = $1
+             WHERE p.category = (SELECT category FROM products WHERE product_id = $2)
+             ORDER BY p.product_name",
+            &[&store_id, &product_id],
+        )
+        .await?;
+
+    Ok(rows)
+}
+
+/// `--query-type store`: a store's page, its details with its latest orders and the
+/// products they are for
+pub async fn execute_store_lookup(
+    client: &deadpool_postgres::Client,
+    ctx: &QueryContext,
+    seed: u64,
+    stores: Option<&StoreWeights>,
+) -> anyhow::Result<Vec<Row>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let store_id = stores::choose_store(stores, ctx.skew, &mut rng);
+    let rows = ctx
+        .statements
+        .query(
+            client,
+            "SELECT s.store_id, s.store_name, s.region, o.order_id, o.order_number,
+                    o.order_status, o.quantity_cases, p.product_name
+             FROM stores s
+             LEFT JOIN orders o ON o.to_store_id = s.store_id
+             LEFT JOIN products p ON p.product_id = o.product_id
+             WHERE s.store_id = $1
+             ORDER BY o.order_date DESC
+             LIMIT 10",
+            &[&store_id],
+        )
+        .await?;
+
+    Ok(rows)
+}
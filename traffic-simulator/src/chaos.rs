@@ -0,0 +1,218 @@
+use crate::recorder::LatencyHistogram;
+use crate::sampling;
+use crate::LatencySummary;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// "chaos", see [`crate::sampling`]
+const CHAOS_SEED_SALT: u64 = 0x6368_616f_7300_0000;
+
+/// What `--chaos` does to a request
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum FaultKind {
+    /// Have the server terminate the request's pooled connection, which then fails
+    /// like a connection killed by a failover or an idle-connection reaper
+    ConnectionDrop,
+    /// Fail the request without sending it to the database
+    Error,
+}
+
+impl FaultKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            FaultKind::ConnectionDrop => "connection-drop",
+            FaultKind::Error => "error",
+        }
+    }
+}
+
+/// One `--chaos KIND:rate=PERCENT` option
+#[derive(Debug, Clone, Copy)]
+pub struct Fault {
+    kind: FaultKind,
+    /// Share of attempts the fault hits, above 0 and at most 1
+    rate: f64,
+}
+
+/// Parse `connection-drop:rate=0.5%` or `error:rate=0.01` (a fraction without `%`)
+pub fn parse_fault(value: &str) -> Result<Fault, String> {
+    let (kind, rate) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected KIND:rate=PERCENT, got '{}'", value))?;
+    let kind = match kind {
+        "connection-drop" => FaultKind::ConnectionDrop,
+        "error" => FaultKind::Error,
+        _ => {
+            return Err(format!(
+                "unknown fault '{}' (expected connection-drop or error)",
+                kind
+            ))
+        }
+    };
+    let rate = rate
+        .strip_prefix("rate=")
+        .ok_or_else(|| format!("missing rate= in '{}'", value))?;
+    Ok(Fault {
+        kind,
+        rate: crate::parse_rate(rate)?,
+    })
+}
+
+/// Error returned by requests an `error` fault hit
+#[derive(Debug)]
+pub struct InjectedError;
+
+impl fmt::Display for InjectedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error injected by --chaos")
+    }
+}
+
+impl std::error::Error for InjectedError {}
+
+/// `--chaos`: faults injected into a share of requests, and how the workload
+/// recovers from each of them
+#[derive(Debug)]
+pub struct Chaos {
+    faults: Vec<Fault>,
+    state: Mutex<ChaosState>,
+}
+
+#[derive(Debug, Default)]
+struct ChaosState {
+    injected: BTreeMap<FaultKind, u64>,
+    /// When each fault no request has succeeded since was injected, oldest first
+    unrecovered: Vec<Instant>,
+    /// Injection to the next success of a request started after it, in milliseconds
+    recovery: LatencyHistogram,
+    failed_while_recovering: u64,
+}
+
+/// How often faults were injected and how quickly requests succeeded again
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ChaosReport {
+    /// Configured share of attempts per fault kind
+    rates: BTreeMap<FaultKind, f64>,
+    injected: BTreeMap<FaultKind, u64>,
+    /// From each injection until a request started after it succeeded
+    recovery: LatencySummary,
+    /// Failed requests, the injected ones included, completed while at least one
+    /// injection was not yet followed by a success
+    failed_while_recovering: u64,
+    /// Injections the run ended before recovering from
+    unrecovered: u64,
+}
+
+impl Chaos {
+    pub fn new(faults: &[Fault]) -> Option<Self> {
+        (!faults.is_empty()).then(|| Self {
+            faults: faults.to_vec(),
+            state: Mutex::new(ChaosState::default()),
+        })
+    }
+
+    /// The fault to inject into attempt `attempt` of the request with `seed`, if any.
+    /// Retries draw again, so they can recover from the fault that hit the first try.
+    pub fn fault(&self, seed: u64, attempt: u32) -> Option<FaultKind> {
+        let mut rng = sampling::attempt_rng(seed, CHAOS_SEED_SALT, attempt);
+        self.faults
+            .iter()
+            .find(|fault| rng.gen_bool(fault.rate))
+            .map(|fault| fault.kind)
+    }
+
+    /// Inject `kind` on a checked-out connection, returning the error the request
+    /// fails with
+    pub async fn inject(&self, kind: FaultKind, client: &tokio_postgres::Client) -> anyhow::Error {
+        {
+            let mut state = self.state.lock().unwrap();
+            *state.injected.entry(kind).or_insert(0) += 1;
+            state.unrecovered.push(Instant::now());
+        }
+        match kind {
+            // The terminated connection goes back to the pool closed, and the pool
+            // replaces it on a later checkout
+            FaultKind::ConnectionDrop => match client
+                .simple_query("SELECT pg_terminate_backend(pg_backend_pid())")
+                .await
+            {
+                Err(e) => e.into(),
+                Ok(_) => anyhow::anyhow!("the server did not terminate the connection"),
+            },
+            FaultKind::Error => InjectedError.into(),
+        }
+    }
+
+    /// Record a finished request that took `latency`
+    pub fn record(&self, latency: Duration, success: bool) {
+        let started = Instant::now() - latency;
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        if state.unrecovered.is_empty() {
+            return;
+        }
+        if !success {
+            state.failed_while_recovering += 1;
+            return;
+        }
+        let recovered = state
+            .unrecovered
+            .iter()
+            .take_while(|injected| **injected <= started)
+            .count();
+        for injected in state.unrecovered.drain(..recovered) {
+            state.recovery.record(injected.elapsed());
+        }
+    }
+
+    pub fn report(&self) -> ChaosReport {
+        let state = self.state.lock().unwrap();
+        ChaosReport {
+            rates: self
+                .faults
+                .iter()
+                .map(|fault| (fault.kind, fault.rate))
+                .collect(),
+            injected: state.injected.clone(),
+            recovery: state.recovery.summary(),
+            failed_while_recovering: state.failed_while_recovering,
+            unrecovered: state.unrecovered.len() as u64,
+        }
+    }
+}
+
+pub fn display_chaos(report: &ChaosReport) {
+    println!("\n💥 Chaos:");
+    for (kind, rate) in &report.rates {
+        println!(
+            "   {:<16} {:>6.2}% of attempts, {:>8} injected",
+            kind.name(),
+            rate * 100.0,
+            report.injected.get(kind).copied().unwrap_or(0)
+        );
+    }
+    if report.recovery.count > 0 {
+        println!(
+            "   Recovery (ms):          avg {:.2}  p50 {:.2}  p99 {:.2}  max {:.2}",
+            report.recovery.average_ms,
+            report.recovery.p50_ms,
+            report.recovery.p99_ms,
+            report.recovery.max_ms
+        );
+    }
+    println!(
+        "   Failed While Recovering: {:>8}",
+        report.failed_while_recovering
+    );
+    if report.unrecovered > 0 {
+        println!("   Unrecovered at End:      {:>8}", report.unrecovered);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::credentials::{self, CredentialProvider};
+use crate::query_timeout;
+use crate::recorder::LatencyHistogram;
+use crate::sampling;
+use crate::tls::TlsOptions;
+use crate::LatencySummary;
+use deadpool_postgres::{ManagerConfig, Pool, RecyclingMethod, Runtime};
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// "churn", see [`crate::sampling`]
+const CHURN_SEED_SALT: u64 = 0x6368_7572_6e00_0000;
+
+/// Parse a fraction of requests above 0 and at most 1, e.g. `0.25`
+pub fn parse_churn_fraction(value: &str) -> Result<f64, String> {
+    let fraction: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid churn fraction '{}'", value))?;
+    if !(fraction > 0.0 && fraction <= 1.0) {
+        return Err(format!(
+            "churn fraction must be above 0 and at most 1, got '{}'",
+            value
+        ));
+    }
+    Ok(fraction)
+}
+
+/// `--connection-churn`: a share of requests open a brand new connection, run, and
+/// close it, like an application without a connection pool
+#[derive(Debug)]
+pub struct ConnectionChurn {
+    fraction: f64,
+    /// Never holds idle connections: every checkout connects (TCP, TLS, startup and
+    /// authentication), and every connection is detached and closed after use
+    pool: Pool,
+    stats: Mutex<ChurnStats>,
+}
+
+/// Mergeable connect and checkout timings, handed from shards to the parent process
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ChurnStats {
+    fraction: f64,
+    /// Connections opened for a single request, in milliseconds
+    connect: LatencyHistogram,
+    /// Checkouts from the regular pool during the same run, in milliseconds
+    pooled_acquire: LatencyHistogram,
+}
+
+/// What opening a connection per request costs compared to checking one out of the pool
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ChurnReport {
+    /// Share of requests configured to open their own connection (--connection-churn)
+    fraction: f64,
+    /// Connections opened and closed for a single request
+    connections_opened: u64,
+    /// TCP connect, TLS handshake and startup/authentication of those connections
+    connect_latency: LatencySummary,
+    pooled_acquire_latency: LatencySummary,
+    /// Average connect minus average pooled checkout: what a pooler saves per request
+    connect_cost_ms: f64,
+}
+
+impl ConnectionChurn {
+    pub fn new(
+        database_url: &str,
+        fraction: f64,
+        max_connections: usize,
+        tls: &TlsOptions,
+        credentials: Option<&Arc<CredentialProvider>>,
+        query_timeout: Option<Duration>,
+    ) -> anyhow::Result<Self> {
+        let mut pg_config = database_url.parse::<tokio_postgres::Config>()?;
+        tls.configure(&mut pg_config);
+        query_timeout::configure(&mut pg_config, query_timeout);
+        let tls = tls.connector()?;
+        let manager = credentials::manager(
+            pg_config,
+            tls,
+            ManagerConfig {
+                recycling_method: RecyclingMethod::Fast,
+            },
+            credentials,
+        );
+        // No create timeout: a slow handshake is what this mode measures
+        let pool = Pool::builder(manager)
+            .max_size(max_connections.max(1))
+            .runtime(Runtime::Tokio1)
+            .build()?;
+
+        Ok(Self {
+            fraction,
+            pool,
+            stats: Mutex::new(ChurnStats {
+                fraction,
+                ..ChurnStats::default()
+            }),
+        })
+    }
+
+    /// Whether the request with `seed` opens its own connection
+    pub fn churns(&self, seed: u64) -> bool {
+        sampling::request_rng(seed, CHURN_SEED_SALT).gen_bool(self.fraction)
+    }
+
+    /// Open a connection for one request
+    pub async fn connect(&self) -> Result<deadpool_postgres::Client, deadpool_postgres::PoolError> {
+        self.pool.get().await
+    }
+
+    /// Close a connection opened by `connect` instead of returning it to the pool
+    pub fn close(&self, client: deadpool_postgres::Client) {
+        drop(deadpool_postgres::Object::take(client));
+    }
+
+    pub fn record_connect(&self, connect_time: Duration) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.connect.record(connect_time);
+    }
+
+    pub fn record_pooled_acquire(&self, acquire_time: Duration) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.pooled_acquire.record(acquire_time);
+    }
+
+    pub fn stats(&self) -> ChurnStats {
+        self.stats.lock().unwrap().clone()
+    }
+}
+
+impl ChurnStats {
+    pub fn merge(&mut self, other: &ChurnStats) {
+        self.fraction = self.fraction.max(other.fraction);
+        self.connect.merge(&other.connect);
+        self.pooled_acquire.merge(&other.pooled_acquire);
+    }
+
+    /// `None` unless --connection-churn was given
+    pub fn report(&self) -> Option<ChurnReport> {
+        if self.fraction == 0.0 {
+            return None;
+        }
+
+        let connect_latency = self.connect.summary();
+        let pooled_acquire_latency = self.pooled_acquire.summary();
+        Some(ChurnReport {
+            fraction: self.fraction,
+            connections_opened: connect_latency.count as u64,
+            connect_cost_ms: connect_latency.average_ms - pooled_acquire_latency.average_ms,
+            connect_latency,
+            pooled_acquire_latency,
+        })
+    }
+}
+
+pub fn display_connection_churn(report: &ChurnReport) {
+    println!(
+        "\n🔌 Connection Churn ({:.0}% of requests connect without a pool):",
+        report.fraction * 100.0
+    );
+    println!(
+        "   Connections Opened:     {:>8}",
+        report.connections_opened
+    );
+    if report.connections_opened > 0 {
+        println!(
+            "   Connect + Handshake (ms): avg {:.2}  p50 {:.2}  p99 {:.2}",
+            report.connect_latency.average_ms,
+            report.connect_latency.p50_ms,
+            report.connect_latency.p99_ms
+        );
+    }
+    if report.pooled_acquire_latency.count > 0 {
+        println!(
+            "   Pooled Checkout (ms):     avg {:.2}  p50 {:.2}  p99 {:.2}",
+            report.pooled_acquire_latency.average_ms,
+            report.pooled_acquire_latency.p50_ms,
+            report.pooled_acquire_latency.p99_ms
+        );
+    }
+    if report.connections_opened > 0 && report.pooled_acquire_latency.count > 0 {
+        println!(
+            "   💡 A pooler saves about {:.2}ms per request that connects",
+            report.connect_cost_ms
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::Args;
+use clap::CommandFactory;
+use std::ffi::OsString;
+
+/// Expand `--config FILE` into the flags the file describes. They are placed ahead
+/// of the command line's own arguments, so flags given there override the file
+/// (repeatable flags such as `--label` add to it).
+pub fn expand_args(args: Vec<OsString>) -> anyhow::Result<Vec<OsString>> {
+    let Some(path) = config_path(&args) else {
+        return Ok(args);
+    };
+    let profile = load_profile(&path)?;
+
+    let mut expanded = Vec::with_capacity(args.len() + profile.len());
+    let mut args = args.into_iter();
+    expanded.extend(args.next());
+    expanded.extend(profile.into_iter().map(OsString::from));
+    expanded.extend(args);
+    Ok(expanded)
+}
+
+fn config_path(args: &[OsString]) -> Option<String> {
+    let mut args = args.iter().skip(1).map(|arg| arg.to_string_lossy());
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        } else if arg == "--config" {
+            return args.next().map(|path| path.into_owned());
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(path.to_string());
+        }
+    }
+    None
+}
+
+/// Translate a profile into flags. Keys are flag names without the leading dashes,
+/// in kebab- or snake-case; lists repeat the flag, and switches take true or false.
+fn load_profile(path: &str) -> anyhow::Result<Vec<String>> {
+    let invalid = |e: String| anyhow::anyhow!("Invalid config '{}': {}", path, e);
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read config '{}': {}", path, e))?;
+    let profile: serde_yaml::Mapping =
+        serde_yaml::from_str(&contents).map_err(|e| invalid(e.to_string()))?;
+
+    let command = Args::command();
+    let mut flags = Vec::new();
+    for (key, value) in &profile {
+        let key = key
+            .as_str()
+            .ok_or_else(|| invalid(format!("setting names must be strings, got {:?}", key)))?;
+        let name = key.replace('_', "-");
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(name.as_str()) && name != "config")
+            .ok_or_else(|| invalid(format!("unknown setting '{}'", key)))?;
+        let flag = format!("--{}", name);
+
+        if !arg.get_action().takes_values() {
+            match value {
+                serde_yaml::Value::Bool(true) => flags.push(flag),
+                serde_yaml::Value::Bool(false) => {}
+                _ => return Err(invalid(format!("'{}' must be true or false", key))),
+            }
+            continue;
+        }
+
+        let values = match value {
+            serde_yaml::Value::Null => Vec::new(),
+            serde_yaml::Value::Sequence(items) => items.iter().collect(),
+            value => vec![value],
+        };
+        for value in values {
+            let value = yaml_scalar(value)
+                .ok_or_else(|| invalid(format!("'{}' must be a value or a list of values", key)))?;
+            // `--flag=value`, so values starting with a dash are not read as flags
+            flags.push(format!("{}={}", flag, value));
+        }
+    }
+    Ok(flags)
+}
+
+fn yaml_scalar(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        _ => None,
+    }
+}
@@ -0,0 +1,198 @@
+use crate::recorder::LatencyHistogram;
+use crate::sampling::Sampler;
+use crate::LatencySummary;
+use deadpool_postgres::Pool;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::cell::Cell;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// "ryw", see [`crate::sampling`]
+const CONSISTENCY_SEED_SALT: u64 = 0x7279_7700_0000_0000;
+
+/// How long an insert may stay invisible before its check counts as timed out
+const VISIBILITY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pause between re-reads while waiting for an insert to show up
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+tokio::task_local! {
+    /// Set while a sampled request runs, so its insert gets re-read
+    static SAMPLED: Sampled;
+}
+
+struct Sampled {
+    checker: Arc<ReadYourWrites>,
+    /// Time spent re-reading, to be left out of the request's latency
+    spent: Cell<Duration>,
+}
+
+/// `--read-your-writes`: re-reads a share of the orders workload's inserts right
+/// after they commit, on the read replica when there is one, and measures how long
+/// the new order takes to become visible
+#[derive(Debug)]
+pub struct ReadYourWrites {
+    sampler: Sampler,
+    on_replica: bool,
+    stats: Mutex<ConsistencyStats>,
+}
+
+#[derive(Debug, Default)]
+struct ConsistencyStats {
+    checks: u64,
+    stale_reads: u64,
+    timeouts: u64,
+    failures: u64,
+    /// Milliseconds from the insert's commit until a read returned it
+    visibility: LatencyHistogram,
+}
+
+/// Read-your-writes checks made during a run
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ConsistencyReport {
+    /// Share of inserts re-read
+    pub sample_rate: f64,
+    /// Where the re-reads went: `primary`, or `replica` with --read-url
+    pub read_from: String,
+    /// Inserts re-read
+    pub checks: u64,
+    /// Checks whose first read did not return the insert
+    pub stale_reads: u64,
+    /// Inserts still not visible after 10 seconds
+    pub timeouts: u64,
+    /// Checks abandoned on a connection or query error
+    pub failures: u64,
+    /// From the insert's commit until a read returned it, connection checkout included
+    pub visibility: LatencySummary,
+}
+
+impl ReadYourWrites {
+    pub fn new(rate: f64, on_replica: bool) -> Self {
+        Self {
+            sampler: Sampler::new(rate, CONSISTENCY_SEED_SALT),
+            on_replica,
+            stats: Mutex::new(ConsistencyStats::default()),
+        }
+    }
+
+    /// Whether attempt `attempt` of the request with `seed` is checked
+    pub fn samples(&self, seed: u64, attempt: u32) -> bool {
+        self.sampler.samples(seed, attempt)
+    }
+
+    /// Read `order_id` on a connection of `pool` until it shows up, from `committed`
+    async fn check(&self, pool: &Pool, order_id: i32, committed: Instant) -> anyhow::Result<()> {
+        let client = pool.get().await?;
+        let mut stale = false;
+        loop {
+            let visible = client
+                .query_opt("SELECT 1 FROM orders WHERE order_id = $1", &[&order_id])
+                .await?
+                .is_some();
+            let elapsed = committed.elapsed();
+            if visible || elapsed >= VISIBILITY_TIMEOUT {
+                let mut stats = self.stats.lock().unwrap();
+                stats.checks += 1;
+                stats.stale_reads += u64::from(stale || !visible);
+                if visible {
+                    stats.visibility.record(elapsed);
+                } else {
+                    stats.timeouts += 1;
+                }
+                return Ok(());
+            }
+            stale = true;
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    pub fn report(&self) -> ConsistencyReport {
+        let stats = self.stats.lock().unwrap();
+        ConsistencyReport {
+            sample_rate: self.sampler.rate(),
+            read_from: if self.on_replica {
+                "replica"
+            } else {
+                "primary"
+            }
+            .to_string(),
+            checks: stats.checks,
+            stale_reads: stats.stale_reads,
+            timeouts: stats.timeouts,
+            failures: stats.failures,
+            visibility: stats.visibility.summary(),
+        }
+    }
+}
+
+/// Run `future`, one attempt of a request, with its insert re-read when `checker`
+/// is given. Returns its output and the time the re-reads took.
+pub async fn scope<F: Future>(
+    checker: Option<Arc<ReadYourWrites>>,
+    future: F,
+) -> (F::Output, Duration) {
+    let Some(checker) = checker else {
+        return (future.await, Duration::ZERO);
+    };
+    let sampled = Sampled {
+        checker,
+        spent: Cell::new(Duration::ZERO),
+    };
+    SAMPLED
+        .scope(sampled, async move {
+            let output = future.await;
+            (output, SAMPLED.with(|sampled| sampled.spent.get()))
+        })
+        .await
+}
+
+/// Re-read the order an insert committed at `committed` on `pool`, when the current
+/// request is sampled. The insert's own connection must already be released, so
+/// the read takes another one like an application's next request would. A failed
+/// check is counted but never fails the request.
+pub async fn check_if_sampled(pool: &Pool, order_id: i32, committed: Instant) {
+    let Ok(checker) = SAMPLED.try_with(|sampled| Arc::clone(&sampled.checker)) else {
+        return;
+    };
+    let start = Instant::now();
+    if checker.check(pool, order_id, committed).await.is_err() {
+        checker.stats.lock().unwrap().failures += 1;
+    }
+    let _ = SAMPLED.try_with(|sampled| sampled.spent.set(sampled.spent.get() + start.elapsed()));
+}
+
+pub fn display_read_your_writes(report: &ConsistencyReport) {
+    println!(
+        "\n🔁 Read-Your-Writes (re-read {:.2}% of inserts on the {}):",
+        report.sample_rate * 100.0,
+        report.read_from
+    );
+    println!("   Checks:                 {:>8}", report.checks);
+    println!(
+        "   Stale Reads:            {:>8} ({:.2}% of checks)",
+        report.stale_reads,
+        report.stale_reads as f64 / report.checks.max(1) as f64 * 100.0
+    );
+    if report.visibility.count > 0 {
+        println!(
+            "   Visibility (ms):        avg {:.2}  p50 {:.2}  p95 {:.2}  p99 {:.2}  max {:.2}",
+            report.visibility.average_ms,
+            report.visibility.p50_ms,
+            report.visibility.p95_ms,
+            report.visibility.p99_ms,
+            report.visibility.max_ms
+        );
+    }
+    if report.timeouts > 0 {
+        println!(
+            "   ⚠️  Never Visible:        {:>8} (not visible within {}s)",
+            report.timeouts,
+            VISIBILITY_TIMEOUT.as_secs()
+        );
+    }
+    if report.failures > 0 {
+        println!("   Failed Checks:          {:>8}", report.failures);
+    }
+}
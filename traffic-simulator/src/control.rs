@@ -0,0 +1,285 @@
+use crate::events::{self, Event};
+use crate::tui::{Dashboard, LiveMetrics};
+use crate::{QueryContext, QueryType};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// Largest request body accepted; every endpoint takes a small JSON object at most
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// `--control-port`: settings a presenter can change while the run goes on, and
+/// the live numbers they are judged by
+#[derive(Debug)]
+pub struct Control {
+    paused: watch::Sender<bool>,
+    settings: Mutex<Settings>,
+    /// Rolling QPS, latency and errors, kept like the --tui dashboard's
+    live: Dashboard,
+}
+
+#[derive(Debug, Default)]
+struct Settings {
+    target_qps: Option<f64>,
+    query_type: Option<QueryType>,
+    /// Earliest start of the next query while a target QPS is set
+    next_slot: Option<Instant>,
+}
+
+/// Response of every endpoint: the settings in effect and the run's progress
+#[derive(Debug, Serialize)]
+struct ControlStatus {
+    paused: bool,
+    /// Set through the API; `null` while the run's own rate applies
+    target_qps: Option<f64>,
+    /// Set through the API; `null` while --query-type applies
+    query_type: Option<QueryType>,
+    /// Queries queued or running
+    pending: usize,
+    #[serde(flatten)]
+    metrics: LiveMetrics,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TargetQpsRequest {
+    qps: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QueryTypeRequest {
+    query_type: Option<QueryType>,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+impl Control {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            paused: watch::Sender::new(false),
+            settings: Mutex::new(Settings::default()),
+            live: Dashboard::new(duration),
+        }
+    }
+
+    pub fn record(&self, latency: Duration, success: bool) {
+        self.live.record(latency, success);
+    }
+
+    pub fn set_phase(&self, phase: impl Into<String>, notes: &[String]) {
+        self.live.set_phase(phase, notes);
+    }
+
+    /// Target QPS set through the API, replacing --target-qps in an open-loop run and
+    /// capping the rate of any other
+    pub fn target_qps(&self) -> Option<f64> {
+        self.settings.lock().unwrap().target_qps
+    }
+
+    /// Query type set through the API, replacing --query-type
+    pub fn query_type(&self) -> Option<QueryType> {
+        self.settings.lock().unwrap().query_type.clone()
+    }
+
+    /// Wait until the run is not paused; returns whether it was
+    pub async fn wait_while_paused(&self) -> bool {
+        let mut paused = self.paused.subscribe();
+        if !*paused.borrow_and_update() {
+            return false;
+        }
+        // The sender lives as long as `self`, so waiting cannot fail
+        let _ = paused.wait_for(|paused| !*paused).await;
+        true
+    }
+
+    /// Hold a query while the run is paused, then space it from the previous one by
+    /// the API's target QPS, if any. Returns whether the query was held, so its
+    /// latency can be measured from its release rather than from its arrival.
+    pub async fn admit(&self) -> bool {
+        let mut held = self.wait_while_paused().await;
+        let slot = {
+            let mut settings = self.settings.lock().unwrap();
+            settings.target_qps.map(|qps| {
+                let now = Instant::now();
+                let slot = settings.next_slot.map_or(now, |next| next.max(now));
+                settings.next_slot = Some(slot + Duration::from_secs_f64(1.0 / qps));
+                slot
+            })
+        };
+        if let Some(slot) = slot.filter(|slot| *slot > Instant::now()) {
+            tokio::time::sleep_until(slot.into()).await;
+            held = true;
+        }
+        held
+    }
+
+    fn status(&self, ctx: &QueryContext) -> ControlStatus {
+        let settings = self.settings.lock().unwrap();
+        ControlStatus {
+            paused: *self.paused.borrow(),
+            target_qps: settings.target_qps,
+            query_type: settings.query_type.clone(),
+            pending: ctx.pending_work.current(),
+            metrics: self.live.live_metrics(),
+        }
+    }
+}
+
+/// Bind the control server's address, failing the run up front if it is taken
+pub async fn bind(address: &str, port: u16) -> anyhow::Result<TcpListener> {
+    TcpListener::bind((address, port))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to listen on {}:{}: {}", address, port, e))
+}
+
+/// Serve the control API until the run ends and the task is aborted
+pub async fn run_control_server(listener: TcpListener, ctx: Arc<QueryContext>) {
+    loop {
+        let (stream, address) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("Control server failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        let ctx = Arc::clone(&ctx);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &ctx).await {
+                warn!("Control request from {} failed: {}", address, e);
+            }
+        });
+    }
+}
+
+/// Answer one HTTP/1.1 request and close the connection
+async fn handle_connection(stream: TcpStream, ctx: &QueryContext) -> anyhow::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(anyhow::anyhow!("malformed request line"));
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 {
+            break;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()?;
+            }
+        }
+    }
+
+    let (status, body) = if content_length > MAX_BODY_BYTES {
+        error(413, "Payload Too Large", "request body too large")
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).await?;
+        route(ctx, &method, &path, &body)
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    writer.write_all(response.as_bytes()).await?;
+    writer.shutdown().await?;
+    Ok(())
+}
+
+/// Status line and JSON body for a request
+fn route(ctx: &QueryContext, method: &str, path: &str, body: &[u8]) -> (String, String) {
+    let Some(control) = &ctx.control else {
+        return error(
+            503,
+            "Service Unavailable",
+            "the run has no control settings",
+        );
+    };
+    match (method, path) {
+        ("GET", "/status") => {}
+        ("POST", "/pause") => {
+            if !control.paused.send_replace(true) {
+                info!("⏸️  Paused through the control API");
+            }
+        }
+        ("POST", "/resume") => {
+            if control.paused.send_replace(false) {
+                info!("▶️  Resumed through the control API");
+            }
+        }
+        ("PUT", "/target-qps") => match serde_json::from_slice::<TargetQpsRequest>(body) {
+            Ok(TargetQpsRequest { qps: Some(qps) }) if !qps.is_finite() || qps <= 0.0 => {
+                return error(
+                    400,
+                    "Bad Request",
+                    "qps must be positive, or null to clear it",
+                )
+            }
+            Ok(TargetQpsRequest { qps }) => {
+                let mut settings = control.settings.lock().unwrap();
+                settings.target_qps = qps;
+                settings.next_slot = None;
+                match qps {
+                    Some(qps) => info!("🎚️  Target QPS set to {} through the control API", qps),
+                    None => info!("🎚️  Target QPS cleared through the control API"),
+                }
+                events::emit(ctx.events.as_deref(), || Event::QpsAdjusted {
+                    qps,
+                    connections: None,
+                    source: "control",
+                });
+            }
+            Err(e) => return error(400, "Bad Request", &e.to_string()),
+        },
+        ("PUT", "/query-type") => match serde_json::from_slice::<QueryTypeRequest>(body) {
+            Ok(QueryTypeRequest { query_type }) => {
+                match &query_type {
+                    Some(query_type) => {
+                        info!(
+                            "🔀 Query type set to {:?} through the control API",
+                            query_type
+                        )
+                    }
+                    None => info!("🔀 Query type reset through the control API"),
+                }
+                control.settings.lock().unwrap().query_type = query_type;
+            }
+            Err(e) => return error(400, "Bad Request", &e.to_string()),
+        },
+        (_, "/status" | "/pause" | "/resume" | "/target-qps" | "/query-type") => {
+            return error(405, "Method Not Allowed", "method not allowed")
+        }
+        _ => return error(404, "Not Found", "unknown endpoint"),
+    }
+    let status = serde_json::to_string(&control.status(ctx)).unwrap_or_default();
+    ("200 OK".to_string(), status)
+}
+
+fn error(code: u16, reason: &str, message: &str) -> (String, String) {
+    let body = serde_json::to_string(&ErrorResponse {
+        error: message.to_string(),
+    })
+    .unwrap_or_default();
+    (format!("{} {}", code, reason), body)
+}
@@ -0,0 +1,278 @@
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Connect, Manager, ManagerConfig};
+use futures::future::BoxFuture;
+use postgres_native_tls::MakeTlsConnector;
+use serde::Deserialize;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Fetch a new token this long before the current one expires
+const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Shortest pause between refreshes, including retries after a failed one
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Where short-lived database passwords come from
+#[derive(Debug, Clone, PartialEq)]
+pub enum CredentialSource {
+    /// `databricks auth token` of the Databricks CLI, optionally for a named profile
+    DatabricksCli { profile: Option<String> },
+    /// OAuth machine-to-machine login of a service principal, from DATABRICKS_HOST,
+    /// DATABRICKS_CLIENT_ID and DATABRICKS_CLIENT_SECRET
+    DatabricksOAuth,
+}
+
+/// Supplies the password of every new database connection and keeps it fresh, so
+/// pools can open connections after the first token has expired. Connections that
+/// are already open stay authenticated.
+pub struct CredentialProvider {
+    source: CredentialSource,
+    client: reqwest::Client,
+    token: RwLock<Option<Token>>,
+}
+
+struct Token {
+    access_token: String,
+    /// `None` when the source did not say how long the token is valid
+    expires_at: Option<Instant>,
+}
+
+/// Output of `databricks auth token`
+#[derive(Debug, Deserialize)]
+struct CliToken {
+    access_token: String,
+    expiry: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OAuthToken {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+/// Parse `databricks-cli`, `databricks-cli:PROFILE` or `databricks-oauth`
+pub fn parse_credentials(value: &str) -> Result<Arc<CredentialProvider>, String> {
+    let source = match value.split_once(':') {
+        None if value == "databricks-cli" => CredentialSource::DatabricksCli { profile: None },
+        Some(("databricks-cli", profile)) if !profile.is_empty() => {
+            CredentialSource::DatabricksCli {
+                profile: Some(profile.to_string()),
+            }
+        }
+        None if value == "databricks-oauth" => CredentialSource::DatabricksOAuth,
+        _ => {
+            return Err(format!(
+            "unknown credential source '{}', expected databricks-cli[:PROFILE] or databricks-oauth",
+            value
+        ))
+        }
+    };
+    Ok(Arc::new(CredentialProvider {
+        source,
+        client: reqwest::Client::new(),
+        token: RwLock::new(None),
+    }))
+}
+
+impl fmt::Debug for CredentialProvider {
+    // The token itself is never printed
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CredentialProvider")
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CredentialProvider {
+    pub fn describe(&self) -> String {
+        match &self.source {
+            CredentialSource::DatabricksCli { profile: None } => "Databricks CLI".to_string(),
+            CredentialSource::DatabricksCli {
+                profile: Some(profile),
+            } => format!("Databricks CLI profile '{}'", profile),
+            CredentialSource::DatabricksOAuth => "Databricks OAuth service principal".to_string(),
+        }
+    }
+
+    /// Fetch a new token and use it for connections opened from now on
+    pub async fn refresh(&self) -> anyhow::Result<()> {
+        let token = match &self.source {
+            CredentialSource::DatabricksCli { profile } => {
+                fetch_cli_token(profile.as_deref()).await
+            }
+            CredentialSource::DatabricksOAuth => self.fetch_oauth_token().await,
+        }
+        .with_context(|| {
+            format!(
+                "Failed to get a database token from the {}",
+                self.describe()
+            )
+        })?;
+        *self.token.write().unwrap() = Some(token);
+        Ok(())
+    }
+
+    /// Password for a connection opened now
+    fn password(&self) -> String {
+        self.token
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|token| token.access_token.clone())
+            .unwrap_or_default()
+    }
+
+    /// How long the current token is still valid, if known
+    fn remaining(&self) -> Option<Duration> {
+        let token = self.token.read().unwrap();
+        let expires_at = token.as_ref()?.expires_at?;
+        Some(expires_at.saturating_duration_since(Instant::now()))
+    }
+
+    async fn fetch_oauth_token(&self) -> anyhow::Result<Token> {
+        let env = |name: &str| {
+            std::env::var(name).map_err(|_| anyhow::anyhow!("databricks-oauth needs {}", name))
+        };
+        let host = env("DATABRICKS_HOST")?;
+        let host = host.trim_end_matches('/');
+        let host = if host.contains("://") {
+            host.to_string()
+        } else {
+            format!("https://{}", host)
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/oidc/v1/token", host))
+            .basic_auth(
+                env("DATABRICKS_CLIENT_ID")?,
+                Some(env("DATABRICKS_CLIENT_SECRET")?),
+            )
+            .form(&[("grant_type", "client_credentials"), ("scope", "all-apis")])
+            .send()
+            .await?
+            .error_for_status()?;
+        let token: OAuthToken = response.json().await?;
+        Ok(Token {
+            access_token: token.access_token,
+            expires_at: token
+                .expires_in
+                .map(|seconds| Instant::now() + Duration::from_secs(seconds)),
+        })
+    }
+}
+
+async fn fetch_cli_token(profile: Option<&str>) -> anyhow::Result<Token> {
+    let mut command = Command::new("databricks");
+    command.args(["auth", "token"]);
+    if let Some(profile) = profile {
+        command.args(["--profile", profile]);
+    }
+    let output = command
+        .output()
+        .await
+        .context("could not run the databricks CLI")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "databricks auth token failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let token: CliToken = serde_json::from_slice(&output.stdout)?;
+    let expires_at = token
+        .expiry
+        .as_deref()
+        .and_then(|expiry| DateTime::parse_from_rfc3339(expiry).ok())
+        .map(|expiry| {
+            let remaining = (expiry.with_timezone(&Utc) - Utc::now())
+                .to_std()
+                .unwrap_or_default();
+            Instant::now() + remaining
+        });
+    Ok(Token {
+        access_token: token.access_token,
+        expires_at,
+    })
+}
+
+/// Replace the token shortly before it expires, for as long as the run lasts. A
+/// failed refresh is retried; connections keep using the last token meanwhile.
+pub async fn run_refresher(provider: Arc<CredentialProvider>) {
+    loop {
+        // Tokens without a known lifetime are assumed to last the usual hour
+        let remaining = provider.remaining().unwrap_or(Duration::from_secs(60 * 60));
+        let delay = remaining
+            .saturating_sub(REFRESH_MARGIN)
+            .max(remaining / 2)
+            .max(MIN_REFRESH_INTERVAL);
+        tokio::time::sleep(delay).await;
+
+        loop {
+            match provider.refresh().await {
+                Ok(()) => {
+                    info!("🔑 Refreshed database credentials");
+                    break;
+                }
+                Err(e) => {
+                    warn!("{:#}; retrying in {:?}", e, MIN_REFRESH_INTERVAL);
+                    tokio::time::sleep(MIN_REFRESH_INTERVAL).await;
+                }
+            }
+        }
+    }
+}
+
+/// Opens connections with the provider's current password instead of the one in
+/// the connection string
+struct RefreshingConnect {
+    tls: MakeTlsConnector,
+    provider: Arc<CredentialProvider>,
+}
+
+impl Connect for RefreshingConnect {
+    fn connect(
+        &self,
+        pg_config: &tokio_postgres::Config,
+    ) -> BoxFuture<'_, Result<(tokio_postgres::Client, JoinHandle<()>), tokio_postgres::Error>>
+    {
+        let mut pg_config = pg_config.clone();
+        pg_config.password(self.provider.password());
+        let tls = self.tls.clone();
+        Box::pin(async move {
+            let (client, connection) = pg_config.connect(tls).await?;
+            let connection = tokio::spawn(async move {
+                if let Err(e) = connection.await {
+                    warn!("Connection error: {}", e);
+                }
+            });
+            Ok((client, connection))
+        })
+    }
+}
+
+/// Pool manager for `pg_config`; with `credentials`, every new connection
+/// authenticates with the provider's current token
+pub fn manager(
+    pg_config: tokio_postgres::Config,
+    tls: MakeTlsConnector,
+    config: ManagerConfig,
+    credentials: Option<&Arc<CredentialProvider>>,
+) -> Manager {
+    match credentials {
+        Some(provider) => Manager::from_connect(
+            pg_config,
+            RefreshingConnect {
+                tls,
+                provider: Arc::clone(provider),
+            },
+            config,
+        ),
+        None => Manager::from_config(pg_config, tls, config),
+    }
+}
@@ -0,0 +1,384 @@
+use crate::export::RunFile;
+use crate::timeline::TimelinePoint;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Longest a single statement may run, including waiting for the warehouse to start
+const STATEMENT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Pause between status checks of a statement that is still running
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Column types of the per-second timeline, matching `TimelinePoint`
+const TIMELINE_SCHEMA: &str = "ARRAY<STRUCT<second: BIGINT, queries: BIGINT, failures: BIGINT, \
+     qps: DOUBLE, error_rate: DOUBLE, p50_ms: DOUBLE, p95_ms: DOUBLE, p99_ms: DOUBLE>>";
+
+/// Parse a Unity Catalog table name, `catalog.schema.table`
+pub fn parse_table_name(value: &str) -> Result<String, String> {
+    let parts: Vec<&str> = value.split('.').collect();
+    let valid = parts.len() == 3
+        && parts.iter().all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        });
+    if !valid {
+        return Err(format!(
+            "invalid table '{}', expected catalog.schema.table",
+            value
+        ));
+    }
+    Ok(value.to_string())
+}
+
+/// Appends finished runs to a Unity Catalog table, and their per-second timeline to
+/// a `<table>_timeline` table next to it, through the Databricks SQL Statement
+/// Execution API
+#[derive(Debug)]
+pub struct DatabricksPublisher {
+    statements: StatementClient,
+    runs_table: String,
+    timeline_table: String,
+}
+
+/// Runs statements on a SQL warehouse through the Statement Execution API
+#[derive(Debug)]
+pub struct StatementClient {
+    client: reqwest::Client,
+    statements_url: String,
+    token: String,
+    warehouse_id: String,
+    /// Default catalog and schema of unqualified table names
+    catalog: Option<String>,
+    schema: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatementRequest<'a> {
+    warehouse_id: &'a str,
+    statement: &'a str,
+    parameters: &'a [Parameter],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    catalog: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema: Option<&'a str>,
+    wait_timeout: &'static str,
+    on_wait_timeout: &'static str,
+}
+
+/// A named `:parameter` of a statement; values are always sent as strings
+#[derive(Debug, Serialize)]
+pub struct Parameter {
+    name: &'static str,
+    value: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatementResponse {
+    statement_id: String,
+    status: StatementStatus,
+    manifest: Option<ResultManifest>,
+    result: Option<ResultData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultManifest {
+    total_row_count: Option<u64>,
+}
+
+/// First chunk of an inline JSON result; values arrive as strings
+#[derive(Debug, Deserialize)]
+struct ResultData {
+    data_array: Option<Vec<Vec<Option<String>>>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatementStatus {
+    state: String,
+    error: Option<StatementError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatementError {
+    message: Option<String>,
+}
+
+impl Parameter {
+    pub fn string(name: &'static str, value: impl Into<String>) -> Self {
+        Self {
+            name,
+            value: value.into(),
+            kind: "STRING",
+        }
+    }
+
+    pub fn typed(name: &'static str, value: impl ToString, kind: &'static str) -> Self {
+        Self {
+            name,
+            value: value.to_string(),
+            kind,
+        }
+    }
+}
+
+impl DatabricksPublisher {
+    /// `host` falls back to the `DATABRICKS_HOST` environment variable; the access
+    /// token is read from `DATABRICKS_TOKEN`
+    pub fn new(table: &str, host: Option<&str>, warehouse_id: &str) -> anyhow::Result<Self> {
+        let host = match host {
+            Some(host) => host.to_string(),
+            None => std::env::var("DATABRICKS_HOST").map_err(|_| {
+                anyhow::anyhow!("--publish-databricks needs --databricks-host or DATABRICKS_HOST")
+            })?,
+        };
+        let token = std::env::var("DATABRICKS_TOKEN")
+            .map_err(|_| anyhow::anyhow!("--publish-databricks needs DATABRICKS_TOKEN"))?;
+
+        Ok(Self {
+            statements: StatementClient::new(&host, token, warehouse_id, None, None),
+            runs_table: quote_table(table, ""),
+            timeline_table: quote_table(table, "_timeline"),
+        })
+    }
+
+    /// The table runs are appended to, for log lines
+    pub fn table(&self) -> &str {
+        &self.runs_table
+    }
+
+    /// Create the tables if needed and append the run and its timeline under the
+    /// run's id, which is returned
+    pub async fn publish(
+        &self,
+        run: &RunFile<'_>,
+        timeline: &[TimelinePoint],
+    ) -> anyhow::Result<String> {
+        let run_id = run.run_id.clone();
+        let result = run.result;
+
+        self.statements
+            .execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {} (
+                    run_id STRING, started_at TIMESTAMP, labels MAP<STRING, STRING>,
+                    database_url STRING, workload STRING, query_type STRING, connections INT,
+                    duration_seconds DOUBLE, total_queries BIGINT, successful_queries BIGINT,
+                    failed_queries BIGINT, queries_per_second DOUBLE, average_latency_ms DOUBLE,
+                    p50_latency_ms DOUBLE, p95_latency_ms DOUBLE, p99_latency_ms DOUBLE,
+                    p999_latency_ms DOUBLE, aborted BOOLEAN, run STRING
+                ) COMMENT 'Runs published by the Postgres traffic simulator'",
+                    self.runs_table
+                ),
+                &[],
+            )
+            .await?;
+        self.statements
+            .execute(
+                &format!(
+                    "INSERT INTO {} VALUES (
+                    :run_id, :started_at, from_json(:labels, 'MAP<STRING, STRING>'),
+                    :database_url, :workload, :query_type, :connections,
+                    :duration_seconds, :total_queries, :successful_queries,
+                    :failed_queries, :queries_per_second, :average_latency_ms,
+                    :p50_latency_ms, :p95_latency_ms, :p99_latency_ms,
+                    :p999_latency_ms, :aborted, :run
+                )",
+                    self.runs_table
+                ),
+                &[
+                    Parameter::string("run_id", &run_id),
+                    Parameter::typed("started_at", run.started_at.to_rfc3339(), "TIMESTAMP"),
+                    Parameter::string("labels", serde_json::to_string(&run.labels)?),
+                    Parameter::string("database_url", &run.config.database_url),
+                    Parameter::string("workload", &run.config.workload),
+                    Parameter::string("query_type", &run.config.query_type),
+                    Parameter::typed("connections", run.config.connections, "INT"),
+                    Parameter::typed("duration_seconds", result.duration_seconds, "DOUBLE"),
+                    Parameter::typed("total_queries", result.total_queries, "BIGINT"),
+                    Parameter::typed("successful_queries", result.successful_queries, "BIGINT"),
+                    Parameter::typed("failed_queries", result.failed_queries, "BIGINT"),
+                    Parameter::typed("queries_per_second", result.queries_per_second, "DOUBLE"),
+                    Parameter::typed("average_latency_ms", result.average_latency_ms, "DOUBLE"),
+                    Parameter::typed("p50_latency_ms", result.p50_latency_ms, "DOUBLE"),
+                    Parameter::typed("p95_latency_ms", result.p95_latency_ms, "DOUBLE"),
+                    Parameter::typed("p99_latency_ms", result.p99_latency_ms, "DOUBLE"),
+                    Parameter::typed("p999_latency_ms", result.p999_latency_ms, "DOUBLE"),
+                    Parameter::typed("aborted", result.aborted.is_some(), "BOOLEAN"),
+                    Parameter::string("run", serde_json::to_string(run)?),
+                ],
+            )
+            .await?;
+
+        if !timeline.is_empty() {
+            self.statements.execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {} (
+                        run_id STRING, second BIGINT, queries BIGINT, failures BIGINT,
+                        qps DOUBLE, error_rate DOUBLE, p50_ms DOUBLE, p95_ms DOUBLE, p99_ms DOUBLE
+                    ) COMMENT 'Per-second timeline of runs published by the Postgres traffic simulator'",
+                    self.timeline_table
+                ),
+                &[],
+            )
+            .await?;
+            // The whole timeline goes in one parameter and is exploded server-side
+            self.statements
+                .execute(
+                    &format!(
+                        "INSERT INTO {} SELECT :run_id, p.second, p.queries, p.failures, p.qps,
+                        p.error_rate, p.p50_ms, p.p95_ms, p.p99_ms
+                     FROM (SELECT explode(from_json(:points, '{}')) AS p)",
+                        self.timeline_table, TIMELINE_SCHEMA
+                    ),
+                    &[
+                        Parameter::string("run_id", &run_id),
+                        Parameter::string("points", serde_json::to_string(timeline)?),
+                    ],
+                )
+                .await?;
+        }
+
+        Ok(run_id)
+    }
+}
+
+impl StatementClient {
+    /// `host` with or without `https://`
+    pub fn new(
+        host: &str,
+        token: String,
+        warehouse_id: &str,
+        catalog: Option<String>,
+        schema: Option<String>,
+    ) -> Self {
+        let host = host.trim_end_matches('/');
+        let host = if host.contains("://") {
+            host.to_string()
+        } else {
+            format!("https://{}", host)
+        };
+
+        Self {
+            client: reqwest::Client::new(),
+            statements_url: format!("{}/api/2.0/sql/statements", host),
+            token,
+            warehouse_id: warehouse_id.to_string(),
+            catalog,
+            schema,
+        }
+    }
+
+    /// Run one statement, polling until it finishes when the warehouse takes longer
+    /// than the API's synchronous wait
+    pub async fn execute(
+        &self,
+        statement: &str,
+        parameters: &[Parameter],
+    ) -> anyhow::Result<StatementResponse> {
+        let started = Instant::now();
+        let request = StatementRequest {
+            warehouse_id: &self.warehouse_id,
+            statement,
+            parameters,
+            catalog: self.catalog.as_deref(),
+            schema: self.schema.as_deref(),
+            wait_timeout: "30s",
+            on_wait_timeout: "CONTINUE",
+        };
+        let mut response = self
+            .send(
+                self.client
+                    .post(&self.statements_url)
+                    .json(&request)
+                    .timeout(Duration::from_secs(60)),
+            )
+            .await?;
+
+        loop {
+            match response.status.state.as_str() {
+                "SUCCEEDED" => return Ok(response),
+                "PENDING" | "RUNNING" if started.elapsed() < STATEMENT_TIMEOUT => {
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                    response = self
+                        .send(
+                            self.client
+                                .get(format!("{}/{}", self.statements_url, response.statement_id))
+                                .timeout(Duration::from_secs(30)),
+                        )
+                        .await?;
+                }
+                "PENDING" | "RUNNING" => {
+                    let _ = self
+                        .client
+                        .post(format!(
+                            "{}/{}/cancel",
+                            self.statements_url, response.statement_id
+                        ))
+                        .bearer_auth(&self.token)
+                        .send()
+                        .await;
+                    return Err(anyhow::anyhow!(
+                        "Databricks statement did not finish within {}s",
+                        STATEMENT_TIMEOUT.as_secs()
+                    ));
+                }
+                state => {
+                    let message = response
+                        .status
+                        .error
+                        .and_then(|error| error.message)
+                        .unwrap_or_default();
+                    return Err(anyhow::anyhow!(
+                        "Databricks statement {}: {}",
+                        state.to_lowercase(),
+                        message
+                    ));
+                }
+            }
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> anyhow::Result<StatementResponse> {
+        let response = request.bearer_auth(&self.token).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Databricks returned {}: {}",
+                status,
+                body.trim()
+            ));
+        }
+        Ok(response.json().await?)
+    }
+}
+
+impl StatementResponse {
+    /// Rows the statement returned
+    pub fn row_count(&self) -> u64 {
+        self.manifest
+            .as_ref()
+            .and_then(|manifest| manifest.total_row_count)
+            .unwrap_or_default()
+    }
+
+    /// First column of the first row, e.g. `num_affected_rows` of an UPDATE
+    pub fn first_value(&self) -> Option<&str> {
+        self.result
+            .as_ref()?
+            .data_array
+            .as_ref()?
+            .first()?
+            .first()?
+            .as_deref()
+    }
+}
+
+/// Backtick-quote each part of a validated `catalog.schema.table`, appending
+/// `suffix` to the table
+fn quote_table(table: &str, suffix: &str) -> String {
+    format!("`{}{}`", table.replace('.', "`.`"), suffix)
+}
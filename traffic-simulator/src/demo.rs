@@ -0,0 +1,68 @@
+use crate::TrafficPhase;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Scripted narrative for presenting the simulator live: each phase carries a title
+/// and talking points that are shown as the phase begins
+#[derive(Debug, Deserialize)]
+pub struct DemoStory {
+    pub title: String,
+    #[serde(default)]
+    pub introduction: Vec<String>,
+    pub phases: Vec<DemoPhase>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DemoPhase {
+    pub title: String,
+    #[serde(default)]
+    pub talking_points: Vec<String>,
+    #[serde(flatten)]
+    pub traffic: TrafficPhase,
+}
+
+const BANNER_WIDTH: usize = 64;
+
+impl DemoStory {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let story: DemoStory = serde_yaml::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Invalid demo story '{}': {}", path, e))?;
+
+        crate::pattern::validate_phases(story.phases.iter().map(|p| &p.traffic))
+            .map_err(|e| anyhow::anyhow!("Invalid demo story '{}': {}", path, e))?;
+
+        Ok(story)
+    }
+
+    pub fn announce_start(&self) {
+        println!("\n{}", "═".repeat(BANNER_WIDTH));
+        println!("  🎬 {}", self.title);
+        println!("{}", "═".repeat(BANNER_WIDTH));
+        for line in &self.introduction {
+            println!("  {}", line);
+        }
+        println!();
+    }
+
+    pub fn announce_phase(&self, index: usize, duration: Duration) {
+        let phase = &self.phases[index];
+        println!("\n╔{}╗", "═".repeat(BANNER_WIDTH - 2));
+        println!(
+            "  ▶ Phase {}/{}: {}",
+            index + 1,
+            self.phases.len(),
+            phase.title
+        );
+        println!(
+            "    {:?} intensity · {:.0}s",
+            phase.traffic.intensity,
+            duration.as_secs_f64()
+        );
+        println!("╚{}╝", "═".repeat(BANNER_WIDTH - 2));
+        for point in &phase.talking_points {
+            println!("   • {}", point);
+        }
+        println!();
+    }
+}
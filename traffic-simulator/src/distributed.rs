@@ -0,0 +1,293 @@
+use crate::baseline::Baseline;
+use crate::databricks::DatabricksPublisher;
+use crate::shard::{self, ShardReport, SHARD_STARTUP_GRACE};
+use crate::{config, Args};
+use chrono::Utc;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::Command;
+use tracing::{info, warn};
+
+/// Coordinator and workers must agree on the report layout they exchange
+const PROTOCOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Flags that configure a process's role in a distributed run; the shard
+/// processes that generate the load must not inherit them
+const ROLE_FLAGS: &[&str] = &["--config", "--listen", "--workers", "--join"];
+const ROLE_SWITCHES: &[&str] = &["--coordinator", "--worker"];
+
+/// Flags that may carry credentials; they stay on the coordinator, and every worker
+/// brings its own endpoint
+const CREDENTIAL_FLAGS: &[&str] = &["--database-url", "-d", "--read-url"];
+
+/// Environment variable holding the secret a worker must present to join
+const JOIN_TOKEN_VAR: &str = "JOIN_TOKEN";
+
+/// One line of JSON on the coordinator/worker connection
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Message {
+    /// Worker → coordinator, on connecting
+    Join { version: String, token: String },
+    /// Coordinator → worker: the run's flags, the worker's shard of it, and how
+    /// long from receipt until the common start line
+    Assign {
+        args: Vec<String>,
+        shard: String,
+        start_in_ms: u64,
+    },
+    /// Worker → coordinator, once its shard finished
+    Report { report: Box<ShardReport> },
+    /// Worker → coordinator, when its shard could not run to completion
+    Failed { error: String },
+}
+
+struct WorkerConnection {
+    address: SocketAddr,
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+/// Wait for `--workers` simulators to join, start them on a common start line with
+/// an equal share of connections, queries and target QPS, then merge their reports
+pub async fn run_coordinator(
+    args: &Args,
+    baseline: Option<&Baseline>,
+    publisher: Option<&DatabricksPublisher>,
+) -> anyhow::Result<()> {
+    let token = join_token()?;
+    let count = usize::from(args.workers.unwrap_or(1));
+    if args.connections < count {
+        return Err(anyhow::anyhow!(
+            "--workers {} needs at least as many --connections",
+            count
+        ));
+    }
+
+    let listener = TcpListener::bind(&args.listen)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", args.listen, e))?;
+    info!(
+        "🛰️  Waiting for {} worker(s) to join on {}",
+        count, args.listen
+    );
+
+    let mut workers = Vec::with_capacity(count);
+    while workers.len() < count {
+        let (stream, address) = listener.accept().await?;
+        let (reader, writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        match receive(&mut lines).await {
+            Ok(Message::Join { token: offered, .. }) if !tokens_match(&offered, &token) => {
+                warn!("Rejected {}: wrong join token", address)
+            }
+            Ok(Message::Join { version, .. }) if version == PROTOCOL_VERSION => {
+                workers.push(WorkerConnection {
+                    address,
+                    lines,
+                    writer,
+                });
+                info!("Worker {} joined ({}/{})", address, workers.len(), count);
+            }
+            Ok(Message::Join { version, .. }) => warn!(
+                "Rejected worker {} running version {} (coordinator runs {})",
+                address, version, PROTOCOL_VERSION
+            ),
+            Ok(_) => warn!("Rejected {}: expected a join message", address),
+            Err(e) => warn!("Rejected {}: {}", address, e),
+        }
+    }
+
+    // Workers receive the profile's flags rather than a path to a file they may not
+    // have, and never the coordinator's connection strings
+    let expanded = config::expand_args(std::env::args_os().collect())?;
+    let mut forwarded = shard::strip_flags(
+        expanded
+            .into_iter()
+            .skip(1)
+            .map(|arg| arg.to_string_lossy().into_owned()),
+        &[ROLE_FLAGS, CREDENTIAL_FLAGS, &["--run-id"]].concat(),
+        ROLE_SWITCHES,
+    );
+    forwarded.retain(|arg| !is_database_url_flag(arg));
+    // Workers tag their writes with the coordinator's run id, generated or not
+    forwarded.extend(["--run-id".to_string(), args.run_id().to_string()]);
+    // Each worker counts the delay from its own receipt of the assignment, so hosts
+    // start together even when their clocks disagree
+    let start_in = args
+        .start_at
+        .map(|start_at| start_at - Utc::now())
+        .filter(|delay| *delay > chrono::Duration::zero())
+        .unwrap_or(SHARD_STARTUP_GRACE);
+    let start_at = Utc::now() + start_in;
+
+    for (index, worker) in workers.iter_mut().enumerate() {
+        let assignment = Message::Assign {
+            args: forwarded.clone(),
+            shard: format!("{}/{}", index, count),
+            start_in_ms: start_in.num_milliseconds() as u64,
+        };
+        send(&mut worker.writer, &assignment).await?;
+    }
+    info!(
+        "🧩 Distributed run across {} workers ({} connections total), starting at {}",
+        count,
+        args.connections,
+        start_at.to_rfc3339()
+    );
+
+    let reports = join_all(workers.into_iter().map(|mut worker| async move {
+        let report = match receive(&mut worker.lines).await {
+            Ok(Message::Report { report }) => Ok(*report),
+            Ok(Message::Failed { error }) => Err(anyhow::anyhow!(error)),
+            Ok(_) => Err(anyhow::anyhow!("expected a report")),
+            Err(e) => Err(e),
+        };
+        report.map_err(|e| anyhow::anyhow!("Worker {} failed: {}", worker.address, e))
+    }))
+    .await;
+    let reports = reports.into_iter().collect::<anyhow::Result<Vec<_>>>()?;
+    info!("All {} workers reported", count);
+
+    shard::report_merged(&reports, args, start_at, baseline, publisher).await
+}
+
+/// Join the coordinator at `coordinator`, run the shard it assigns in a child
+/// process and send the shard's report back. Flags given to the worker itself
+/// override the coordinator's, and must include its --database-url.
+pub async fn run_worker(coordinator: &str) -> anyhow::Result<()> {
+    let token = join_token()?;
+    let expanded = config::expand_args(std::env::args_os().collect())?;
+    let own_args = shard::strip_flags(
+        expanded
+            .into_iter()
+            .skip(1)
+            .map(|arg| arg.to_string_lossy().into_owned()),
+        ROLE_FLAGS,
+        ROLE_SWITCHES,
+    );
+    if !own_args.iter().any(|arg| is_database_url_flag(arg)) {
+        return Err(anyhow::anyhow!(
+            "--worker needs its own --database-url; the coordinator does not share its connection strings"
+        ));
+    }
+
+    let stream = TcpStream::connect(coordinator)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to join coordinator at {}: {}", coordinator, e))?;
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let join = Message::Join {
+        version: PROTOCOL_VERSION.to_string(),
+        token,
+    };
+    send(&mut writer, &join).await?;
+    info!(
+        "🛰️  Joined coordinator at {}, waiting for the run to start",
+        coordinator
+    );
+
+    let Message::Assign {
+        args,
+        shard,
+        start_in_ms,
+    } = receive(&mut lines).await?
+    else {
+        return Err(anyhow::anyhow!("Coordinator sent no assignment"));
+    };
+    let start_at = Utc::now() + chrono::Duration::milliseconds(start_in_ms as i64);
+    info!(
+        "Assigned shard {}, starting at {}",
+        shard,
+        start_at.to_rfc3339()
+    );
+
+    let report_path = std::env::temp_dir().join(format!(
+        "traffic-simulator-{}-worker.json",
+        std::process::id()
+    ));
+    let status = Command::new(std::env::current_exe()?)
+        .args(&args)
+        .args(&own_args)
+        .arg("--shard")
+        .arg(&shard)
+        .arg("--shard-report")
+        .arg(&report_path)
+        .arg("--start-at")
+        .arg(start_at.to_rfc3339())
+        .kill_on_drop(true)
+        .status()
+        .await?;
+
+    let report = if status.success() {
+        shard::read_report(&report_path)
+            .map_err(|e| anyhow::anyhow!("Unreadable shard report: {}", e))
+    } else {
+        Err(anyhow::anyhow!("Shard {} exited with {}", shard, status))
+    };
+    match report {
+        Ok(report) => {
+            let message = Message::Report {
+                report: Box::new(report),
+            };
+            send(&mut writer, &message).await?;
+            info!("📤 Report sent to the coordinator");
+            Ok(())
+        }
+        Err(e) => {
+            let message = Message::Failed {
+                error: e.to_string(),
+            };
+            send(&mut writer, &message).await?;
+            Err(e)
+        }
+    }
+}
+
+/// The join token from the environment; coordinator and workers refuse to run without one
+fn join_token() -> anyhow::Result<String> {
+    std::env::var(JOIN_TOKEN_VAR)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Distributed runs need a shared secret in {} on the coordinator and every worker",
+                JOIN_TOKEN_VAR
+            )
+        })
+}
+
+/// Compare without stopping at the first differing byte, so response timing does
+/// not reveal how much of a guess was right
+fn tokens_match(offered: &str, expected: &str) -> bool {
+    offered.len() == expected.len()
+        && offered
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// `--database-url` in any of its spellings, including `-dURL`
+fn is_database_url_flag(arg: &str) -> bool {
+    arg == "--database-url" || arg.starts_with("--database-url=") || arg.starts_with("-d")
+}
+
+async fn send(writer: &mut OwnedWriteHalf, message: &Message) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+async fn receive(lines: &mut Lines<BufReader<OwnedReadHalf>>) -> anyhow::Result<Message> {
+    let line = lines
+        .next_line()
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("connection closed"))?;
+    serde_json::from_str(&line).map_err(|e| anyhow::anyhow!("malformed message: {}", e))
+}
@@ -0,0 +1,23 @@
+use crate::{QueryAttempt, QueryContext, QueryType};
+use futures::future::BoxFuture;
+
+/// A database the workload runs against. Each driver runs the workload's requests
+/// in its own dialect and reports them as `QueryAttempt`s, so traffic patterns,
+/// retries and reporting are shared by every backend.
+pub trait DatabaseDriver: Send + Sync {
+    /// Fail unless the database answers a trivial query
+    fn test_connection(&self) -> BoxFuture<'_, anyhow::Result<()>>;
+
+    /// The server's own version string, recorded with the results
+    fn server_version(&self) -> BoxFuture<'_, anyhow::Result<String>>;
+
+    /// Run attempt number `attempt` of the request with `seed`, timing connection
+    /// acquisition and execution separately
+    fn execute_attempt<'a>(
+        &'a self,
+        ctx: &'a QueryContext,
+        query_type: &'a QueryType,
+        seed: u64,
+        attempt: u32,
+    ) -> BoxFuture<'a, QueryAttempt>;
+}
@@ -0,0 +1,273 @@
+use crate::baseline::{error_rate, percent_change};
+use crate::report::{self, RunFileResult};
+use crate::shard::{strip_flags, SHARD_STARTUP_GRACE};
+use crate::{mask_password, Args};
+use chrono::Utc;
+use futures::future::join_all;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+use tracing::info;
+
+/// Flags naming a file each endpoint's process writes; every endpoint gets its own,
+/// numbered after the endpoint (`results.json` → `results-2.json`)
+const FILE_FLAGS: &[&str] = &[
+    "--output-json",
+    "--report-html",
+    "--timeline-output",
+    "--queue-depth-output",
+    "--interval-output",
+    "--raw-metrics",
+    "--event-log",
+];
+
+/// Width of an endpoint's column in the comparison table
+const COLUMN_WIDTH: usize = 22;
+
+/// One `--database-url` of a multi-endpoint run
+struct Endpoint {
+    /// 1-based, in command line order
+    number: usize,
+    database_url: String,
+    /// Where its process exports its result
+    output_json: PathBuf,
+    /// The result file is the user's --output-json, kept after the comparison
+    keep: bool,
+}
+
+/// Run the same workload against every `--database-url` at once: one simulator
+/// process per endpoint, each with its own pool, all with the same seed and start
+/// line so they issue the same sequence of operations. Their results are compared
+/// side by side, the first endpoint being the reference.
+pub async fn run_endpoints(args: &Args) -> anyhow::Result<()> {
+    let unsupported = [
+        (args.processes > 1, "--processes"),
+        (args.coordinator || args.worker, "distributed runs"),
+        (args.tui, "--tui"),
+        (args.control_port.is_some(), "--control-port"),
+        (args.baseline.is_some(), "--baseline"),
+        (args.publish_databricks.is_some(), "--publish-databricks"),
+    ];
+    if let Some((_, setting)) = unsupported.iter().find(|(set, _)| *set) {
+        return Err(anyhow::anyhow!(
+            "{} cannot be combined with more than one --database-url",
+            setting
+        ));
+    }
+
+    let endpoints: Vec<Endpoint> = args
+        .database_urls
+        .iter()
+        .enumerate()
+        .map(|(index, database_url)| Endpoint {
+            number: index + 1,
+            database_url: database_url.clone(),
+            output_json: match &args.output_json {
+                Some(path) => numbered_path(path, index + 1).into(),
+                None => std::env::temp_dir().join(format!(
+                    "traffic-simulator-{}-endpoint-{}.json",
+                    std::process::id(),
+                    index + 1
+                )),
+            },
+            keep: args.output_json.is_some(),
+        })
+        .collect();
+
+    // The expanded command line, so a --config profile's flags reach every process
+    let forwarded = strip_flags(
+        args.argv
+            .iter()
+            .skip(1)
+            .map(|arg| arg.to_string_lossy().into_owned()),
+        &[
+            &["-d", "--database-url", "--config", "--run-id", "--start-at"],
+            FILE_FLAGS,
+        ]
+        .concat(),
+        &[],
+    );
+    let start_at = args
+        .start_at
+        .unwrap_or_else(|| Utc::now() + SHARD_STARTUP_GRACE);
+    if !args.disable_logging {
+        info!(
+            "🏁 Running the workload against {} endpoints at once, starting at {}",
+            endpoints.len(),
+            start_at.to_rfc3339()
+        );
+        for endpoint in &endpoints {
+            info!(
+                "   #{} {}",
+                endpoint.number,
+                mask_password(&endpoint.database_url)
+            );
+        }
+    }
+
+    let exe = std::env::current_exe()?;
+    let mut children = Vec::with_capacity(endpoints.len());
+    for endpoint in &endpoints {
+        // Not to mistake a previous run's file for this one's result
+        let _ = std::fs::remove_file(&endpoint.output_json);
+        let mut command = Command::new(&exe);
+        command
+            .args(&forwarded)
+            .arg("--database-url")
+            .arg(&endpoint.database_url)
+            .arg("--output-json")
+            .arg(&endpoint.output_json)
+            .arg("--run-id")
+            .arg(args.run_id())
+            .arg("--start-at")
+            .arg(start_at.to_rfc3339())
+            .arg("--label")
+            .arg(format!("endpoint={}", endpoint.number))
+            // Their narration would interleave; the comparison is printed here
+            .arg("--disable-logging")
+            .stdout(Stdio::null());
+        for flag in FILE_FLAGS.iter().filter(|flag| **flag != "--output-json") {
+            if let Some(path) = file_flag(args, flag).filter(|path| *path != "-") {
+                command.arg(flag).arg(numbered_path(path, endpoint.number));
+            }
+        }
+        children.push(command.kill_on_drop(true).spawn()?);
+    }
+
+    let statuses = join_all(children.iter_mut().map(|child| child.wait())).await;
+    let mut results = Vec::with_capacity(endpoints.len());
+    let mut failed = Vec::new();
+    for (endpoint, status) in endpoints.iter().zip(statuses) {
+        let status = status?;
+        let run = report::load_run(&endpoint.output_json);
+        if !endpoint.keep {
+            let _ = std::fs::remove_file(&endpoint.output_json);
+        }
+        match run {
+            // A run that fails its own checks (e.g. --abort-if) still exported a result
+            Ok(run) => results.push((endpoint, run.result)),
+            Err(_) if !status.success() => {
+                failed.push(format!("#{} (exited with {})", endpoint.number, status))
+            }
+            Err(e) => failed.push(format!("#{} ({})", endpoint.number, e)),
+        }
+    }
+
+    if !results.is_empty() {
+        display_endpoints(&results);
+        if args.output_json.is_some() && !args.disable_logging {
+            info!(
+                "📝 Results written to {}",
+                results
+                    .iter()
+                    .map(|(endpoint, _)| endpoint.output_json.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+    }
+    if !failed.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No result from endpoint {}",
+            failed.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// The value of file flag `flag`
+fn file_flag<'a>(args: &'a Args, flag: &str) -> Option<&'a str> {
+    match flag {
+        "--report-html" => args.report_html.as_deref(),
+        "--timeline-output" => args.timeline_output.as_deref(),
+        "--queue-depth-output" => args.queue_depth_output.as_deref(),
+        "--interval-output" => args.interval_output.as_deref(),
+        "--raw-metrics" => args.raw_metrics.as_deref(),
+        "--event-log" => args.event_log.as_deref(),
+        _ => None,
+    }
+}
+
+/// `path` with `-number` before its extension
+fn numbered_path(path: &str, number: usize) -> String {
+    let path = Path::new(path);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, number, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, number),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+fn display_endpoints(results: &[(&Endpoint, RunFileResult)]) {
+    println!("\n🏁 Endpoint Comparison (same workload, started together):");
+    for (endpoint, _) in results {
+        println!(
+            "   #{}  {}",
+            endpoint.number,
+            mask_password(&endpoint.database_url)
+        );
+    }
+
+    let mut header = format!("   {:<20}", "Metric");
+    for (endpoint, _) in results {
+        header.push_str(&format!(
+            "{:>width$}",
+            format!("#{}", endpoint.number),
+            width = COLUMN_WIDTH
+        ));
+    }
+    println!("{}", header);
+
+    let column = |metric: fn(&RunFileResult) -> f64| -> Vec<f64> {
+        results.iter().map(|(_, result)| metric(result)).collect()
+    };
+    let rows = [
+        ("Queries/Second", column(|r| r.queries_per_second)),
+        ("Average Latency (ms)", column(|r| r.average_latency_ms)),
+        ("P50 Latency (ms)", column(|r| r.p50_latency_ms)),
+        ("P95 Latency (ms)", column(|r| r.p95_latency_ms)),
+        ("P99 Latency (ms)", column(|r| r.p99_latency_ms)),
+        ("P99.9 Latency (ms)", column(|r| r.p999_latency_ms)),
+    ];
+    for (name, values) in rows {
+        let mut line = format!("   {:<20}", name);
+        for (index, value) in values.iter().enumerate() {
+            // Other endpoints show their change against the first
+            let cell = if index == 0 {
+                format!("{:.2}", value)
+            } else {
+                format!("{:.2} ({:+.1}%)", value, percent_change(values[0], *value))
+            };
+            line.push_str(&format!("{:>width$}", cell, width = COLUMN_WIDTH));
+        }
+        println!("{}", line);
+    }
+    let mut line = format!("   {:<20}", "Error Rate (%)");
+    for (_, result) in results {
+        let cell = format!(
+            "{:.2}",
+            error_rate(result.failed_queries, result.total_queries)
+        );
+        line.push_str(&format!("{:>width$}", cell, width = COLUMN_WIDTH));
+    }
+    println!("{}", line);
+
+    let fastest = results
+        .iter()
+        .max_by(|a, b| a.1.queries_per_second.total_cmp(&b.1.queries_per_second));
+    let steadiest = results
+        .iter()
+        .min_by(|a, b| a.1.p99_latency_ms.total_cmp(&b.1.p99_latency_ms));
+    if let (true, Some((fastest, _)), Some((steadiest, _))) =
+        (results.len() > 1, fastest, steadiest)
+    {
+        println!(
+            "   💡 Highest throughput: #{}; lowest p99 latency: #{}",
+            fastest.number, steadiest.number
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use crate::failures::{self, FailureKind};
+use crate::FailureStage;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Rate limiter for per-failure warnings: only the first `limit` failures of each
+/// SQLSTATE (or failure kind, for errors without one) are logged per window, and the rest are summarized as counts, so a
+/// failure storm cannot flood the output or slow the generator down
+#[derive(Debug)]
+pub struct ErrorLogSampler {
+    limit: usize,
+    window: Duration,
+    state: Mutex<SamplerWindow>,
+}
+
+#[derive(Debug)]
+struct SamplerWindow {
+    started: Instant,
+    counts: BTreeMap<LogClass, usize>,
+}
+
+/// What failures are rate-limited by
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum LogClass {
+    Sqlstate(String),
+    Kind(FailureKind),
+}
+
+impl LogClass {
+    fn of(stage: FailureStage, error: &anyhow::Error) -> Self {
+        match failures::sqlstate(error) {
+            Some(code) => LogClass::Sqlstate(code),
+            None => LogClass::Kind(failures::classify(stage, error)),
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            LogClass::Sqlstate(code) => format!("SQLSTATE {}", code),
+            LogClass::Kind(kind) => kind.name().to_string(),
+        }
+    }
+}
+
+impl ErrorLogSampler {
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            state: Mutex::new(SamplerWindow {
+                started: Instant::now(),
+                counts: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Log a failure unless its class has already used up this window's quota
+    pub fn log(&self, stage: FailureStage, error: &anyhow::Error) {
+        let class = LogClass::of(stage, error);
+        let mut state = self.state.lock().unwrap();
+
+        if state.started.elapsed() >= self.window {
+            self.report_suppressed(&state);
+            state.started = Instant::now();
+            state.counts.clear();
+        }
+
+        let count = state.counts.entry(class).or_insert(0);
+        *count += 1;
+        if *count <= self.limit {
+            match stage {
+                FailureStage::Connection => warn!("Connection failed: {}", error),
+                FailureStage::Query => warn!("Query failed: {}", error),
+            }
+        }
+    }
+
+    /// Summarize anything suppressed in the current window (called at end of run)
+    pub fn flush(&self) {
+        let mut state = self.state.lock().unwrap();
+        self.report_suppressed(&state);
+        state.counts.clear();
+    }
+
+    fn report_suppressed(&self, state: &SamplerWindow) {
+        for (class, count) in &state.counts {
+            if *count > self.limit {
+                warn!(
+                    "Suppressed {} more failures of class {} in the last {:.0}s",
+                    count - self.limit,
+                    class.name(),
+                    state.started.elapsed().as_secs_f64()
+                );
+            }
+        }
+    }
+}
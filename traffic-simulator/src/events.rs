@@ -0,0 +1,128 @@
+use anyhow::Context;
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::sync::Mutex;
+use tracing::warn;
+
+/// `--event-log`: the run's lifecycle as JSON lines, one per event, for a web app
+/// or orchestrator that follows the simulator without parsing its log
+pub struct EventLog {
+    run_id: String,
+    /// `None` once a write has failed; the run carries on without the log
+    out: Mutex<Option<Box<dyn Write + Send>>>,
+}
+
+impl std::fmt::Debug for EventLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventLog")
+            .field("run_id", &self.run_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// What happened; `event` is the kebab-case variant name, e.g. `run-started`
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    /// The measured run starts, after any warmup and --start-at wait
+    RunStarted {
+        query_type: String,
+        connections: usize,
+        duration_seconds: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        target_qps: Option<f64>,
+    },
+    /// A traffic phase, story chapter or load mode starts
+    PhaseStarted {
+        phase: String,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        notes: Vec<String>,
+    },
+    /// The target rate or connection ceiling changed, from a real-world phase's
+    /// variation (`source: phase`) or the control API (`source: control`)
+    QpsAdjusted {
+        /// `null` when the control API cleared its target
+        qps: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        connections: Option<usize>,
+        source: &'static str,
+    },
+    /// A watched metric crossed its limit: an --abort-if condition (`action: abort`)
+    /// or a fast burn of the --slo error budget (`action: alert`)
+    ThresholdBreached {
+        threshold: String,
+        observed: f64,
+        action: &'static str,
+    },
+    /// The run is over and its results are computed
+    RunFinished {
+        total_queries: usize,
+        successful_queries: usize,
+        failed_queries: usize,
+        queries_per_second: f64,
+        p50_latency_ms: f64,
+        p95_latency_ms: f64,
+        p99_latency_ms: f64,
+        aborted: bool,
+    },
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    timestamp: String,
+    run_id: &'a str,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+impl EventLog {
+    /// Write to `path`, or to standard output for `-`
+    pub fn create(path: &str, run_id: &str) -> anyhow::Result<Self> {
+        let out: Box<dyn Write + Send> = if path == "-" {
+            Box::new(std::io::stdout())
+        } else {
+            Box::new(
+                File::create(path)
+                    .with_context(|| format!("Failed to create --event-log {}", path))?,
+            )
+        };
+        Ok(Self {
+            run_id: run_id.to_string(),
+            out: Mutex::new(Some(out)),
+        })
+    }
+
+    /// Append `event` stamped with the current time, flushed at once so followers
+    /// see it as it happens
+    pub fn emit(&self, event: Event) {
+        let line = Line {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            run_id: &self.run_id,
+            event: &event,
+        };
+        let mut out = self.out.lock().unwrap();
+        let Some(writer) = out.as_mut() else {
+            return;
+        };
+        let written = serde_json::to_writer(&mut *writer, &line)
+            .map_err(std::io::Error::from)
+            .and_then(|()| writer.write_all(b"\n"))
+            .and_then(|()| writer.flush());
+        if let Err(e) = written {
+            warn!(
+                "Failed to write to the event log, no more events will be written: {}",
+                e
+            );
+            *out = None;
+        }
+    }
+}
+
+/// Emit `event` when the run keeps an event log
+pub fn emit(events: Option<&EventLog>, event: impl FnOnce() -> Event) {
+    if let Some(events) = events {
+        events.emit(event());
+    }
+}
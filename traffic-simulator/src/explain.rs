@@ -0,0 +1,288 @@
+use crate::recorder::LatencyHistogram;
+use crate::sampling::Sampler;
+use crate::LatencySummary;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::{FromSql, ToSql, Type};
+
+/// "expla", see [`crate::sampling`]
+const EXPLAIN_SEED_SALT: u64 = 0x6578_706c_6100_0000;
+
+/// Characters of a statement's text kept in the report
+const STATEMENT_PREVIEW_LEN: usize = 100;
+
+tokio::task_local! {
+    /// Set while a sampled request runs, so its workload statements get explained
+    static SAMPLED: Sampled;
+}
+
+struct Sampled {
+    sampler: Arc<ExplainSampler>,
+    /// Time spent explaining, to be left out of the request's latency
+    spent: Cell<Duration>,
+}
+
+/// `--explain-sample-rate`: runs EXPLAIN (ANALYZE, BUFFERS) of the workload
+/// statements of a share of requests, and groups the plans by statement and shape
+#[derive(Debug)]
+pub struct ExplainSampler {
+    sampler: Sampler,
+    stats: Mutex<ExplainStats>,
+}
+
+#[derive(Debug, Default)]
+struct ExplainStats {
+    samples: u64,
+    failures: u64,
+    /// Keyed by statement preview and plan shape
+    plans: BTreeMap<(String, String), PlanStats>,
+}
+
+#[derive(Debug, Default)]
+struct PlanStats {
+    samples: u64,
+    /// Milliseconds, as reported by the server
+    planning: LatencyHistogram,
+    execution: LatencyHistogram,
+    shared_hit_blocks: u64,
+    shared_read_blocks: u64,
+}
+
+/// The plans sampled during a run
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ExplainReport {
+    /// Share of requests whose statements were explained
+    pub sample_rate: f64,
+    /// Statements explained
+    pub samples: u64,
+    /// EXPLAINs that failed, e.g. on a lock or statement timeout
+    pub failures: u64,
+    /// One entry per statement and plan shape, slowest execution first
+    pub plans: Vec<PlanReport>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PlanReport {
+    /// Statement text, whitespace collapsed and cut to 100 characters
+    pub statement: String,
+    /// Node types of the plan tree, e.g. `Limit -> Index Scan using orders_pkey`
+    pub shape: String,
+    pub samples: u64,
+    pub planning: LatencySummary,
+    pub execution: LatencySummary,
+    /// Shared buffers per execution found in cache and read from disk or the OS
+    pub average_shared_hit_blocks: f64,
+    pub average_shared_read_blocks: f64,
+}
+
+/// A `json` column as its text
+struct JsonText(String);
+
+impl<'a> FromSql<'a> for JsonText {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+        Ok(JsonText(std::str::from_utf8(raw)?.to_string()))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::JSON
+    }
+}
+
+impl ExplainSampler {
+    pub fn new(rate: f64) -> Self {
+        Self {
+            sampler: Sampler::new(rate, EXPLAIN_SEED_SALT),
+            stats: Mutex::new(ExplainStats::default()),
+        }
+    }
+
+    /// Whether attempt `attempt` of the request with `seed` is explained
+    pub fn samples(&self, seed: u64, attempt: u32) -> bool {
+        self.sampler.samples(seed, attempt)
+    }
+
+    fn record(&self, sql: &str, plan: Result<Value, tokio_postgres::Error>) {
+        let mut stats = self.stats.lock().unwrap();
+        let Ok(plan) = plan else {
+            stats.failures += 1;
+            return;
+        };
+        // FORMAT JSON returns a one-element array
+        let plan = &plan[0];
+        let root = &plan["Plan"];
+        stats.samples += 1;
+        let entry = stats.plans.entry((preview(sql), shape(root))).or_default();
+        entry.samples += 1;
+        entry
+            .planning
+            .record_ms(plan["Planning Time"].as_f64().unwrap_or(0.0));
+        entry
+            .execution
+            .record_ms(plan["Execution Time"].as_f64().unwrap_or(0.0));
+        // Buffer counts of the root node include its children's
+        entry.shared_hit_blocks += root["Shared Hit Blocks"].as_u64().unwrap_or(0);
+        entry.shared_read_blocks += root["Shared Read Blocks"].as_u64().unwrap_or(0);
+    }
+
+    pub fn report(&self) -> ExplainReport {
+        let stats = self.stats.lock().unwrap();
+        let mut plans: Vec<PlanReport> = stats
+            .plans
+            .iter()
+            .map(|((statement, shape), plan)| PlanReport {
+                statement: statement.clone(),
+                shape: shape.clone(),
+                samples: plan.samples,
+                planning: plan.planning.summary(),
+                execution: plan.execution.summary(),
+                average_shared_hit_blocks: plan.shared_hit_blocks as f64 / plan.samples as f64,
+                average_shared_read_blocks: plan.shared_read_blocks as f64 / plan.samples as f64,
+            })
+            .collect();
+        plans.sort_by(|a, b| b.execution.max_ms.total_cmp(&a.execution.max_ms));
+        ExplainReport {
+            sample_rate: self.sampler.rate(),
+            samples: stats.samples,
+            failures: stats.failures,
+            plans,
+        }
+    }
+}
+
+/// Run `future`, one attempt of a sampled request, with its statements explained.
+/// Returns its output and the time the EXPLAINs took.
+pub async fn scope<F: Future>(sampler: Arc<ExplainSampler>, future: F) -> (F::Output, Duration) {
+    let sampled = Sampled {
+        sampler,
+        spent: Cell::new(Duration::ZERO),
+    };
+    SAMPLED
+        .scope(sampled, async move {
+            let output = future.await;
+            (output, SAMPLED.with(|sampled| sampled.spent.get()))
+        })
+        .await
+}
+
+/// Explain `sql` before it runs, when the current request is sampled. A failed
+/// EXPLAIN is counted but never fails the request.
+pub async fn explain_if_sampled(
+    client: &tokio_postgres::Client,
+    sql: &str,
+    params: &[&(dyn ToSql + Sync)],
+) {
+    let Ok(sampler) = SAMPLED.try_with(|sampled| Arc::clone(&sampled.sampler)) else {
+        return;
+    };
+    let start = Instant::now();
+    let plan = explain(client, sql, params).await;
+    sampler.record(sql, plan);
+    let _ = SAMPLED.try_with(|sampled| sampled.spent.set(sampled.spent.get() + start.elapsed()));
+}
+
+/// EXPLAIN ANALYZE executes the statement, so it runs in a savepoint (or its own
+/// transaction outside of one) that is rolled back: writes are undone, and the real
+/// statement that follows still finds the rows and keys it expects
+async fn explain(
+    client: &tokio_postgres::Client,
+    sql: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<Value, tokio_postgres::Error> {
+    let in_transaction = match client.batch_execute("SAVEPOINT explain_sample").await {
+        Ok(()) => true,
+        Err(e) if e.code() == Some(&SqlState::NO_ACTIVE_SQL_TRANSACTION) => false,
+        Err(e) => return Err(e),
+    };
+    if !in_transaction {
+        client.batch_execute("BEGIN").await?;
+    }
+    let plan = client
+        .query_one(
+            &format!("EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON) {}", sql),
+            params,
+        )
+        .await;
+    client
+        .batch_execute(if in_transaction {
+            "ROLLBACK TO SAVEPOINT explain_sample; RELEASE SAVEPOINT explain_sample"
+        } else {
+            "ROLLBACK"
+        })
+        .await?;
+    let JsonText(plan) = plan?.get(0);
+    Ok(serde_json::from_str(&plan).unwrap_or(Value::Null))
+}
+
+/// `sql` on one line, cut to a readable length
+fn preview(sql: &str) -> String {
+    let sql = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+    match sql.char_indices().nth(STATEMENT_PREVIEW_LEN) {
+        Some((end, _)) => format!("{}...", &sql[..end]),
+        None => sql,
+    }
+}
+
+/// Node types of a plan tree: `A -> B` for a single child, `A -> (B, C)` for several
+fn shape(node: &Value) -> String {
+    let mut label = node["Node Type"].as_str().unwrap_or("?").to_string();
+    if let Some(index) = node["Index Name"].as_str() {
+        label = format!("{} using {}", label, index);
+    } else if let Some(relation) = node["Relation Name"].as_str() {
+        label = format!("{} on {}", label, relation);
+    }
+    let children: Vec<String> = node["Plans"]
+        .as_array()
+        .map(|plans| plans.iter().map(shape).collect())
+        .unwrap_or_default();
+    match children.as_slice() {
+        [] => label,
+        [child] => format!("{} -> {}", label, child),
+        _ => format!("{} -> ({})", label, children.join(", ")),
+    }
+}
+
+pub fn display_explain(report: &ExplainReport) {
+    println!(
+        "\n🔬 Query Plans (EXPLAIN ANALYZE of {:.2}% of requests, {} statements{}):",
+        report.sample_rate * 100.0,
+        report.samples,
+        if report.failures > 0 {
+            format!(", {} failed", report.failures)
+        } else {
+            String::new()
+        }
+    );
+    for plan in &report.plans {
+        println!("   {}", plan.statement);
+        println!("     {}", plan.shape);
+        println!(
+            "     {} samples  planning avg {:.2}ms  execution avg {:.2}ms  p99 {:.2}ms  max {:.2}ms  buffers hit {:.1} read {:.1}",
+            plan.samples,
+            plan.planning.average_ms,
+            plan.execution.average_ms,
+            plan.execution.p99_ms,
+            plan.execution.max_ms,
+            plan.average_shared_hit_blocks,
+            plan.average_shared_read_blocks
+        );
+    }
+
+    let mut shapes: BTreeMap<&str, usize> = BTreeMap::new();
+    for plan in &report.plans {
+        *shapes.entry(plan.statement.as_str()).or_insert(0) += 1;
+    }
+    let flipped = shapes.values().filter(|count| **count > 1).count();
+    if flipped > 0 {
+        println!(
+            "   💡 {} statements ran with more than one plan; compare their execution times",
+            flipped
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use crate::metadata::RunMetadata;
+use crate::pool_options::PoolConfig;
+use crate::{mask_password, Args, BackendKind, SimulationResult};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Document written by `--output-json`, and read back by the `report` subcommand
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RunFile<'a> {
+    /// Id the run stamped on the order numbers it inserted (--run-id)
+    pub run_id: String,
+    /// When the measured run started (after warmup)
+    pub started_at: DateTime<Utc>,
+    /// When the traffic stopped
+    pub finished_at: DateTime<Utc>,
+    pub labels: BTreeMap<String, String>,
+    pub config: RunConfig,
+    pub metadata: RunMetadata,
+    pub result: &'a SimulationResult,
+}
+
+/// Settings the run was started with
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RunConfig {
+    /// Connection string with the password masked
+    pub database_url: String,
+    pub backend: String,
+    pub workload: String,
+    pub query_type: String,
+    pub connections: usize,
+    /// Connection pool settings; PostgreSQL only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool: Option<PoolConfig>,
+    /// Transaction isolation level (--isolation)
+    pub isolation: Option<String>,
+    /// Column the run id was written to on inserts and updates (--tag-column)
+    pub tag_column: Option<String>,
+    pub total_queries: Option<usize>,
+    pub duration_seconds: u64,
+    pub duration_only: bool,
+    pub warmup_seconds: u64,
+    /// Linear ramps at the start and end of the run (--ramp-up, --ramp-down)
+    pub ramp_up_seconds: Option<f64>,
+    pub ramp_down_seconds: Option<f64>,
+    pub real_simulation: bool,
+    pub demo_mode: Option<String>,
+    /// Built-in traffic pattern picked with --pattern
+    pub pattern: Option<String>,
+    pub pattern_file: Option<String>,
+    /// Arrival process of the real-world simulation (--arrival)
+    pub arrival: String,
+    /// Seed of the run's random choices (--seed); the same seed replays the same
+    /// sequence of operations
+    pub seed: u64,
+    pub virtual_users: Option<usize>,
+    /// Mean pause between a session's queries (--think-time-ms)
+    pub think_time_ms: Option<u64>,
+    pub think_time_distribution: Option<String>,
+    pub processes: u16,
+    pub plugin: Option<String>,
+    pub script: Option<String>,
+    /// `--config` profile the settings were read from, if any
+    pub config_file: Option<String>,
+    /// Full command line, with passwords in connection strings masked
+    pub command_line: Vec<String>,
+}
+
+impl RunConfig {
+    fn from_args(args: &Args) -> Self {
+        Self {
+            database_url: mask_password(&args.database_url),
+            backend: value_name(&args.backend),
+            workload: value_name(&args.workload),
+            query_type: value_name(&args.query_type),
+            connections: args.connections,
+            pool: (args.backend == BackendKind::Postgres)
+                .then(|| args.pool_options().config(args.connections)),
+            isolation: args.isolation.as_ref().map(value_name),
+            tag_column: args.tag_column.clone(),
+            total_queries: args.total_queries,
+            duration_seconds: args.duration,
+            duration_only: args.duration_only,
+            warmup_seconds: args.warmup,
+            ramp_up_seconds: args.ramp_up.map(|ramp| ramp.as_secs_f64()),
+            ramp_down_seconds: args.ramp_down.map(|ramp| ramp.as_secs_f64()),
+            real_simulation: args.real_simulation,
+            demo_mode: args.demo_mode.clone(),
+            pattern: args.pattern.as_ref().map(value_name),
+            pattern_file: args.pattern_file.clone(),
+            arrival: value_name(&args.arrival),
+            seed: args.seed,
+            virtual_users: args.virtual_users,
+            think_time_ms: args.think_time_ms,
+            think_time_distribution: args
+                .think_time_ms
+                .map(|_| value_name(&args.think_time_distribution)),
+            processes: args.processes,
+            plugin: args.plugin.clone(),
+            script: args.script.clone(),
+            config_file: args.config.clone(),
+            command_line: std::env::args()
+                .map(|arg| {
+                    if arg.contains("://") {
+                        mask_password(&arg)
+                    } else {
+                        arg
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+pub fn value_name<T: ValueEnum>(value: &T) -> String {
+    value
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// When a finished run ran and what it ran against
+#[derive(Debug)]
+pub struct RunSpan {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub server_version: Option<String>,
+}
+
+/// The run file for `result`
+pub fn run_file<'a>(args: &Args, span: &RunSpan, result: &'a SimulationResult) -> RunFile<'a> {
+    RunFile {
+        run_id: args.run_id().to_string(),
+        started_at: span.started_at,
+        finished_at: span.finished_at,
+        labels: args.label_map(),
+        config: RunConfig::from_args(args),
+        metadata: RunMetadata::collect(args, span.server_version.clone()),
+        result,
+    }
+}
+
+/// Write the result, run configuration and metadata to `path` as pretty JSON
+pub fn write_run_file(
+    path: &str,
+    args: &Args,
+    span: &RunSpan,
+    result: &SimulationResult,
+) -> anyhow::Result<()> {
+    let run = run_file(args, span, result);
+    std::fs::write(path, serde_json::to_string_pretty(&run)?)
+        .map_err(|e| anyhow::anyhow!("Failed to write results to '{}': {}", path, e))
+}
+
+/// Columns appended to every row of the CSV exports (--raw-metrics,
+/// --timeline-output, --queue-depth-output): the run id, then one column per
+/// label, so files from several runs can be concatenated and still told apart
+#[derive(Debug, Clone, Default)]
+pub struct CsvLabels {
+    header: String,
+    values: String,
+}
+
+impl CsvLabels {
+    pub fn new(args: &Args) -> Self {
+        let mut header = String::from(",run_id");
+        let mut values = format!(",{}", csv_field(args.run_id()));
+        for (key, value) in args.label_map() {
+            header.push_str(&format!(",{}", csv_field(&key)));
+            values.push_str(&format!(",{}", csv_field(&value)));
+        }
+        Self { header, values }
+    }
+
+    /// Column names, each preceded by a comma
+    pub fn header(&self) -> &str {
+        &self.header
+    }
+
+    /// This run's values, each preceded by a comma
+    pub fn values(&self) -> &str {
+        &self.values
+    }
+}
+
+/// Quote a field that holds a comma, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,186 @@
+use crate::chaos::InjectedError;
+use crate::mysql;
+use crate::query_timeout::QueryTimeout;
+use crate::FailureStage;
+use deadpool_postgres::{PoolError, TimeoutType};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use tokio_postgres::error::SqlState;
+
+/// What a failed query ran into, finer-grained than the retry classes
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// No pooled connection freed up within the pool's wait timeout
+    PoolWaitTimeout,
+    /// Opening or health-checking a connection timed out
+    ConnectionTimeout,
+    /// Opening a connection failed, e.g. refused or rejected at authentication
+    ConnectionFailed,
+    /// The server is out of connection slots (SQLSTATE 53300)
+    TooManyConnections,
+    /// Duplicate key (SQLSTATE 23505)
+    UniqueViolation,
+    /// SQLSTATE 40001
+    SerializationFailure,
+    /// SQLSTATE 40P01
+    Deadlock,
+    /// Canceled by statement_timeout (SQLSTATE 57014)
+    StatementTimeout,
+    /// No answer from the server within --query-timeout-ms
+    QueryTimeout,
+    /// lock_timeout (SQLSTATE 55P03), or SQLite busy or locked
+    LockTimeout,
+    /// Connection closed or reset mid-request by the server or network
+    NetworkReset,
+    /// Any other error reported by the database
+    DatabaseError,
+    /// Failed on purpose by an `error` fault (--chaos)
+    Injected,
+    Other,
+}
+
+impl FailureKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            FailureKind::PoolWaitTimeout => "pool wait timeout",
+            FailureKind::ConnectionTimeout => "connection timeout",
+            FailureKind::ConnectionFailed => "connection failed",
+            FailureKind::TooManyConnections => "too many connections",
+            FailureKind::UniqueViolation => "unique violation",
+            FailureKind::SerializationFailure => "serialization failure",
+            FailureKind::Deadlock => "deadlock",
+            FailureKind::StatementTimeout => "statement timeout",
+            FailureKind::QueryTimeout => "query timeout",
+            FailureKind::LockTimeout => "lock timeout",
+            FailureKind::NetworkReset => "network reset",
+            FailureKind::DatabaseError => "other database error",
+            FailureKind::Injected => "injected by --chaos",
+            FailureKind::Other => "other",
+        }
+    }
+}
+
+/// Classify a failure by the stage it happened in, looking through pool and driver
+/// error wrappers
+pub fn classify(stage: FailureStage, error: &anyhow::Error) -> FailureKind {
+    if let Some(e) = error.downcast_ref::<rusqlite::Error>() {
+        return classify_sqlite(e);
+    }
+    if let Some(mysql_async::Error::Server(e)) = error.downcast_ref::<mysql_async::Error>() {
+        return classify_mysql(e.code);
+    }
+    if error.is::<QueryTimeout>() {
+        return FailureKind::QueryTimeout;
+    }
+    if error.is::<InjectedError>() {
+        return FailureKind::Injected;
+    }
+
+    let db_error = match error.downcast_ref::<PoolError>() {
+        Some(PoolError::Timeout(TimeoutType::Wait)) => return FailureKind::PoolWaitTimeout,
+        Some(PoolError::Timeout(_)) => return FailureKind::ConnectionTimeout,
+        Some(PoolError::Backend(e)) => Some(e),
+        Some(_) => return FailureKind::ConnectionFailed,
+        None => error.downcast_ref::<tokio_postgres::Error>(),
+    };
+
+    if let Some(code) = db_error.and_then(|e| e.code()) {
+        return classify_sqlstate(code);
+    }
+    match io_error_kind(error) {
+        Some(ErrorKind::TimedOut) => FailureKind::ConnectionTimeout,
+        Some(
+            ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::BrokenPipe
+            | ErrorKind::UnexpectedEof,
+        ) if stage == FailureStage::Query => FailureKind::NetworkReset,
+        _ if stage == FailureStage::Connection => FailureKind::ConnectionFailed,
+        _ if db_error.is_some_and(|e| e.is_closed()) => FailureKind::NetworkReset,
+        _ => FailureKind::Other,
+    }
+}
+
+/// SQLSTATE the database reported for a failure, if any
+pub fn sqlstate(error: &anyhow::Error) -> Option<String> {
+    if let Some(mysql_async::Error::Server(e)) = error.downcast_ref::<mysql_async::Error>() {
+        return Some(e.state.clone());
+    }
+    let db_error = match error.downcast_ref::<PoolError>() {
+        Some(PoolError::Backend(e)) => Some(e),
+        _ => error.downcast_ref::<tokio_postgres::Error>(),
+    };
+    db_error
+        .and_then(|e| e.code())
+        .map(|code| code.code().to_string())
+}
+
+fn classify_sqlstate(code: &SqlState) -> FailureKind {
+    match code {
+        c if *c == SqlState::UNIQUE_VIOLATION => FailureKind::UniqueViolation,
+        c if *c == SqlState::T_R_SERIALIZATION_FAILURE => FailureKind::SerializationFailure,
+        c if *c == SqlState::T_R_DEADLOCK_DETECTED => FailureKind::Deadlock,
+        c if *c == SqlState::QUERY_CANCELED => FailureKind::StatementTimeout,
+        c if *c == SqlState::LOCK_NOT_AVAILABLE => FailureKind::LockTimeout,
+        c if *c == SqlState::TOO_MANY_CONNECTIONS => FailureKind::TooManyConnections,
+        c if *c == SqlState::ADMIN_SHUTDOWN => FailureKind::NetworkReset,
+        _ => FailureKind::DatabaseError,
+    }
+}
+
+fn classify_sqlite(error: &rusqlite::Error) -> FailureKind {
+    match error {
+        rusqlite::Error::SqliteFailure(e, _)
+            if e.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE =>
+        {
+            FailureKind::UniqueViolation
+        }
+        rusqlite::Error::SqliteFailure(e, _)
+            if e.code == rusqlite::ErrorCode::DatabaseBusy
+                || e.code == rusqlite::ErrorCode::DatabaseLocked =>
+        {
+            FailureKind::LockTimeout
+        }
+        _ => FailureKind::DatabaseError,
+    }
+}
+
+fn classify_mysql(code: u16) -> FailureKind {
+    match code {
+        mysql::ER_DUP_ENTRY => FailureKind::UniqueViolation,
+        mysql::ER_LOCK_DEADLOCK => FailureKind::Deadlock,
+        mysql::ER_LOCK_WAIT_TIMEOUT => FailureKind::LockTimeout,
+        mysql::ER_QUERY_TIMEOUT | mysql::ER_QUERY_INTERRUPTED => FailureKind::StatementTimeout,
+        mysql::ER_CON_COUNT_ERROR => FailureKind::TooManyConnections,
+        mysql::ER_SERVER_SHUTDOWN => FailureKind::NetworkReset,
+        _ => FailureKind::DatabaseError,
+    }
+}
+
+/// Kind of the first I/O error in a failure's source chain, if any
+fn io_error_kind(error: &anyhow::Error) -> Option<ErrorKind> {
+    error
+        .chain()
+        .find_map(|e| e.downcast_ref::<std::io::Error>())
+        .map(|e| e.kind())
+}
+
+pub fn display_failures(failures: &BTreeMap<FailureKind, u64>, failed_queries: usize) {
+    println!("\n🧯 Failures by Cause:");
+    for (kind, count) in failures {
+        println!(
+            "   {:<24}{:>9}  {:>5.1}%",
+            kind.name(),
+            count,
+            *count as f64 / failed_queries.max(1) as f64 * 100.0
+        );
+    }
+    if failures.contains_key(&FailureKind::PoolWaitTimeout) {
+        println!("   💡 Requests gave up waiting for a pooled connection: the load needs more than --connections");
+    }
+}
@@ -0,0 +1,93 @@
+use crate::TrafficIntensity;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Posts region annotations to the Grafana HTTP API so dashboards show which
+/// traffic phase the simulator was running at any moment
+#[derive(Debug)]
+pub struct GrafanaAnnotator {
+    client: reqwest::Client,
+    annotations_url: String,
+    token: Option<String>,
+    dashboard_uid: Option<String>,
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Annotation<'a> {
+    #[serde(rename = "dashboardUID", skip_serializing_if = "Option::is_none")]
+    dashboard_uid: Option<&'a str>,
+    time: u128,
+    time_end: u128,
+    tags: Vec<String>,
+    text: String,
+}
+
+impl GrafanaAnnotator {
+    /// Labels become `key:value` tags; the API token, if any, is read from the
+    /// `GRAFANA_TOKEN` environment variable
+    pub fn new(
+        url: &str,
+        dashboard_uid: Option<String>,
+        labels: &BTreeMap<String, String>,
+    ) -> Self {
+        let mut tags = vec!["traffic-simulator".to_string()];
+        tags.extend(
+            labels
+                .iter()
+                .map(|(key, value)| format!("{}:{}", key, value)),
+        );
+
+        Self {
+            client: reqwest::Client::new(),
+            annotations_url: format!("{}/api/annotations", url.trim_end_matches('/')),
+            token: std::env::var("GRAFANA_TOKEN").ok(),
+            dashboard_uid,
+            tags,
+        }
+    }
+
+    /// Annotate the region a phase is about to cover. The request runs in the
+    /// background so a slow Grafana never delays the phase itself.
+    pub fn annotate_phase(&self, name: &str, intensity: &TrafficIntensity, duration: Duration) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let intensity = format!("{:?}", intensity).to_lowercase();
+
+        let mut tags = self.tags.clone();
+        tags.push("phase".to_string());
+        tags.push(intensity.clone());
+        let annotation = Annotation {
+            dashboard_uid: self.dashboard_uid.as_deref(),
+            time: now.as_millis(),
+            time_end: (now + duration).as_millis(),
+            tags,
+            text: format!("{} ({} intensity)", name, intensity),
+        };
+
+        let mut request = self
+            .client
+            .post(&self.annotations_url)
+            .json(&annotation)
+            .timeout(Duration::from_secs(5));
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        tokio::spawn(async move {
+            match request.send().await {
+                Ok(response) if !response.status().is_success() => warn!(
+                    "Grafana annotation rejected with {}: {}",
+                    response.status(),
+                    response.text().await.unwrap_or_default()
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("Failed to post Grafana annotation: {}", e),
+            }
+        });
+    }
+}
@@ -0,0 +1,408 @@
+use crate::export::RunFile;
+use crate::timeline::{PhaseMark, TimelinePoint};
+use std::fmt::Write;
+
+/// Plot area of the charts, in SVG user units
+const CHART_WIDTH: f64 = 880.0;
+const CHART_HEIGHT: f64 = 240.0;
+const MARGIN_LEFT: f64 = 64.0;
+const MARGIN_RIGHT: f64 = 16.0;
+const MARGIN_TOP: f64 = 24.0;
+const MARGIN_BOTTOM: f64 = 36.0;
+
+/// Height of one bar of a bar chart, gap included
+const BAR_HEIGHT: f64 = 30.0;
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2rem auto; max-width: 960px; color: #1f2933; }
+h1 { font-size: 1.6rem; margin-bottom: 0.2rem; }
+h2 { font-size: 1.15rem; margin-top: 2rem; border-bottom: 1px solid #e4e7eb; padding-bottom: 0.3rem; }
+.meta { color: #616e7c; margin-top: 0; }
+.cards { display: flex; flex-wrap: wrap; gap: 0.8rem; }
+.card { border: 1px solid #e4e7eb; border-radius: 6px; padding: 0.6rem 1rem; min-width: 120px; }
+.card .value { font-size: 1.3rem; font-weight: 600; }
+.card .label { color: #616e7c; font-size: 0.85rem; }
+table { border-collapse: collapse; font-size: 0.9rem; }
+td, th { text-align: left; padding: 0.25rem 1rem 0.25rem 0; vertical-align: top; }
+th { color: #616e7c; font-weight: normal; }
+code { font-size: 0.85rem; word-break: break-all; }
+svg text { font-size: 11px; fill: #52606d; }
+.legend span { display: inline-block; margin-right: 1rem; font-size: 0.85rem; }
+.legend i { display: inline-block; width: 12px; height: 3px; margin-right: 4px; vertical-align: middle; }
+.empty { color: #616e7c; font-style: italic; }
+";
+
+/// One line of a time series chart
+struct Series<'a> {
+    name: &'a str,
+    color: &'a str,
+    values: Vec<f64>,
+}
+
+/// Write the run as a single HTML file with inline styles and SVG charts, so it
+/// opens anywhere without network access or other files
+pub fn write_html_report(
+    path: &str,
+    run: &RunFile,
+    timeline: &[TimelinePoint],
+    phases: &[PhaseMark],
+) -> anyhow::Result<()> {
+    std::fs::write(path, render(run, timeline, phases))
+        .map_err(|e| anyhow::anyhow!("Failed to write HTML report to '{}': {}", path, e))
+}
+
+fn render(run: &RunFile, timeline: &[TimelinePoint], phases: &[PhaseMark]) -> String {
+    let result = run.result;
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Traffic simulation {}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        run.started_at.format("%Y-%m-%d %H:%M UTC"),
+        STYLE
+    );
+    let _ = write!(
+        html,
+        "<h1>🚀 Traffic Simulation Results</h1>\n<p class=\"meta\">Started {} · {} backend · {} connections · {:.1}s measured</p>\n",
+        run.started_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        escape(&run.config.backend),
+        result.concurrent_connections,
+        result.duration_seconds
+    );
+
+    let success_rate = if result.total_queries == 0 {
+        0.0
+    } else {
+        result.successful_queries as f64 / result.total_queries as f64 * 100.0
+    };
+    html.push_str("<div class=\"cards\">\n");
+    for (label, value) in [
+        ("Total queries", result.total_queries.to_string()),
+        ("Success rate", format!("{:.2}%", success_rate)),
+        (
+            "Queries/second",
+            format!("{:.1}", result.queries_per_second),
+        ),
+        ("p50 latency", format!("{:.2}ms", result.p50_latency_ms)),
+        ("p95 latency", format!("{:.2}ms", result.p95_latency_ms)),
+        ("p99 latency", format!("{:.2}ms", result.p99_latency_ms)),
+    ] {
+        let _ = writeln!(
+            html,
+            "<div class=\"card\"><div class=\"value\">{}</div><div class=\"label\">{}</div></div>",
+            value, label
+        );
+    }
+    html.push_str("</div>\n");
+
+    let seconds: Vec<f64> = timeline.iter().map(|point| point.second as f64).collect();
+    html.push_str("<h2>Throughput</h2>\n");
+    html.push_str(&line_chart(
+        "queries/s",
+        &seconds,
+        &[
+            Series {
+                name: "Queries/second",
+                color: "#2680c2",
+                values: timeline.iter().map(|point| point.qps).collect(),
+            },
+            Series {
+                name: "Failures/second",
+                color: "#d64545",
+                values: timeline
+                    .iter()
+                    .map(|point| point.qps * point.error_rate)
+                    .collect(),
+            },
+        ],
+        phases,
+    ));
+
+    html.push_str("<h2>Latency over time</h2>\n");
+    html.push_str(&line_chart(
+        "ms",
+        &seconds,
+        &[
+            Series {
+                name: "p50",
+                color: "#3ebd93",
+                values: timeline.iter().map(|point| point.p50_ms).collect(),
+            },
+            Series {
+                name: "p95",
+                color: "#f0b429",
+                values: timeline.iter().map(|point| point.p95_ms).collect(),
+            },
+            Series {
+                name: "p99",
+                color: "#d64545",
+                values: timeline.iter().map(|point| point.p99_ms).collect(),
+            },
+        ],
+        phases,
+    ));
+
+    html.push_str("<h2>Latency percentiles</h2>\n");
+    html.push_str(&bar_chart(
+        &[
+            ("p50".to_string(), result.p50_latency_ms),
+            ("p95".to_string(), result.p95_latency_ms),
+            ("p99".to_string(), result.p99_latency_ms),
+            ("p99.9".to_string(), result.p999_latency_ms),
+            ("p99.99".to_string(), result.p9999_latency_ms),
+            ("max".to_string(), result.max_latency_ms),
+        ],
+        "ms",
+        "#2680c2",
+    ));
+
+    html.push_str("<h2>Failures</h2>\n");
+    match result
+        .failures
+        .as_ref()
+        .filter(|failures| !failures.is_empty())
+    {
+        Some(failures) => {
+            let _ = writeln!(
+                html,
+                "<p>{} of {} queries failed ({:.2}%).</p>",
+                result.failed_queries,
+                result.total_queries,
+                100.0 - success_rate
+            );
+            let bars: Vec<(String, f64)> = failures
+                .iter()
+                .map(|(kind, count)| (kind.name().to_string(), *count as f64))
+                .collect();
+            html.push_str(&bar_chart(&bars, "", "#d64545"));
+        }
+        None => html.push_str("<p class=\"empty\">No queries failed.</p>\n"),
+    }
+
+    if !phases.is_empty() {
+        html.push_str("<h2>Phases</h2>\n<table>\n<tr><th>Start</th><th>Phase</th></tr>\n");
+        for phase in phases {
+            let _ = writeln!(
+                html,
+                "<tr><td>{:.1}s</td><td>{}</td></tr>",
+                phase.second,
+                escape(&phase.name)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("<h2>Configuration</h2>\n<table>\n");
+    let config = &run.config;
+    let mut rows = vec![
+        ("Database", config.database_url.clone()),
+        ("Workload", config.workload.clone()),
+        ("Query type", config.query_type.clone()),
+        ("Connections", config.connections.to_string()),
+        ("Duration", format!("{}s", config.duration_seconds)),
+        ("Warmup", format!("{}s", config.warmup_seconds)),
+        ("Seed", config.seed.to_string()),
+    ];
+    let metadata = &run.metadata;
+    if let Some(server_version) = &metadata.server_version {
+        rows.push(("Server", server_version.clone()));
+    }
+    rows.push((
+        "Simulator",
+        match &metadata.git_commit {
+            Some(commit) => format!("{} ({})", metadata.simulator_version, commit),
+            None => metadata.simulator_version.clone(),
+        },
+    ));
+    rows.push(("Host", metadata.hostname.clone()));
+    rows.extend(
+        run.labels
+            .iter()
+            .map(|(key, value)| ("Label", format!("{}={}", key, value))),
+    );
+    for (name, value) in rows {
+        let _ = writeln!(
+            html,
+            "<tr><th>{}</th><td>{}</td></tr>",
+            name,
+            escape(&value)
+        );
+    }
+    let _ = writeln!(
+        html,
+        "<tr><th>Command</th><td><code>{}</code></td></tr>\n</table>",
+        escape(&config.command_line.join(" "))
+    );
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Time series chart of `series` over `seconds`, with a dashed line where each
+/// phase started
+fn line_chart(unit: &str, seconds: &[f64], series: &[Series], phases: &[PhaseMark]) -> String {
+    if seconds.is_empty() {
+        return "<p class=\"empty\">No queries completed.</p>\n".to_string();
+    }
+    let width = MARGIN_LEFT + CHART_WIDTH + MARGIN_RIGHT;
+    let height = MARGIN_TOP + CHART_HEIGHT + MARGIN_BOTTOM;
+    let max_x = seconds.last().copied().unwrap_or(0.0).max(1.0);
+    let max_y = nice_ceiling(
+        series
+            .iter()
+            .flat_map(|series| series.values.iter().copied())
+            .fold(0.0, f64::max),
+    );
+    let x = |second: f64| MARGIN_LEFT + second / max_x * CHART_WIDTH;
+    let y = |value: f64| MARGIN_TOP + CHART_HEIGHT - value / max_y * CHART_HEIGHT;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg viewBox=\"0 0 {} {}\" width=\"100%\" role=\"img\">",
+        width, height
+    );
+    for tick in 0..=4 {
+        let value = max_y * tick as f64 / 4.0;
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{:.1}\" x2=\"{:.1}\" y1=\"{:.1}\" y2=\"{:.1}\" stroke=\"#e4e7eb\"/>\
+             <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+            MARGIN_LEFT,
+            MARGIN_LEFT + CHART_WIDTH,
+            y(value),
+            y(value),
+            MARGIN_LEFT - 6.0,
+            y(value) + 4.0,
+            format_tick(value)
+        );
+    }
+    for tick in 0..=6 {
+        let second = max_x * tick as f64 / 6.0;
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{:.0}s</text>",
+            x(second),
+            MARGIN_TOP + CHART_HEIGHT + 16.0,
+            second
+        );
+    }
+    let _ = writeln!(
+        svg,
+        "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+        MARGIN_LEFT,
+        MARGIN_TOP - 10.0,
+        escape(unit)
+    );
+
+    for phase in phases {
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{x:.1}\" x2=\"{x:.1}\" y1=\"{:.1}\" y2=\"{:.1}\" stroke=\"#9aa5b1\" stroke-dasharray=\"4 3\"/>\
+             <text x=\"{:.1}\" y=\"{:.1}\"><title>{name}</title>{name}</text>",
+            MARGIN_TOP,
+            MARGIN_TOP + CHART_HEIGHT,
+            x(phase.second.min(max_x)) + 3.0,
+            MARGIN_TOP + 10.0,
+            x = x(phase.second.min(max_x)),
+            name = escape(&phase.name)
+        );
+    }
+
+    for series in series {
+        let points: Vec<String> = seconds
+            .iter()
+            .zip(&series.values)
+            .map(|(second, value)| format!("{:.1},{:.1}", x(*second), y(*value)))
+            .collect();
+        let _ = writeln!(
+            svg,
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"/>",
+            series.color,
+            points.join(" ")
+        );
+    }
+    svg.push_str("</svg>\n<div class=\"legend\">");
+    for series in series {
+        let _ = write!(
+            svg,
+            "<span><i style=\"background:{}\"></i>{}</span>",
+            series.color,
+            escape(series.name)
+        );
+    }
+    svg.push_str("</div>\n");
+    svg
+}
+
+/// Horizontal bars, one per labelled value
+fn bar_chart(bars: &[(String, f64)], unit: &str, color: &str) -> String {
+    let label_width = 160.0;
+    let width = label_width + CHART_WIDTH * 0.75;
+    let height = bars.len() as f64 * BAR_HEIGHT;
+    let max = bars.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg viewBox=\"0 0 {} {}\" width=\"100%\" role=\"img\">",
+        width, height
+    );
+    for (index, (label, value)) in bars.iter().enumerate() {
+        let top = index as f64 * BAR_HEIGHT;
+        let length = if max > 0.0 {
+            value / max * (width - label_width - 90.0)
+        } else {
+            0.0
+        };
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>\
+             <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\" rx=\"2\"/>\
+             <text x=\"{:.1}\" y=\"{:.1}\">{}{}</text>",
+            label_width - 8.0,
+            top + BAR_HEIGHT / 2.0 + 4.0,
+            escape(label),
+            label_width,
+            top + 5.0,
+            length,
+            BAR_HEIGHT - 10.0,
+            color,
+            label_width + length + 6.0,
+            top + BAR_HEIGHT / 2.0 + 4.0,
+            format_tick(*value),
+            escape(unit)
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Smallest 1, 2 or 5 times a power of ten at or above `value`, so axis ticks
+/// land on round numbers
+fn nice_ceiling(value: f64) -> f64 {
+    if value <= 0.0 {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(value.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|step| step * magnitude)
+        .find(|ceiling| *ceiling >= value)
+        .unwrap_or(10.0 * magnitude)
+}
+
+fn format_tick(value: f64) -> String {
+    if value >= 100.0 || value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
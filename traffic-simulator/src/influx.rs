@@ -0,0 +1,120 @@
+use crate::interval_sink::{self, IntervalAggregator};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Measurement name every interval point is written under
+const MEASUREMENT: &str = "traffic_simulator";
+
+/// Pushes one line-protocol point per interval to an InfluxDB v2 (or Telegraf
+/// `influxdb_v2_listener`) write endpoint
+#[derive(Debug)]
+pub struct InfluxSink {
+    client: reqwest::Client,
+    write_url: String,
+    token: Option<String>,
+    tags: String,
+    interval: IntervalAggregator,
+}
+
+impl InfluxSink {
+    /// Labels become tags on every point; the API token, if any, is read from
+    /// the `INFLUX_TOKEN` environment variable
+    pub fn new(
+        url: &str,
+        bucket: &str,
+        org: Option<&str>,
+        labels: &BTreeMap<String, String>,
+    ) -> Self {
+        let mut write_url = format!(
+            "{}/api/v2/write?bucket={}&precision=ns",
+            url.trim_end_matches('/'),
+            query_escape(bucket)
+        );
+        if let Some(org) = org {
+            write_url.push_str(&format!("&org={}", query_escape(org)));
+        }
+
+        Self {
+            client: reqwest::Client::new(),
+            write_url,
+            token: std::env::var("INFLUX_TOKEN").ok(),
+            tags: labels
+                .iter()
+                .map(|(key, value)| format!(",{}={}", key, tag_escape(value)))
+                .collect(),
+            interval: IntervalAggregator::new(),
+        }
+    }
+
+    pub fn record(&self, latency: Duration, success: bool) {
+        self.interval.record(latency, success);
+    }
+
+    /// Write the queries recorded since the last flush as one point
+    pub async fn flush(&self, pending: usize) -> anyhow::Result<()> {
+        let summary = self.interval.take();
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+
+        let mut fields = format!(
+            "queries={}i,failures={}i,qps={:.3}",
+            summary.queries, summary.failures, summary.qps
+        );
+        // Latency is left out of intervals without successful queries rather than
+        // reported as 0
+        if let Some(latency) = &summary.latency {
+            fields.push_str(&format!(
+                ",latency_avg_ms={:.3},latency_p50_ms={:.3},latency_p95_ms={:.3},latency_p99_ms={:.3},latency_max_ms={:.3}",
+                latency.average_ms, latency.p50_ms, latency.p95_ms, latency.p99_ms, latency.max_ms
+            ));
+        }
+        let line = format!(
+            "{MEASUREMENT}{} {},pending={}i {}",
+            self.tags, fields, pending, timestamp
+        );
+
+        let mut request = self.client.post(&self.write_url).body(line);
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("Token {}", token));
+        }
+        let response = request.timeout(Duration::from_secs(5)).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "InfluxDB write returned {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Push a point every `interval` until aborted
+pub async fn run_influx_writer(ctx: std::sync::Arc<crate::QueryContext>, interval: Duration) {
+    let Some(sink) = &ctx.influx else { return };
+    interval_sink::flush_every(interval, "InfluxDB", || {
+        sink.flush(ctx.pending_work.current())
+    })
+    .await;
+}
+
+/// Escape commas, spaces and equals signs in a tag value
+fn tag_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+fn query_escape(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -0,0 +1,106 @@
+use crate::recorder::LatencyHistogram;
+use crate::LatencySummary;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Queries finished since the last push, shared by the sinks that write one
+/// aggregate per interval (--influx-url, --remote-write-url); each sink only
+/// encodes the `IntervalSummary` it is handed
+#[derive(Debug)]
+pub struct IntervalAggregator {
+    interval: Mutex<IntervalStats>,
+}
+
+#[derive(Debug)]
+struct IntervalStats {
+    started: Instant,
+    /// Successful queries only; a failure's time to error is not a latency
+    latency: LatencyHistogram,
+    failures: u64,
+    /// Since the start of the run, for cumulative counters
+    total_queries: u64,
+    total_failures: u64,
+}
+
+/// One interval, ready to encode
+#[derive(Debug)]
+pub struct IntervalSummary {
+    /// Queries finished in the interval, failed ones included
+    pub queries: u64,
+    pub failures: u64,
+    pub qps: f64,
+    /// `None` when no query succeeded in the interval; sinks leave latency out
+    /// rather than report it as 0
+    pub latency: Option<LatencySummary>,
+    pub total_queries: u64,
+    pub total_failures: u64,
+}
+
+impl IntervalAggregator {
+    pub fn new() -> Self {
+        Self {
+            interval: Mutex::new(IntervalStats {
+                started: Instant::now(),
+                latency: LatencyHistogram::default(),
+                failures: 0,
+                total_queries: 0,
+                total_failures: 0,
+            }),
+        }
+    }
+
+    pub fn record(&self, latency: Duration, success: bool) {
+        let mut interval = self.interval.lock().unwrap();
+        interval.total_queries += 1;
+        if success {
+            interval.latency.record(latency);
+        } else {
+            interval.failures += 1;
+            interval.total_failures += 1;
+        }
+    }
+
+    /// Close the current interval and start the next
+    pub fn take(&self) -> IntervalSummary {
+        let stats = {
+            let mut interval = self.interval.lock().unwrap();
+            let next = IntervalStats {
+                started: Instant::now(),
+                latency: LatencyHistogram::default(),
+                failures: 0,
+                total_queries: interval.total_queries,
+                total_failures: interval.total_failures,
+            };
+            std::mem::replace(&mut *interval, next)
+        };
+        let queries = stats.latency.count() + stats.failures;
+        IntervalSummary {
+            queries,
+            failures: stats.failures,
+            qps: queries as f64 / stats.started.elapsed().as_secs_f64().max(f64::EPSILON),
+            latency: (stats.latency.count() > 0).then(|| stats.latency.summary()),
+            total_queries: stats.total_queries,
+            total_failures: stats.total_failures,
+        }
+    }
+}
+
+/// Call `flush` every `interval` until aborted; failed writes are logged and the
+/// run carries on
+pub async fn flush_every<F, Fut>(interval: Duration, destination: &str, mut flush: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+        if let Err(e) = flush().await {
+            warn!("Failed to push metrics to {}: {}", destination, e);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::recorder::MetricsRecorder;
+use crate::{calculate_operational_result, QueryMetric, SimulationResult};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How each --report-interval summary is printed
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    /// One log line
+    Text,
+    /// One JSON document per line on standard output, for scripts and log shippers
+    Json,
+}
+
+/// Summary of one --report-interval of a long-running simulation
+#[derive(Serialize)]
+struct IntervalReport<'a> {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    total_queries: usize,
+    failed_queries: usize,
+    /// Percentage of the interval's queries that succeeded; 100 when there were none
+    success_rate: f64,
+    /// `None` when none of the interval's queries succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<&'a SimulationResult>,
+}
+
+/// Queries of the interval in progress
+#[derive(Debug)]
+struct Interval {
+    start: DateTime<Utc>,
+    started: Instant,
+    recorder: MetricsRecorder,
+}
+
+impl Interval {
+    fn new() -> Self {
+        Self {
+            start: Utc::now(),
+            started: Instant::now(),
+            recorder: MetricsRecorder::default(),
+        }
+    }
+}
+
+/// Reports the queries of every --report-interval on their own, so a run that
+/// lasts days shows how each hour went instead of only its overall totals
+#[derive(Debug)]
+pub struct IntervalReporter {
+    interval: Duration,
+    connections: usize,
+    robust_stats: bool,
+    format: ReportFormat,
+    /// --interval-output: JSON Lines file the reports are appended to
+    output: Option<String>,
+    current: Mutex<Interval>,
+}
+
+impl IntervalReporter {
+    pub fn new(
+        interval: Duration,
+        connections: usize,
+        robust_stats: bool,
+        format: ReportFormat,
+        output: Option<String>,
+    ) -> Self {
+        Self {
+            interval,
+            connections,
+            robust_stats,
+            format,
+            output,
+            current: Mutex::new(Interval::new()),
+        }
+    }
+
+    pub fn record(&self, metric: &QueryMetric) {
+        self.current.lock().unwrap().recorder.record(metric);
+    }
+
+    /// Report the interval in progress and start the next one
+    pub fn report(&self) {
+        let interval = std::mem::replace(&mut *self.current.lock().unwrap(), Interval::new());
+        let total_queries = interval.recorder.total_queries;
+        let failed_queries = total_queries - interval.recorder.successful_queries;
+        let result = calculate_operational_result(
+            interval.recorder,
+            interval.started.elapsed(),
+            self.connections,
+            0.0,
+            self.robust_stats,
+        )
+        .ok();
+        let success_rate = if total_queries == 0 {
+            100.0
+        } else {
+            (total_queries - failed_queries) as f64 / total_queries as f64 * 100.0
+        };
+        let report = IntervalReport {
+            start: interval.start,
+            end: Utc::now(),
+            total_queries,
+            failed_queries,
+            success_rate,
+            result: result.as_ref(),
+        };
+
+        match (self.format, &result) {
+            (ReportFormat::Json, _) => match serde_json::to_string(&report) {
+                Ok(line) => println!("{}", line),
+                Err(e) => warn!("Failed to serialize interval report: {}", e),
+            },
+            (ReportFormat::Text, Some(result)) => info!(
+                "📋 Interval {} – {}: {} queries, {:.2}% successful, {:.1} QPS, p50 {:.2}ms, p95 {:.2}ms, p99 {:.2}ms",
+                report.start.format("%Y-%m-%d %H:%M:%S"),
+                report.end.format("%H:%M:%S"),
+                total_queries,
+                success_rate,
+                result.queries_per_second,
+                result.p50_latency_ms,
+                result.p95_latency_ms,
+                result.p99_latency_ms
+            ),
+            (ReportFormat::Text, None) => info!(
+                "📋 Interval {} – {}: {} queries, none successful",
+                report.start.format("%Y-%m-%d %H:%M:%S"),
+                report.end.format("%H:%M:%S"),
+                total_queries
+            ),
+        }
+        if let Some(path) = &self.output {
+            if let Err(e) = append_line(path, &report) {
+                warn!("Failed to append interval report to {}: {}", path, e);
+            }
+        }
+    }
+}
+
+fn append_line(path: &str, report: &IntervalReport) -> anyhow::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(report)?)?;
+    Ok(())
+}
+
+/// Report every interval until aborted; the caller reports the final, partial one
+pub async fn run_interval_reports(reporter: Arc<IntervalReporter>) {
+    let start = tokio::time::Instant::now() + reporter.interval;
+    let mut ticker = tokio::time::interval_at(start, reporter.interval);
+    loop {
+        ticker.tick().await;
+        reporter.report();
+    }
+}
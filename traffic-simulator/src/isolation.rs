@@ -0,0 +1,100 @@
+use crate::failures::FailureKind;
+use crate::retry::{ErrorClass, RetryClassStats};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio_postgres::Config;
+
+/// Transaction isolation level of the workload's sessions (--isolation)
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IsolationLevel {
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+    pub fn sql_name(self) -> &'static str {
+        match self {
+            IsolationLevel::ReadCommitted => "read committed",
+            IsolationLevel::RepeatableRead => "repeatable read",
+            IsolationLevel::Serializable => "serializable",
+        }
+    }
+}
+
+/// Make `level` the default of every transaction on the connection, including the
+/// ones benchmark presets and `--query-type transaction` begin themselves. Set as a
+/// startup option like the statement timeout, so it costs no round trip.
+pub fn configure(pg_config: &mut Config, level: Option<IsolationLevel>) {
+    let Some(level) = level else { return };
+    // Spaces inside a startup option value are escaped with a backslash
+    let setting = format!(
+        "-c default_transaction_isolation={}",
+        level.sql_name().replace(' ', "\\ ")
+    );
+    let options = match pg_config.get_options() {
+        Some(options) => format!("{} {}", options, setting),
+        None => setting,
+    };
+    pg_config.options(&options);
+}
+
+/// How often the isolation level made the server abort a transaction
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct IsolationReport {
+    pub level: IsolationLevel,
+    /// Queries that failed with a serialization failure (SQLSTATE 40001)
+    pub serialization_failures: u64,
+    /// Queries that failed with a deadlock (SQLSTATE 40P01)
+    pub deadlocks: u64,
+    /// Serialization failures and deadlocks that were retried (--retry-on)
+    pub retried_conflicts: u64,
+    /// Failed serialization failures and deadlocks, as a percentage of all queries
+    pub conflict_rate: f64,
+}
+
+pub fn report(
+    level: IsolationLevel,
+    total_queries: usize,
+    failures: Option<&BTreeMap<FailureKind, u64>>,
+    retries: Option<&BTreeMap<ErrorClass, RetryClassStats>>,
+) -> IsolationReport {
+    let count = |kind| {
+        failures
+            .and_then(|failures| failures.get(&kind))
+            .copied()
+            .unwrap_or(0)
+    };
+    let serialization_failures = count(FailureKind::SerializationFailure);
+    let deadlocks = count(FailureKind::Deadlock);
+    IsolationReport {
+        level,
+        serialization_failures,
+        deadlocks,
+        retried_conflicts: retries
+            .and_then(|retries| retries.get(&ErrorClass::SerializationFailure))
+            .map_or(0, |stats| stats.retries),
+        conflict_rate: crate::baseline::error_rate(
+            (serialization_failures + deadlocks) as usize,
+            total_queries,
+        ),
+    }
+}
+
+pub fn display_isolation(report: &IsolationReport) {
+    println!("\n🔒 Isolation ({}):", report.level.sql_name());
+    println!(
+        "   Serialization Failures: {:>12}",
+        report.serialization_failures
+    );
+    println!("   Deadlocks:              {:>12}", report.deadlocks);
+    println!("   Conflict Rate:          {:>11.2}%", report.conflict_rate);
+    if report.retried_conflicts > 0 {
+        println!(
+            "   Retried Conflicts:      {:>12}",
+            report.retried_conflicts
+        );
+    }
+}
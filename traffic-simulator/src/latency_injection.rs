@@ -0,0 +1,100 @@
+use crate::sampling;
+use crate::SimulationResult;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::time::Duration;
+
+/// "laten", see [`crate::sampling`]
+const LATENCY_SEED_SALT: u64 = 0x6c61_7465_6e00_0000;
+
+/// `--inject-latency`: a delay slept before every attempt, standing in for the
+/// distance between the client and the database
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InjectedLatency {
+    delay: Duration,
+    /// Delays are drawn uniformly from `delay ± jitter`, never below zero
+    jitter: Duration,
+}
+
+/// Parse `20ms`, `20ms±5ms` or its ASCII spelling `20ms+-5ms`
+pub fn parse_injected_latency(value: &str) -> Result<InjectedLatency, String> {
+    let (delay, jitter) = match value.split_once('±').or_else(|| value.split_once("+-")) {
+        Some((delay, jitter)) => (delay, Some(jitter)),
+        None => (value, None),
+    };
+    Ok(InjectedLatency {
+        delay: crate::parse_duration(delay)?,
+        jitter: jitter
+            .map(crate::parse_duration)
+            .transpose()?
+            .unwrap_or_default(),
+    })
+}
+
+impl InjectedLatency {
+    /// Delay before attempt `attempt` of the query with `seed`
+    pub fn sample(&self, seed: u64, attempt: u32) -> Duration {
+        if self.jitter.is_zero() {
+            return self.delay;
+        }
+        let mut rng = sampling::attempt_rng(seed, LATENCY_SEED_SALT, attempt);
+        let jitter = self.jitter.as_secs_f64();
+        let offset = rng.gen_range(-jitter..=jitter);
+        Duration::from_secs_f64((self.delay.as_secs_f64() + offset).max(0.0))
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{:.0}ms ±{:.0}ms",
+            self.delay.as_secs_f64() * 1000.0,
+            self.jitter.as_secs_f64() * 1000.0
+        )
+    }
+
+    /// How much of the run's average latency the injected delay accounts for
+    pub fn report(&self, average_latency_ms: f64) -> InjectedLatencyReport {
+        let delay_ms = self.delay.as_secs_f64() * 1000.0;
+        InjectedLatencyReport {
+            delay_ms,
+            jitter_ms: self.jitter.as_secs_f64() * 1000.0,
+            share_of_latency_percent: if average_latency_ms > 0.0 {
+                (delay_ms / average_latency_ms * 100.0).min(100.0)
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// Report the injected latency of a finished run, and take it out of the time
+/// attributed to the database
+pub fn annotate(result: &mut SimulationResult, latency: Option<InjectedLatency>) {
+    let Some(latency) = latency else { return };
+    let report = latency.report(result.average_latency_ms);
+    result.database_processing_time_ms -= report.delay_ms;
+    result.injected_latency = Some(report);
+}
+
+/// The simulated client distance of a run with --inject-latency
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct InjectedLatencyReport {
+    /// Mean delay slept before each attempt
+    pub delay_ms: f64,
+    pub jitter_ms: f64,
+    /// Mean delay as a share of the average query latency; the rest is the
+    /// database, the real network and the client
+    pub share_of_latency_percent: f64,
+}
+
+pub fn display_injected_latency(report: &InjectedLatencyReport) {
+    println!(
+        "🌍 Injected Client Latency ({:.0}ms ±{:.0}ms):",
+        report.delay_ms, report.jitter_ms
+    );
+    println!(
+        "   Share of Latency:       {:>7.1}%",
+        report.share_of_latency_percent
+    );
+    println!();
+}
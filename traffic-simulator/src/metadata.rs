@@ -0,0 +1,74 @@
+use crate::{mask_password, Args};
+use clap::CommandFactory;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// What a result was produced with and against, so an archived run file can still
+/// be interpreted months later
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RunMetadata {
+    /// Release of the simulator
+    pub simulator_version: String,
+    /// Commit the simulator was built from; absent for builds outside a git checkout
+    pub git_commit: Option<String>,
+    /// Machine that generated the load
+    pub hostname: String,
+    /// The database server's own version string, e.g. `SELECT version()`
+    pub server_version: Option<String>,
+    /// Every option as the run resolved it, keyed by flag name: the command line
+    /// over any --config profile over the defaults, with passwords in connection
+    /// strings masked
+    pub effective_config: BTreeMap<String, Vec<String>>,
+}
+
+impl RunMetadata {
+    pub fn collect(args: &Args, server_version: Option<String>) -> Self {
+        Self {
+            simulator_version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: option_env!("SIMULATOR_GIT_COMMIT").map(str::to_string),
+            hostname: hostname(),
+            server_version,
+            effective_config: effective_config(args),
+        }
+    }
+}
+
+/// Re-parse the run's expanded command line to read back each option's value,
+/// defaults included
+fn effective_config(args: &Args) -> BTreeMap<String, Vec<String>> {
+    let command = Args::command();
+    let Ok(matches) = command.clone().try_get_matches_from(&args.argv) else {
+        return BTreeMap::new();
+    };
+    command
+        .get_arguments()
+        .filter_map(|arg| {
+            let name = arg.get_long()?;
+            let values = matches.get_raw(arg.get_id().as_str())?;
+            let values = values
+                .map(|value| {
+                    let value = value.to_string_lossy();
+                    if value.contains("://") {
+                        mask_password(&value)
+                    } else {
+                        value.into_owned()
+                    }
+                })
+                .collect();
+            Some((name.to_string(), values))
+        })
+        .collect()
+}
+
+/// This machine's host name, telling apart load generators of the same run
+pub fn hostname() -> String {
+    let mut buffer = [0u8; 256];
+    // SAFETY: gethostname writes at most `buffer.len()` bytes into the buffer
+    let result = unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) };
+    if result != 0 {
+        return "unknown".to_string();
+    }
+    let end = buffer.iter().position(|b| *b == 0).unwrap_or(buffer.len());
+    String::from_utf8_lossy(&buffer[..end]).into_owned()
+}
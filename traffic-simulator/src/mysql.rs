@@ -0,0 +1,378 @@
+use crate::driver::DatabaseDriver;
+use crate::skew::{self, KeySkew};
+use crate::stores::{self, StoreWeights};
+use crate::transaction;
+use crate::writes::{WriteCounts, WriteKind};
+use crate::{FailureStage, QueryAttempt, QueryContext, QueryType, UUID_ORDER_NUMBER_ATTEMPT};
+use futures::future::BoxFuture;
+use mysql_async::prelude::Queryable;
+use mysql_async::{Conn, Opts, OptsBuilder, Pool, PoolConstraints, PoolOpts, Row};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Number of seeded orders, matching the order id range the workloads draw from
+const SEED_ORDERS: i64 = 100_000;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS orders (
+        order_id INT AUTO_INCREMENT PRIMARY KEY,
+        order_number VARCHAR(50) NOT NULL UNIQUE,
+        from_store_id INT,
+        to_store_id INT NOT NULL,
+        product_id INT NOT NULL,
+        quantity_cases INT NOT NULL,
+        order_status VARCHAR(20) NOT NULL DEFAULT 'pending_review'
+            CHECK (order_status IN ('pending_review', 'approved', 'fulfilled', 'cancelled')),
+        requested_by INT NOT NULL,
+        approved_by INT,
+        order_date TIMESTAMP NULL DEFAULT CURRENT_TIMESTAMP,
+        approved_date TIMESTAMP NULL,
+        fulfilled_date TIMESTAMP NULL,
+        notes TEXT,
+        version INT DEFAULT 1,
+        INDEX idx_orders_status (order_status),
+        INDEX idx_orders_to_store (to_store_id)
+    )
+";
+
+/// Server error codes the failure and retry classes are told apart by
+pub const ER_DUP_ENTRY: u16 = 1062;
+pub const ER_LOCK_DEADLOCK: u16 = 1213;
+pub const ER_LOCK_WAIT_TIMEOUT: u16 = 1205;
+pub const ER_QUERY_INTERRUPTED: u16 = 1317;
+pub const ER_QUERY_TIMEOUT: u16 = 3024;
+pub const ER_CON_COUNT_ERROR: u16 = 1040;
+pub const ER_SERVER_SHUTDOWN: u16 = 1053;
+
+/// Whether `--database-url` names a MySQL server, which selects this backend
+pub fn is_mysql_url(database_url: &str) -> bool {
+    database_url.starts_with("mysql://")
+}
+
+/// MySQL and MySQL-compatible servers (MariaDB, TiDB, Aurora MySQL, ...), running
+/// the orders workload in MySQL's dialect so results compare with PostgreSQL runs
+#[derive(Clone)]
+pub struct MysqlBackend {
+    pool: Pool,
+}
+
+impl MysqlBackend {
+    /// Create a pool of up to `connections` connections and the orders table,
+    /// seeded when empty
+    pub async fn connect(database_url: &str, connections: usize) -> anyhow::Result<Self> {
+        let constraints = PoolConstraints::new(0, connections.max(1))
+            .expect("zero minimum never exceeds the maximum");
+        let opts = OptsBuilder::from_opts(Opts::from_url(database_url)?)
+            .pool_opts(PoolOpts::default().with_constraints(constraints));
+        let backend = Self {
+            pool: Pool::new(opts),
+        };
+        backend.bootstrap().await?;
+
+        Ok(backend)
+    }
+
+    async fn bootstrap(&self) -> anyhow::Result<()> {
+        let mut conn = self.pool.get_conn().await?;
+        conn.query_drop(SCHEMA).await?;
+
+        let existing: Option<i64> = conn.query_first("SELECT order_id FROM orders LIMIT 1").await?;
+        if existing.is_none() {
+            conn.query_drop(format!("SET SESSION cte_max_recursion_depth = {}", SEED_ORDERS))
+                .await?;
+            conn.exec_drop(
+                "INSERT INTO orders (order_number, to_store_id, product_id, quantity_cases, requested_by, order_status)
+                 WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < ?)
+                 SELECT CONCAT('SEED', n), 1 + n % 10, 1 + n % 50, 1 + n % 20, 1 + n % 5,
+                        CASE n % 3 WHEN 0 THEN 'pending_review' WHEN 1 THEN 'approved' ELSE 'fulfilled' END
+                 FROM seq",
+                (SEED_ORDERS,),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        ctx: &QueryContext,
+        query_type: &QueryType,
+        seed: u64,
+        attempt: u32,
+    ) -> QueryAttempt {
+        let connection_start = Instant::now();
+        let conn = self.pool.get_conn().await;
+        let connection_time = connection_start.elapsed();
+
+        let mut writes = WriteCounts::default();
+        let (error, query_execution_time) = match conn {
+            Ok(mut conn) => {
+                let query_start = Instant::now();
+                let result = execute_operational_query(
+                    &mut conn,
+                    ctx,
+                    query_type,
+                    seed,
+                    attempt,
+                    &mut writes,
+                )
+                .await;
+                let error = result.err().map(|e| (FailureStage::Query, e));
+                (error, query_start.elapsed())
+            }
+            Err(e) => (Some((FailureStage::Connection, e.into())), Duration::ZERO),
+        };
+
+        QueryAttempt {
+            connection_time,
+            query_execution_time,
+            connection_recycled: false,
+            error,
+            writes,
+            think_time: Duration::ZERO,
+        }
+    }
+}
+
+impl DatabaseDriver for MysqlBackend {
+    fn test_connection(&self) -> BoxFuture<'_, anyhow::Result<()>> {
+        Box::pin(async move {
+            let mut conn = self.pool.get_conn().await?;
+            conn.query_drop("SELECT 1").await?;
+            Ok(())
+        })
+    }
+
+    fn server_version(&self) -> BoxFuture<'_, anyhow::Result<String>> {
+        Box::pin(async move {
+            let mut conn = self.pool.get_conn().await?;
+            let version: Option<String> = conn.query_first("SELECT VERSION()").await?;
+            version.ok_or_else(|| anyhow::anyhow!("VERSION() returned no row"))
+        })
+    }
+
+    fn execute_attempt<'a>(
+        &'a self,
+        ctx: &'a QueryContext,
+        query_type: &'a QueryType,
+        seed: u64,
+        attempt: u32,
+    ) -> BoxFuture<'a, QueryAttempt> {
+        Box::pin(self.attempt(ctx, query_type, seed, attempt))
+    }
+}
+
+async fn execute_operational_query(
+    conn: &mut Conn,
+    ctx: &QueryContext,
+    query_type: &QueryType,
+    seed: u64,
+    attempt: u32,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<usize> {
+    let stores = ctx.stores_for(seed).map(Arc::as_ref);
+    match query_type.resolve(seed) {
+        QueryType::Select => execute_select(conn, seed, ctx.skew).await,
+        QueryType::Insert => execute_insert(conn, ctx, seed, attempt, stores, writes).await,
+        QueryType::Update => execute_update(conn, seed, ctx.skew, writes).await,
+        QueryType::Search => execute_search(conn, seed, stores, ctx.skew).await,
+        QueryType::Mixed => unreachable!("Mixed resolves to a single statement"),
+        QueryType::Transaction => execute_transaction(conn, ctx, seed, attempt, writes).await,
+        query_type @ (QueryType::Workflow
+        | QueryType::Inventory
+        | QueryType::Catalog
+        | QueryType::Store) => Err(anyhow::anyhow!(
+            "--query-type {} requires the PostgreSQL backend",
+            query_type.name()
+        )),
+    }
+}
+
+/// Same transaction shape as the PostgreSQL workload; InnoDB detects deadlocks
+/// between concurrent transactions and rolls one of them back
+async fn execute_transaction(
+    conn: &mut Conn,
+    ctx: &QueryContext,
+    seed: u64,
+    attempt: u32,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<usize> {
+    let tracker = &ctx.transactions;
+    let stores = ctx.stores_for(seed).map(Arc::as_ref);
+    conn.query_drop("BEGIN").await?;
+
+    let mut rows = 0;
+    for (statement_seed, kind) in transaction::statement_plan(seed, tracker.statements) {
+        let result = match kind {
+            WriteKind::Insert => {
+                execute_insert(conn, ctx, statement_seed, attempt, stores, writes).await
+            }
+            _ => execute_update(conn, statement_seed, ctx.skew, writes).await,
+        };
+        match result {
+            Ok(statement_rows) => rows += statement_rows,
+            Err(e) => {
+                let _ = conn.query_drop("ROLLBACK").await;
+                tracker.record_rollback(&e);
+                return Err(e);
+            }
+        }
+    }
+
+    let commit_start = Instant::now();
+    if let Err(e) = conn.query_drop("COMMIT").await {
+        let _ = conn.query_drop("ROLLBACK").await;
+        let e = e.into();
+        tracker.record_rollback(&e);
+        return Err(e);
+    }
+    tracker.record_commit(commit_start.elapsed());
+
+    Ok(rows)
+}
+
+async fn execute_select(conn: &mut Conn, seed: u64, skew: KeySkew) -> anyhow::Result<usize> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let order_id = skew.pick_i64(skew::ORDER_KEY_COUNT.into(), &mut rng);
+
+    let rows: Vec<Row> = conn
+        .exec(
+            "SELECT order_id, order_number, order_status, quantity_cases FROM orders WHERE order_id = ?",
+            (order_id,),
+        )
+        .await?;
+
+    Ok(rows.len())
+}
+
+async fn execute_search(
+    conn: &mut Conn,
+    seed: u64,
+    stores: Option<&StoreWeights>,
+    skew: KeySkew,
+) -> anyhow::Result<usize> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let to_store_id = stores::choose_store(stores, skew, &mut rng);
+    let status = ["pending_review", "approved", "fulfilled"][rng.gen_range(0..3)];
+
+    let rows: Vec<Row> = conn
+        .exec(
+            "SELECT order_id, order_number, order_status, quantity_cases FROM orders
+             WHERE to_store_id = ? AND order_status = ? ORDER BY order_date DESC LIMIT 20",
+            (to_store_id, status),
+        )
+        .await?;
+
+    Ok(rows.len())
+}
+
+async fn execute_insert(
+    conn: &mut Conn,
+    ctx: &QueryContext,
+    seed: u64,
+    attempt: u32,
+    stores: Option<&StoreWeights>,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<usize> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let to_store_id = stores::choose_store(stores, ctx.skew, &mut rng);
+    let product_id = ctx.skew.pick_i64(skew::PRODUCT_COUNT.into(), &mut rng);
+    let quantity_cases = rng.gen_range(1..=20i64);
+    let requested_by = rng.gen_range(1..=5i64);
+
+    // Same order-number scheme as the PostgreSQL workload; collisions are retried
+    // by the unique_violation retry policy
+    let prefix = ctx.run_tag.order_number_prefix(stores);
+    let order_number = if attempt >= UUID_ORDER_NUMBER_ATTEMPT {
+        let uuid_suffix = uuid::Uuid::new_v4().simple().to_string();
+        format!("{}ORD{}", prefix, uuid_suffix[..8].to_uppercase())
+    } else {
+        let timestamp_component = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let unique_component =
+            (seed.wrapping_mul(1000) + attempt as u64 + (timestamp_component % 10000)) % 999999;
+        format!("{}ORD{:06}", prefix, unique_component + 1)
+    };
+
+    match ctx.backdate.as_deref().map(|backdate| backdate.sample(&mut rng)) {
+        Some(order_date) => {
+            conn.exec_drop(
+                "INSERT INTO orders (order_number, to_store_id, product_id, quantity_cases, requested_by, order_status, order_date)
+                 VALUES (?, ?, ?, ?, ?, 'pending_review', FROM_UNIXTIME(?))",
+                (
+                    order_number,
+                    to_store_id,
+                    product_id,
+                    quantity_cases,
+                    requested_by,
+                    order_date,
+                ),
+            )
+            .await?
+        }
+        None => {
+            conn.exec_drop(
+                "INSERT INTO orders (order_number, to_store_id, product_id, quantity_cases, requested_by, order_status)
+                 VALUES (?, ?, ?, ?, ?, 'pending_review')",
+                (
+                    order_number,
+                    to_store_id,
+                    product_id,
+                    quantity_cases,
+                    requested_by,
+                ),
+            )
+            .await?
+        }
+    }
+    let rows = conn.affected_rows() as usize;
+    writes.record(WriteKind::Insert, rows as u64);
+
+    Ok(rows)
+}
+
+async fn execute_update(
+    conn: &mut Conn,
+    seed: u64,
+    skew: KeySkew,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<usize> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // MySQL cannot select from the table it updates in a subquery, so the
+    // status transitions pick their row with UPDATE ... LIMIT
+    match rng.gen_range(0..3) {
+        0 => {
+            conn.query_drop(
+                "UPDATE orders SET order_status = 'approved', approved_date = CURRENT_TIMESTAMP
+                 WHERE order_status = 'pending_review' LIMIT 1",
+            )
+            .await?
+        }
+        1 => {
+            conn.query_drop(
+                "UPDATE orders SET order_status = 'fulfilled', fulfilled_date = CURRENT_TIMESTAMP
+                 WHERE order_status = 'approved' LIMIT 1",
+            )
+            .await?
+        }
+        _ => {
+            let order_id = skew.pick_i64(skew::ORDER_KEY_COUNT.into(), &mut rng);
+            let new_quantity = rng.gen_range(1..=25i64);
+            conn.exec_drop(
+                "UPDATE orders SET quantity_cases = ? WHERE order_id = ? AND order_status = 'pending_review'",
+                (new_quantity, order_id),
+            )
+            .await?
+        }
+    }
+    let rows = conn.affected_rows() as usize;
+    writes.record(WriteKind::Update, rows as u64);
+
+    Ok(rows)
+}
@@ -0,0 +1,92 @@
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+/// Head start given to each connection attempt before the next address is tried
+/// in parallel (the "Connection Attempt Delay" recommended by RFC 8305)
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Address family to try first when a host resolves to both
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AddressPreference {
+    Ipv6,
+    Ipv4,
+}
+
+/// Resolve `host` and order its addresses for happy eyeballs: families
+/// alternate, starting with the preferred one (IPv6 unless told otherwise)
+pub async fn resolve(
+    host: &str,
+    port: u16,
+    preference: Option<AddressPreference>,
+) -> io::Result<Vec<SocketAddr>> {
+    let mut ipv6 = Vec::new();
+    let mut ipv4 = Vec::new();
+    for addr in tokio::net::lookup_host((host, port)).await? {
+        let family = if addr.is_ipv6() { &mut ipv6 } else { &mut ipv4 };
+        if !family.contains(&addr) {
+            family.push(addr);
+        }
+    }
+
+    let (first, second) = match preference {
+        Some(AddressPreference::Ipv4) => (ipv4, ipv6),
+        _ => (ipv6, ipv4),
+    };
+    let mut ordered = Vec::with_capacity(first.len() + second.len());
+    let mut first = first.into_iter();
+    let mut second = second.into_iter();
+    loop {
+        match (first.next(), second.next()) {
+            (None, None) => break,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+
+    if ordered.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} did not resolve to any address", host),
+        ));
+    }
+    Ok(ordered)
+}
+
+/// Connect to the first address that answers: attempts start in order, each one
+/// `ATTEMPT_DELAY` after the previous (or immediately once it fails), so an
+/// unreachable family costs a short delay instead of a full connect timeout
+pub async fn connect(addrs: &[SocketAddr]) -> io::Result<(TcpStream, SocketAddr)> {
+    let mut remaining = addrs.iter().copied();
+    let mut attempts = FuturesUnordered::new();
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no addresses to connect to");
+
+    if let Some(addr) = remaining.next() {
+        attempts.push(attempt(addr));
+    }
+    while !attempts.is_empty() {
+        tokio::select! {
+            Some((addr, result)) = attempts.next() => match result {
+                Ok(stream) => return Ok((stream, addr)),
+                Err(e) => {
+                    last_error = e;
+                    if let Some(addr) = remaining.next() {
+                        attempts.push(attempt(addr));
+                    }
+                }
+            },
+            _ = tokio::time::sleep(ATTEMPT_DELAY), if remaining.len() > 0 => {
+                if let Some(addr) = remaining.next() {
+                    attempts.push(attempt(addr));
+                }
+            }
+        }
+    }
+
+    Err(last_error)
+}
+
+async fn attempt(addr: SocketAddr) -> (SocketAddr, io::Result<TcpStream>) {
+    (addr, TcpStream::connect(addr).await)
+}
@@ -0,0 +1,120 @@
+use crate::recorder::MetricsRecorder;
+use crate::{
+    calculate_operational_result, execute_query_from, Args, Backend, QueryContext, SimulationResult,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Parse a positive query rate, e.g. `500` or `2.5`
+pub fn parse_target_qps(value: &str) -> Result<f64, String> {
+    let qps: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid query rate '{}'", value))?;
+    if !qps.is_finite() || qps <= 0.0 {
+        return Err(format!("query rate must be positive, got '{}'", value));
+    }
+    Ok(qps)
+}
+
+/// Issue queries on a fixed arrival schedule of `target_qps`, whether or not earlier
+/// queries have completed. Latency is measured from each query's scheduled arrival,
+/// so time spent queued behind a slow database is counted instead of omitted.
+pub async fn run_open_loop_simulation(
+    backend: &Backend,
+    args: &Args,
+    ctx: &Arc<QueryContext>,
+    target_qps: f64,
+    baseline_latency: f64,
+) -> anyhow::Result<SimulationResult> {
+    let start_time = Instant::now();
+    let end_time = start_time + Duration::from_secs(args.duration);
+    let semaphore = Arc::new(Semaphore::new(args.connections));
+    let query_limit = match (args.duration_only, args.total_queries) {
+        (false, Some(total)) => total,
+        _ => usize::MAX,
+    };
+
+    let mut tasks = JoinSet::new();
+    let mut recorder = MetricsRecorder::default();
+    // Origin, first arrival and rate of the schedule once --control-port paused the
+    // run or changed its rate; arrivals then follow from there at that rate
+    let mut rescheduled: Option<(Instant, usize, f64)> = None;
+    let mut previous = start_time;
+    for query_count in 0..query_limit {
+        if let Some(control) = &ctx.control {
+            let resumed = control.wait_while_paused().await;
+            let qps = control.target_qps().unwrap_or(target_qps);
+            let current = rescheduled.map_or(target_qps, |(_, _, qps)| qps);
+            if resumed || qps != current {
+                // Queries that fell due while paused are skipped, not sent in a burst
+                let origin = if resumed {
+                    Instant::now()
+                } else {
+                    previous.max(Instant::now())
+                };
+                rescheduled = Some((origin, query_count, qps));
+            }
+        }
+
+        // Derive each arrival from the start so timer drift does not accumulate
+        let offset = match (rescheduled, ctx.ramp) {
+            (Some((origin, first, qps)), _) => Some(
+                origin - start_time + Duration::from_secs_f64((query_count - first) as f64 / qps),
+            ),
+            (None, Some(ramp)) => ramp.arrival_offset(query_count as u64, target_qps),
+            (None, None) => Some(Duration::from_secs_f64(query_count as f64 / target_qps)),
+        };
+        let Some(scheduled) = offset.map(|offset| start_time + offset) else {
+            break;
+        };
+        previous = scheduled;
+        if scheduled >= end_time || ctx.aborted() {
+            break;
+        }
+        tokio::time::sleep_until(scheduled.into()).await;
+
+        let backend = backend.clone();
+        let semaphore = Arc::clone(&semaphore);
+        let ctx = Arc::clone(ctx);
+        ctx.pending_work.task_spawned();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire().await.unwrap();
+            let metrics = execute_query_from(
+                &backend,
+                &ctx,
+                &ctx.query_type,
+                query_count as u64,
+                scheduled,
+            )
+            .await;
+            ctx.pending_work.task_finished();
+            metrics
+        });
+
+        while let Some(result) = tasks.try_join_next() {
+            if let Ok(query_metrics) = result {
+                recorder.extend(query_metrics);
+            }
+        }
+    }
+
+    // Queries still in flight when the schedule ends are waited for, not dropped
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(query_metrics) = result {
+            recorder.extend(query_metrics);
+        }
+    }
+
+    let (recorder, total_duration) = ctx.with_warmup(recorder, start_time.elapsed());
+    let mut result = calculate_operational_result(
+        recorder,
+        total_duration,
+        args.connections,
+        baseline_latency,
+        args.robust_stats,
+    )?;
+    result.target_queries_per_second = Some(target_qps);
+    Ok(result)
+}
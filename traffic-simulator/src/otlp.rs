@@ -0,0 +1,262 @@
+use crate::{BackendKind, FailureStage, QueryMetric};
+use rand::Rng;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// How often buffered spans are sent to the collector
+pub const EXPORT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Spans buffered between exports; beyond this, spans are dropped and counted
+/// rather than letting a slow collector grow memory without bound
+const MAX_QUEUED_SPANS: usize = 50_000;
+
+/// SPAN_KIND_CLIENT and the STATUS_CODE_* values of the OTLP trace protocol
+const SPAN_KIND_CLIENT: u8 = 3;
+const STATUS_CODE_OK: u8 = 1;
+const STATUS_CODE_ERROR: u8 = 2;
+
+/// Sends one client span per query to an OpenTelemetry collector over OTLP/HTTP
+/// (JSON encoding)
+#[derive(Debug)]
+pub struct OtlpExporter {
+    client: reqwest::Client,
+    traces_url: String,
+    /// Extra request headers from `OTEL_EXPORTER_OTLP_HEADERS` (e.g. an API key)
+    headers: Vec<(String, String)>,
+    sample_ratio: f64,
+    resource: Value,
+    /// Attributes of the target database, repeated on every span
+    database: Vec<Value>,
+    queue: Mutex<SpanQueue>,
+}
+
+#[derive(Debug, Default)]
+struct SpanQueue {
+    spans: Vec<Value>,
+    dropped: u64,
+}
+
+impl OtlpExporter {
+    /// Labels become resource attributes; `endpoint` is the collector's base URL
+    /// (`/v1/traces` is appended unless already present)
+    pub fn new(
+        endpoint: &str,
+        sample_ratio: f64,
+        backend: BackendKind,
+        database_url: &str,
+        labels: &BTreeMap<String, String>,
+    ) -> Self {
+        let endpoint = endpoint.trim_end_matches('/');
+        let traces_url = if endpoint.ends_with("/v1/traces") {
+            endpoint.to_string()
+        } else {
+            format!("{}/v1/traces", endpoint)
+        };
+        let headers = std::env::var("OTEL_EXPORTER_OTLP_HEADERS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+
+        let mut resource = vec![
+            attribute(
+                "service.name",
+                json!({ "stringValue": "postgres-traffic-simulator" }),
+            ),
+            attribute(
+                "service.version",
+                json!({ "stringValue": env!("CARGO_PKG_VERSION") }),
+            ),
+        ];
+        resource.extend(
+            labels
+                .iter()
+                .map(|(key, value)| attribute(key, json!({ "stringValue": value }))),
+        );
+
+        let mut database = Vec::new();
+        if backend == BackendKind::Sqlite {
+            database.push(attribute("db.system", json!({ "stringValue": "sqlite" })));
+        } else if backend == BackendKind::Mysql {
+            database.push(attribute("db.system", json!({ "stringValue": "mysql" })));
+        } else if backend == BackendKind::DatabricksSql {
+            database.push(attribute(
+                "db.system",
+                json!({ "stringValue": "databricks" }),
+            ));
+        } else {
+            database.push(attribute(
+                "db.system",
+                json!({ "stringValue": "postgresql" }),
+            ));
+            if let Ok(config) = database_url.parse::<tokio_postgres::Config>() {
+                if let Some(name) = config.get_dbname() {
+                    database.push(attribute("db.name", json!({ "stringValue": name })));
+                }
+                if let Some(tokio_postgres::config::Host::Tcp(host)) = config.get_hosts().first() {
+                    database.push(attribute("server.address", json!({ "stringValue": host })));
+                }
+                if let Some(port) = config.get_ports().first() {
+                    database.push(attribute(
+                        "server.port",
+                        json!({ "intValue": port.to_string() }),
+                    ));
+                }
+            }
+        }
+
+        Self {
+            client: reqwest::Client::new(),
+            traces_url,
+            headers,
+            sample_ratio,
+            resource: json!({ "attributes": resource }),
+            database,
+            queue: Mutex::new(SpanQueue::default()),
+        }
+    }
+
+    /// Buffer a span for a query that just finished (subject to the sample ratio)
+    pub fn record(&self, metric: &QueryMetric) {
+        let mut rng = rand::thread_rng();
+        if self.sample_ratio < 1.0 && !rng.gen_bool(self.sample_ratio) {
+            return;
+        }
+
+        let end = SystemTime::now();
+        let start = end - metric.latency;
+        let query_type = format!("{:?}", metric.query_type).to_lowercase();
+        let mut attributes = self.database.clone();
+        attributes.extend([
+            attribute(
+                "db.operation",
+                json!({ "stringValue": query_type.to_uppercase() }),
+            ),
+            attribute("simulator.query_type", json!({ "stringValue": query_type })),
+            attribute(
+                "simulator.connection_acquisition_ms",
+                json!({ "doubleValue": metric.connection_time.as_secs_f64() * 1000.0 }),
+            ),
+            attribute(
+                "simulator.execution_ms",
+                json!({ "doubleValue": metric.query_execution_time.as_secs_f64() * 1000.0 }),
+            ),
+            attribute(
+                "simulator.connection_recycled",
+                json!({ "boolValue": metric.connection_recycled }),
+            ),
+        ]);
+        let status = match metric.failure_stage {
+            None => json!({ "code": STATUS_CODE_OK }),
+            Some(stage) => {
+                let stage = match stage {
+                    FailureStage::Connection => "connection",
+                    FailureStage::Query => "query",
+                };
+                attributes.push(attribute(
+                    "simulator.failure_stage",
+                    json!({ "stringValue": stage }),
+                ));
+                json!({ "code": STATUS_CODE_ERROR, "message": format!("{} failed", stage) })
+            }
+        };
+
+        let span = json!({
+            "traceId": format!("{:032x}", rng.gen::<u128>().max(1)),
+            "spanId": format!("{:016x}", rng.gen::<u64>().max(1)),
+            "name": query_type.to_uppercase(),
+            "kind": SPAN_KIND_CLIENT,
+            "startTimeUnixNano": unix_nanos(start).to_string(),
+            "endTimeUnixNano": unix_nanos(end).to_string(),
+            "attributes": attributes,
+            "status": status,
+        });
+
+        let mut queue = self.queue.lock().unwrap();
+        if queue.spans.len() < MAX_QUEUED_SPANS {
+            queue.spans.push(span);
+        } else {
+            queue.dropped += 1;
+        }
+    }
+
+    /// Send the spans buffered since the last export
+    pub async fn flush(&self) -> anyhow::Result<()> {
+        let queue = std::mem::take(&mut *self.queue.lock().unwrap());
+        if queue.dropped > 0 {
+            warn!(
+                "Dropped {} spans: more than {} queued between OTLP exports",
+                queue.dropped, MAX_QUEUED_SPANS
+            );
+        }
+        if queue.spans.is_empty() {
+            return Ok(());
+        }
+
+        let body = json!({
+            "resourceSpans": [{
+                "resource": self.resource,
+                "scopeSpans": [{
+                    "scope": { "name": "postgres-traffic-simulator", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": queue.spans,
+                }],
+            }],
+        });
+        let mut request = self.client.post(&self.traces_url).json(&body);
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        let response = request.timeout(Duration::from_secs(10)).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "OTLP export returned {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Export buffered spans every `EXPORT_INTERVAL` until aborted; failed exports are
+/// logged and the run carries on
+pub async fn run_otlp_exporter(exporter: std::sync::Arc<OtlpExporter>) {
+    let mut ticker = tokio::time::interval(EXPORT_INTERVAL);
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+        if let Err(e) = exporter.flush().await {
+            warn!("Failed to export spans over OTLP: {}", e);
+        }
+    }
+}
+
+/// Parse a sampling ratio between 0 (exclusive) and 1
+pub fn parse_sample_ratio(value: &str) -> Result<f64, String> {
+    let ratio: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid sample ratio '{}'", value))?;
+    if !(ratio > 0.0 && ratio <= 1.0) {
+        return Err(format!(
+            "sample ratio must be above 0 and at most 1, got '{}'",
+            value
+        ));
+    }
+    Ok(ratio)
+}
+
+fn attribute(key: &str, value: Value) -> Value {
+    json!({ "key": key, "value": value })
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
@@ -0,0 +1,63 @@
+use crate::TrafficPhase;
+use serde::Deserialize;
+
+/// User-defined traffic shape for the real-world simulation, in YAML or JSON
+#[derive(Debug, Deserialize)]
+pub struct PatternFile {
+    pub phases: Vec<TrafficPhase>,
+}
+
+impl PatternFile {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read traffic pattern '{}': {}", path, e))?;
+        // YAML is a superset of JSON, so one parser reads both
+        let pattern: PatternFile = serde_yaml::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Invalid traffic pattern '{}': {}", path, e))?;
+        validate_phases(&pattern.phases)
+            .map_err(|e| anyhow::anyhow!("Invalid traffic pattern '{}': {}", path, e))?;
+        Ok(pattern)
+    }
+}
+
+/// Check that phases cover the run exactly once and have usable variances
+pub fn validate_phases<'a>(
+    phases: impl IntoIterator<Item = &'a TrafficPhase>,
+) -> Result<(), String> {
+    let mut total_percent = 0.0;
+    let mut count = 0;
+    for (index, phase) in phases.into_iter().enumerate() {
+        count += 1;
+        if !phase.duration_percent.is_finite() || phase.duration_percent <= 0.0 {
+            return Err(format!(
+                "phase {} needs a positive duration_percent",
+                index + 1
+            ));
+        }
+        for (name, std) in [
+            ("qps_variance_std", phase.qps_variance_std),
+            ("connection_variance_std", phase.connection_variance_std),
+        ] {
+            if !std.is_finite() || std < 0.0 {
+                return Err(format!(
+                    "phase {} {} must be zero or more, got {}",
+                    index + 1,
+                    name,
+                    std
+                ));
+            }
+        }
+        total_percent += phase.duration_percent;
+    }
+
+    if count == 0 {
+        return Err("no phases".to_string());
+    }
+    if (total_percent - 1.0).abs() > 0.01 {
+        return Err(format!(
+            "phase duration_percent values must sum to 1.0 (got {:.2})",
+            total_percent
+        ));
+    }
+    Ok(())
+}
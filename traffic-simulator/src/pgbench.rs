@@ -0,0 +1,174 @@
+use crate::prepared::Statements;
+use crate::writes::{WriteCounts, WriteKind};
+use deadpool_postgres::Pool;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio_postgres::Row;
+use tracing::info;
+
+/// Rows per scale factor, as defined by pgbench
+const ACCOUNTS_PER_BRANCH: i64 = 100_000;
+const TELLERS_PER_BRANCH: i64 = 10;
+
+/// Create and load the pgbench tables using server-side generation (the
+/// equivalent of `pgbench -i -I dtGvp -s <scale>`)
+pub async fn initialize(pool: &Pool, scale: u32, disable_logging: bool) -> anyhow::Result<()> {
+    let client = pool.get().await?;
+    let scale = scale as i64;
+
+    if !disable_logging {
+        info!(
+            "🏦 Initializing pgbench tables at scale {} ({} accounts)...",
+            scale,
+            scale * ACCOUNTS_PER_BRANCH
+        );
+    }
+
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS pgbench_accounts, pgbench_branches, pgbench_tellers, pgbench_history;
+             CREATE TABLE pgbench_history (tid int, bid int, aid int, delta int, mtime timestamp, filler char(22));
+             CREATE TABLE pgbench_tellers (tid int NOT NULL, bid int, tbalance int, filler char(84)) WITH (fillfactor = 100);
+             CREATE TABLE pgbench_accounts (aid int NOT NULL, bid int, abalance int, filler char(84)) WITH (fillfactor = 100);
+             CREATE TABLE pgbench_branches (bid int NOT NULL, bbalance int, filler char(88)) WITH (fillfactor = 100);",
+        )
+        .await?;
+
+    client
+        .execute(
+            "INSERT INTO pgbench_branches (bid, bbalance) SELECT bid, 0 FROM generate_series(1, $1::bigint) AS bid",
+            &[&scale],
+        )
+        .await?;
+    client
+        .execute(
+            "INSERT INTO pgbench_tellers (tid, bid, tbalance)
+             SELECT tid, (tid - 1) / $2::bigint + 1, 0 FROM generate_series(1, $1::bigint * $2) AS tid",
+            &[&scale, &TELLERS_PER_BRANCH],
+        )
+        .await?;
+    client
+        .execute(
+            "INSERT INTO pgbench_accounts (aid, bid, abalance, filler)
+             SELECT aid, (aid - 1) / $2::bigint + 1, 0, '' FROM generate_series(1, $1::bigint * $2) AS aid",
+            &[&scale, &ACCOUNTS_PER_BRANCH],
+        )
+        .await?;
+
+    client
+        .batch_execute(
+            "VACUUM ANALYZE pgbench_branches, pgbench_tellers, pgbench_accounts, pgbench_history",
+        )
+        .await?;
+    client
+        .batch_execute(
+            "ALTER TABLE pgbench_branches ADD PRIMARY KEY (bid);
+             ALTER TABLE pgbench_tellers ADD PRIMARY KEY (tid);
+             ALTER TABLE pgbench_accounts ADD PRIMARY KEY (aid);",
+        )
+        .await?;
+
+    if !disable_logging {
+        info!("✅ pgbench tables initialized");
+    }
+
+    Ok(())
+}
+
+/// Check that the pgbench tables exist and were loaded at `scale`
+pub async fn verify(pool: &Pool, scale: u32) -> anyhow::Result<()> {
+    let client = pool.get().await?;
+    let branches: i64 = client
+        .query_one("SELECT count(*) FROM pgbench_branches", &[])
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "pgbench tables not found ({}); rerun with --pgbench-init",
+                e
+            )
+        })?
+        .get(0);
+
+    if branches != scale as i64 {
+        return Err(anyhow::anyhow!(
+            "pgbench tables were initialized at scale {}, not {}; rerun with --pgbench-init",
+            branches,
+            scale
+        ));
+    }
+
+    Ok(())
+}
+
+/// The TPC-B-like transaction pgbench runs by default (`-b tpcb-like`)
+pub async fn execute_transaction(
+    client: &deadpool_postgres::Client,
+    statements: &Statements,
+    scale: u32,
+    seed: u64,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<Vec<Row>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let scale = scale as i32;
+    let aid = rng.gen_range(1..=scale * ACCOUNTS_PER_BRANCH as i32);
+    let bid = rng.gen_range(1..=scale);
+    let tid = rng.gen_range(1..=scale * TELLERS_PER_BRANCH as i32);
+    let delta = rng.gen_range(-5000..=5000i32);
+
+    client.batch_execute("BEGIN").await?;
+    let result = async {
+        let updated = statements
+            .execute(
+                client,
+                "UPDATE pgbench_accounts SET abalance = abalance + $1 WHERE aid = $2",
+                &[&delta, &aid],
+            )
+            .await?;
+        writes.record(WriteKind::Update, updated);
+        let rows = statements
+            .query(
+                client,
+                "SELECT abalance FROM pgbench_accounts WHERE aid = $1",
+                &[&aid],
+            )
+            .await?;
+        let updated = statements
+            .execute(
+                client,
+                "UPDATE pgbench_tellers SET tbalance = tbalance + $1 WHERE tid = $2",
+                &[&delta, &tid],
+            )
+            .await?;
+        writes.record(WriteKind::Update, updated);
+        let updated = statements
+            .execute(
+                client,
+                "UPDATE pgbench_branches SET bbalance = bbalance + $1 WHERE bid = $2",
+                &[&delta, &bid],
+            )
+            .await?;
+        writes.record(WriteKind::Update, updated);
+        let inserted = statements
+            .execute(
+                client,
+                "INSERT INTO pgbench_history (tid, bid, aid, delta, mtime)
+                 VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP)",
+                &[&tid, &bid, &aid, &delta],
+            )
+            .await?;
+        writes.record(WriteKind::Insert, inserted);
+        Ok::<_, tokio_postgres::Error>(rows)
+    }
+    .await;
+
+    match result {
+        Ok(rows) => {
+            client.batch_execute("COMMIT").await?;
+            Ok(rows)
+        }
+        Err(e) => {
+            let _ = client.batch_execute("ROLLBACK").await;
+            Err(e.into())
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::SimulationResult;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Results of one phase of a real-world simulation, on its own
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PhaseResult {
+    /// Story chapter title, or `Phase N`
+    pub name: String,
+    pub intensity: String,
+    /// When the phase started, counted from the start of the measured run
+    pub start_seconds: f64,
+    pub duration_seconds: f64,
+    /// Queries submitted during the phase, including failures
+    pub total_queries: usize,
+    pub failed_queries: usize,
+    /// Most connections the phase let queries use at once; the ceiling varies around
+    /// the intensity's share of --connections
+    pub connection_ceiling: usize,
+    /// Most queries in flight at once during the phase, each on a connection of its own
+    pub peak_connections: usize,
+    /// `None` when none of the phase's queries succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Box<SimulationResult>>,
+}
+
+pub fn display_phases(phases: &[PhaseResult]) {
+    println!("\n🌊 Results by Phase (latency in ms):");
+    println!(
+        "   {:<28}{:>7}{:>9}{:>9}{:>8}{:>9}{:>9}{:>9}{:>12}",
+        "Phase", "Time", "Queries", "QPS", "Errors", "p50", "p95", "p99", "Peak Conns"
+    );
+    for phase in phases {
+        let errors = phase.failed_queries as f64 / phase.total_queries.max(1) as f64 * 100.0;
+        let name: String = phase.name.chars().take(27).collect();
+        let connections = format!("{}/{}", phase.peak_connections, phase.connection_ceiling);
+        match &phase.result {
+            Some(result) => println!(
+                "   {:<28}{:>6.1}s{:>9}{:>9.1}{:>7.1}%{:>9.2}{:>9.2}{:>9.2}{:>12}",
+                name,
+                phase.duration_seconds,
+                phase.total_queries,
+                result.queries_per_second,
+                errors,
+                result.p50_latency_ms,
+                result.p95_latency_ms,
+                result.p99_latency_ms,
+                connections
+            ),
+            None => println!(
+                "   {:<28}{:>6.1}s{:>9}{:>9.1}{:>7.1}%   (no successful queries) {:>12}",
+                name, phase.duration_seconds, phase.total_queries, 0.0, errors, connections
+            ),
+        }
+    }
+    println!("   Peak Conns: most queries in flight at once / the phase's connection ceiling");
+}
@@ -0,0 +1,208 @@
+use crate::writes::{WriteCounts, WriteKind};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tokio_postgres::types::{ToSql, Type};
+use tokio_postgres::Row;
+use wasmi::{Config, Engine, Linker, Memory, Module, Store, TypedFunc};
+
+/// Instruction budget for a single `generate` call, so a buggy plugin fails the
+/// query instead of hanging the generator
+const FUEL_PER_CALL: u64 = 10_000_000;
+
+/// Upper bound on a generated statement, guarding against bogus pointers/lengths
+const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Custom query generator compiled to WebAssembly.
+///
+/// Host interface (no imports are provided, so plugins have no I/O of any kind):
+/// - `memory`: the module's exported linear memory
+/// - `generate(seed: i64) -> i64`: returns `(ptr << 32) | len` of a UTF-8 JSON
+///   document `{"sql": "...", "params": [...], "think_ms": 0}` in `memory`
+///
+/// Parameters are JSON scalars converted to the types PostgreSQL infers for the
+/// statement's placeholders.
+pub struct WasmPlugin {
+    path: String,
+    instance: Mutex<PluginInstance>,
+}
+
+struct PluginInstance {
+    store: Store<()>,
+    memory: Memory,
+    generate: TypedFunc<i64, i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PluginStatement {
+    pub sql: String,
+    #[serde(default)]
+    pub params: Vec<Value>,
+    /// Pause before the worker sends its next query, in milliseconds
+    #[serde(default)]
+    pub think_ms: u64,
+}
+
+impl WasmPlugin {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let wasm = std::fs::read(path)?;
+
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &wasm)
+            .map_err(|e| anyhow::anyhow!("Invalid WASM plugin '{}': {}", path, e))?;
+
+        let mut store = Store::new(&engine, ());
+        store.set_fuel(FUEL_PER_CALL).map_err(fuel_error)?;
+        let instance = Linker::<()>::new(&engine)
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| anyhow::anyhow!("Failed to instantiate plugin '{}': {}", path, e))?;
+
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| anyhow::anyhow!("Plugin '{}' does not export `memory`", path))?;
+        let generate = instance
+            .get_typed_func::<i64, i64>(&store, "generate")
+            .map_err(|e| anyhow::anyhow!("Plugin '{}' has no usable `generate`: {}", path, e))?;
+
+        Ok(Self {
+            path: path.to_string(),
+            instance: Mutex::new(PluginInstance {
+                store,
+                memory,
+                generate,
+            }),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Ask the plugin for the statement to run for `seed` on the blocking pool, so a
+    /// slow plugin cannot stall the async workers driving the load
+    pub async fn generate_async(self: Arc<Self>, seed: u64) -> anyhow::Result<PluginStatement> {
+        tokio::task::spawn_blocking(move || self.generate(seed)).await?
+    }
+
+    fn generate(&self, seed: u64) -> anyhow::Result<PluginStatement> {
+        let mut instance = self.instance.lock().unwrap();
+        let PluginInstance {
+            store,
+            memory,
+            generate,
+        } = &mut *instance;
+
+        store.set_fuel(FUEL_PER_CALL).map_err(fuel_error)?;
+        let packed = generate
+            .call(&mut *store, seed as i64)
+            .map_err(|e| anyhow::anyhow!("Plugin trapped: {}", e))?;
+
+        let ptr = (packed as u64 >> 32) as usize;
+        let len = (packed as u64 & 0xFFFF_FFFF) as usize;
+        if len > MAX_OUTPUT_BYTES {
+            return Err(anyhow::anyhow!("Plugin output too large ({} bytes)", len));
+        }
+        let bytes = memory
+            .data(&*store)
+            .get(ptr..ptr + len)
+            .ok_or_else(|| anyhow::anyhow!("Plugin output is out of bounds"))?;
+
+        serde_json::from_slice(bytes).map_err(|e| anyhow::anyhow!("Invalid plugin output: {}", e))
+    }
+}
+
+impl std::fmt::Debug for WasmPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmPlugin")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+fn fuel_error(e: wasmi::errors::FuelError) -> anyhow::Error {
+    anyhow::anyhow!("Plugin fuel error: {}", e)
+}
+
+/// Run a plugin-generated statement, binding JSON parameters to the placeholder
+/// types. Writes without `RETURNING` report their affected-row count.
+pub async fn execute_statement(
+    client: &deadpool_postgres::Client,
+    statement: &PluginStatement,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<Vec<Row>> {
+    let prepared = client.prepare_cached(&statement.sql).await?;
+    if prepared.params().len() != statement.params.len() {
+        return Err(anyhow::anyhow!(
+            "Plugin statement expects {} parameters, got {}",
+            prepared.params().len(),
+            statement.params.len()
+        ));
+    }
+
+    let params = prepared
+        .params()
+        .iter()
+        .zip(&statement.params)
+        .map(|(ty, value)| to_sql_param(value, ty))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let params: Vec<&(dyn ToSql + Sync)> = params
+        .iter()
+        .map(|p| p.as_ref() as &(dyn ToSql + Sync))
+        .collect();
+
+    match WriteKind::of_sql(&statement.sql) {
+        Some(kind) if prepared.columns().is_empty() => {
+            writes.record(kind, client.execute(&prepared, &params).await?);
+            Ok(Vec::new())
+        }
+        Some(kind) => {
+            let rows = client.query(&prepared, &params).await?;
+            writes.record(kind, rows.len() as u64);
+            Ok(rows)
+        }
+        None => Ok(client.query(&prepared, &params).await?),
+    }
+}
+
+fn to_sql_param(value: &Value, ty: &Type) -> anyhow::Result<Box<dyn ToSql + Sync + Send>> {
+    let param: Box<dyn ToSql + Sync + Send> = match *ty {
+        Type::BOOL => Box::new(typed(value, ty, Value::as_bool)?),
+        Type::INT2 => Box::new(typed(value, ty, |v| {
+            v.as_i64().and_then(|n| i16::try_from(n).ok())
+        })?),
+        Type::INT4 => Box::new(typed(value, ty, |v| {
+            v.as_i64().and_then(|n| i32::try_from(n).ok())
+        })?),
+        Type::INT8 => Box::new(typed(value, ty, Value::as_i64)?),
+        Type::FLOAT4 => Box::new(typed(value, ty, |v| v.as_f64().map(|n| n as f32))?),
+        Type::FLOAT8 => Box::new(typed(value, ty, Value::as_f64)?),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => {
+            Box::new(typed(value, ty, |v| v.as_str().map(str::to_string))?)
+        }
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Unsupported plugin parameter type {} (cast the placeholder, e.g. $1::text)",
+                ty
+            ))
+        }
+    };
+
+    Ok(param)
+}
+
+/// Convert a JSON value for a placeholder of type `ty`; JSON null binds SQL NULL
+fn typed<T>(
+    value: &Value,
+    ty: &Type,
+    convert: impl Fn(&Value) -> Option<T>,
+) -> anyhow::Result<Option<T>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    convert(value)
+        .map(Some)
+        .ok_or_else(|| anyhow::anyhow!("Plugin parameter {} is not a valid {}", value, ty))
+}
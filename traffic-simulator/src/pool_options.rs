@@ -0,0 +1,90 @@
+use crate::isolation::IsolationLevel;
+use deadpool_postgres::{Pool, RecyclingMethod};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::time::Duration;
+
+/// How a connection is checked when it goes back into use
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PoolRecycle {
+    /// Only check that the connection has not been closed
+    Fast,
+    /// Run a test query first, catching connections the server or network dropped
+    Verified,
+    /// Reset session state like a pooler does (close cursors, deallocate prepared
+    /// statements, reset settings and locks) before each reuse
+    Clean,
+}
+
+/// Settings of the workload's connection pool
+#[derive(Debug, Clone, Copy)]
+pub struct PoolOptions {
+    /// How long a request waits for a free connection before failing
+    pub wait_timeout: Duration,
+    /// How long opening a new connection may take
+    pub create_timeout: Duration,
+    pub recycle: PoolRecycle,
+    /// Connections opened before the run starts
+    pub min_idle: usize,
+    /// Default isolation level of the connections' transactions
+    pub isolation: Option<IsolationLevel>,
+    /// Look the host up again for each new connection rather than pinning the
+    /// address it resolved to at startup
+    pub resolve_per_connection: bool,
+}
+
+/// The pool settings a run used, as exported with its configuration
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PoolConfig {
+    pub max_size: usize,
+    pub wait_timeout_ms: u64,
+    pub create_timeout_ms: u64,
+    pub recycle: PoolRecycle,
+    pub min_idle: usize,
+}
+
+impl Default for PoolOptions {
+    /// Fail fast on a busy pool and slow connects, so saturation shows up as errors
+    /// rather than as latency hidden in the pool
+    fn default() -> Self {
+        Self {
+            wait_timeout: Duration::from_millis(100),
+            create_timeout: Duration::from_millis(500),
+            recycle: PoolRecycle::Fast,
+            min_idle: 0,
+            isolation: None,
+            resolve_per_connection: false,
+        }
+    }
+}
+
+impl PoolOptions {
+    pub fn recycling_method(&self) -> RecyclingMethod {
+        match self.recycle {
+            PoolRecycle::Fast => RecyclingMethod::Fast,
+            PoolRecycle::Verified => RecyclingMethod::Verified,
+            PoolRecycle::Clean => RecyclingMethod::Clean,
+        }
+    }
+
+    pub fn config(&self, max_size: usize) -> PoolConfig {
+        PoolConfig {
+            max_size,
+            wait_timeout_ms: self.wait_timeout.as_millis() as u64,
+            create_timeout_ms: self.create_timeout.as_millis() as u64,
+            recycle: self.recycle,
+            min_idle: self.min_idle,
+        }
+    }
+
+    /// Open `min_idle` connections at once and put them back, so the first queries
+    /// do not pay for connection setup. The pool keeps idle connections open.
+    pub async fn fill(&self, pool: &Pool) -> anyhow::Result<()> {
+        let connections = futures::future::try_join_all((0..self.min_idle).map(|_| pool.get()))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to open --pool-min-idle connections: {}", e))?;
+        drop(connections);
+        Ok(())
+    }
+}
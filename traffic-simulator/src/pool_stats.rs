@@ -0,0 +1,156 @@
+use crate::timeline::TimelinePoint;
+use deadpool_postgres::{Pool, Status};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Connection pool status sampled once per second of the measured run
+#[derive(Debug)]
+pub struct PoolMonitor {
+    start: Instant,
+    samples: Mutex<BTreeMap<u64, PoolSample>>,
+}
+
+/// `Pool::status()` at one second of the run
+#[derive(Debug, Clone, Copy)]
+struct PoolSample {
+    max_size: usize,
+    in_use: usize,
+    waiting: usize,
+}
+
+/// Pool saturation counters, mergeable across shards (each with its own pool)
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PoolStats {
+    samples: u64,
+    /// Seconds every connection of the pool was checked out
+    saturated: u64,
+    /// Seconds with requests queued for a connection
+    with_waiters: u64,
+    in_use_sum: u64,
+    capacity_sum: u64,
+    waiting_sum: u64,
+    peak_in_use: usize,
+    peak_waiting: usize,
+}
+
+/// How close the run came to exhausting its connection pool
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PoolReport {
+    /// Once-per-second samples of the pool's status
+    samples: u64,
+    peak_in_use: usize,
+    /// Average share of the pool's connections checked out
+    utilization_percent: f64,
+    /// Share of samples with every connection checked out
+    saturated_percent: f64,
+    /// Share of samples with requests waiting for a connection
+    waiting_percent: f64,
+    average_waiting: f64,
+    peak_waiting: usize,
+}
+
+impl PoolMonitor {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            samples: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn sample(&self, status: Status) {
+        let second = self.start.elapsed().as_secs();
+        let sample = PoolSample {
+            max_size: status.max_size,
+            in_use: status.size.saturating_sub(status.available),
+            waiting: status.waiting,
+        };
+        self.samples.lock().unwrap().insert(second, sample);
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let mut stats = PoolStats::default();
+        for sample in self.samples.lock().unwrap().values() {
+            stats.samples += 1;
+            stats.saturated += u64::from(sample.in_use >= sample.max_size);
+            stats.with_waiters += u64::from(sample.waiting > 0);
+            stats.in_use_sum += sample.in_use as u64;
+            stats.capacity_sum += sample.max_size as u64;
+            stats.waiting_sum += sample.waiting as u64;
+            stats.peak_in_use = stats.peak_in_use.max(sample.in_use);
+            stats.peak_waiting = stats.peak_waiting.max(sample.waiting);
+        }
+        stats
+    }
+
+    /// Fill in the pool columns of the timeline's seconds
+    pub fn annotate(&self, points: &mut [TimelinePoint]) {
+        let samples = self.samples.lock().unwrap();
+        for point in points {
+            if let Some(sample) = samples.get(&point.second) {
+                point.pool_in_use = Some(sample.in_use);
+                point.pool_waiting = Some(sample.waiting);
+            }
+        }
+    }
+}
+
+impl PoolStats {
+    pub fn merge(&mut self, other: &PoolStats) {
+        self.samples += other.samples;
+        self.saturated += other.saturated;
+        self.with_waiters += other.with_waiters;
+        self.in_use_sum += other.in_use_sum;
+        self.capacity_sum += other.capacity_sum;
+        self.waiting_sum += other.waiting_sum;
+        self.peak_in_use = self.peak_in_use.max(other.peak_in_use);
+        self.peak_waiting = self.peak_waiting.max(other.peak_waiting);
+    }
+
+    /// `None` when nothing was sampled (SQLite, or runs shorter than a second)
+    pub fn report(&self) -> Option<PoolReport> {
+        if self.samples == 0 {
+            return None;
+        }
+        let per_sample = |count: u64| count as f64 / self.samples as f64;
+        Some(PoolReport {
+            samples: self.samples,
+            peak_in_use: self.peak_in_use,
+            utilization_percent: self.in_use_sum as f64 / self.capacity_sum.max(1) as f64 * 100.0,
+            saturated_percent: per_sample(self.saturated) * 100.0,
+            waiting_percent: per_sample(self.with_waiters) * 100.0,
+            average_waiting: per_sample(self.waiting_sum),
+            peak_waiting: self.peak_waiting,
+        })
+    }
+}
+
+/// Sample the pool's status once per second until aborted
+pub async fn run_pool_sampler(pool: Pool, monitor: Arc<PoolMonitor>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+        monitor.sample(pool.status());
+    }
+}
+
+pub fn display_pool(report: &PoolReport) {
+    println!("\n🏊 Connection Pool ({} samples):", report.samples);
+    println!(
+        "   In Use:                 {:>7.1}% avg, {} peak",
+        report.utilization_percent, report.peak_in_use
+    );
+    println!(
+        "   Saturated:              {:>7.1}% of seconds",
+        report.saturated_percent
+    );
+    println!(
+        "   Waiting for Connection: {:>7.1}% of seconds (avg {:.1}, peak {})",
+        report.waiting_percent, report.average_waiting, report.peak_waiting
+    );
+    if report.waiting_percent > 0.0 {
+        println!("   💡 Requests queued for a connection: latency includes pool waits, not just database time");
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{summarize_latencies, LatencySummary};
+use deadpool_postgres::{Object, Pool};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::time::Instant;
+
+/// Connection setup of a run with --preconnect, paid before the warmup instead of
+/// by its first queries
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PreconnectReport {
+    /// Connections opened; ones already open from --pool-min-idle are not counted
+    pub connections: usize,
+    /// Until every connection of the pool was open
+    pub duration_ms: f64,
+    /// TCP connect, TLS handshake and startup/authentication of each connection,
+    /// all opened at once
+    pub connect_latency: LatencySummary,
+}
+
+/// Check out every connection the pool can hold at once, so each one not yet open
+/// is opened now, then put them all back
+pub async fn preconnect(pool: &Pool) -> anyhow::Result<PreconnectReport> {
+    let start = Instant::now();
+    let checkouts = (0..pool.status().max_size).map(|_| async {
+        let checkout_start = Instant::now();
+        let client = pool.get().await?;
+        let opened = Object::metrics(&client).created >= start;
+        let connect_ms = opened.then(|| checkout_start.elapsed().as_secs_f64() * 1000.0);
+        Ok::<_, deadpool_postgres::PoolError>((client, connect_ms))
+    });
+    let checkouts = futures::future::try_join_all(checkouts)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to preconnect the pool: {}", e))?;
+    let duration = start.elapsed();
+
+    let latencies: Vec<f64> = checkouts
+        .iter()
+        .filter_map(|(_, connect_ms)| *connect_ms)
+        .collect();
+    Ok(PreconnectReport {
+        connections: latencies.len(),
+        duration_ms: duration.as_secs_f64() * 1000.0,
+        connect_latency: summarize_latencies(latencies),
+    })
+}
+
+pub fn display_preconnect(report: &PreconnectReport) {
+    println!("🔗 Preconnect (before the warmup):");
+    println!(
+        "   Connections Opened:     {:>7} in {:.0}ms",
+        report.connections, report.duration_ms
+    );
+    if report.connections > 0 {
+        println!(
+            "   Connect + Handshake (ms): avg {:.2}  p50 {:.2}  p99 {:.2}  max {:.2}",
+            report.connect_latency.average_ms,
+            report.connect_latency.p50_ms,
+            report.connect_latency.p99_ms,
+            report.connect_latency.max_ms
+        );
+    }
+    println!();
+}
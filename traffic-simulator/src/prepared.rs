@@ -0,0 +1,165 @@
+use crate::explain;
+use crate::recorder::LatencyHistogram;
+use crate::server_timing;
+use crate::LatencySummary;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Row, Statement};
+
+/// How workload statements reach the server: parsed and planned for every execution
+/// (what `client.query` with SQL text does), or with `--prepared` prepared once per
+/// connection and reused from its statement cache
+#[derive(Debug)]
+pub struct Statements {
+    prepared: bool,
+    stats: Mutex<PrepareStats>,
+}
+
+/// Mergeable statement preparation counts, handed from shards to the parent process
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PrepareStats {
+    prepared: bool,
+    statements: u64,
+    prepares: u64,
+    /// Parse/describe round trips, in milliseconds
+    prepare_latency: LatencyHistogram,
+}
+
+/// What preparing statements cost the run
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PrepareReport {
+    /// Statements were prepared once per connection and reused (--prepared)
+    prepared: bool,
+    /// Workload statements executed
+    statements: u64,
+    /// Parse/describe round trips: one per statement without --prepared, one per
+    /// distinct statement per connection with it
+    prepares: u64,
+    prepare_latency: LatencySummary,
+    /// Time spent preparing per request, in milliseconds; without --prepared this
+    /// is roughly what --prepared saves
+    prepare_ms_per_request: f64,
+}
+
+impl Statements {
+    pub fn new(prepared: bool) -> Self {
+        Self {
+            prepared,
+            stats: Mutex::new(PrepareStats {
+                prepared,
+                ..PrepareStats::default()
+            }),
+        }
+    }
+
+    pub async fn query(
+        &self,
+        client: &deadpool_postgres::Client,
+        sql: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, tokio_postgres::Error> {
+        let statement = self.statement(client, sql).await?;
+        explain::explain_if_sampled(client, sql, params).await;
+        server_timing::time_if_sampled(client, client.query(&statement, params)).await
+    }
+
+    pub async fn execute(
+        &self,
+        client: &deadpool_postgres::Client,
+        sql: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<u64, tokio_postgres::Error> {
+        let statement = self.statement(client, sql).await?;
+        explain::explain_if_sampled(client, sql, params).await;
+        server_timing::time_if_sampled(client, client.execute(&statement, params)).await
+    }
+
+    /// The statement to execute `sql` with, timing the round trip when the server
+    /// has to parse it
+    async fn statement(
+        &self,
+        client: &deadpool_postgres::Client,
+        sql: &str,
+    ) -> Result<Statement, tokio_postgres::Error> {
+        let cached = client.statement_cache.size();
+        let start = Instant::now();
+        let statement = if self.prepared {
+            client.prepare_cached(sql).await?
+        } else {
+            client.prepare(sql).await?
+        };
+        let prepare_time = start.elapsed();
+
+        let mut stats = self.stats.lock().unwrap();
+        stats.statements += 1;
+        if !self.prepared || client.statement_cache.size() > cached {
+            stats.prepares += 1;
+            stats.prepare_latency.record(prepare_time);
+        }
+        Ok(statement)
+    }
+
+    pub fn stats(&self) -> PrepareStats {
+        self.stats.lock().unwrap().clone()
+    }
+}
+
+impl PrepareStats {
+    pub fn merge(&mut self, other: &PrepareStats) {
+        self.prepared |= other.prepared;
+        self.statements += other.statements;
+        self.prepares += other.prepares;
+        self.prepare_latency.merge(&other.prepare_latency);
+    }
+
+    /// `None` when no workload statement went through the tracker (e.g. plugins,
+    /// which always prepare, or the SQLite backend)
+    pub fn report(&self, requests: usize) -> Option<PrepareReport> {
+        if self.statements == 0 {
+            return None;
+        }
+
+        let summary = self.prepare_latency.summary();
+        Some(PrepareReport {
+            prepared: self.prepared,
+            statements: self.statements,
+            prepares: self.prepares,
+            prepare_ms_per_request: summary.average_ms * self.prepares as f64
+                / requests.max(1) as f64,
+            prepare_latency: summary,
+        })
+    }
+}
+
+pub fn display_prepared_statements(report: &PrepareReport, average_latency_ms: f64) {
+    println!(
+        "\n📜 Statement Preparation ({}):",
+        if report.prepared {
+            "prepared once per connection"
+        } else {
+            "parsed for every execution"
+        }
+    );
+    println!("   Statements Executed:    {:>8}", report.statements);
+    println!("   Parse Round Trips:      {:>8}", report.prepares);
+    // With --prepared, connections that prepared during warmup parse nothing here
+    if report.prepares > 0 {
+        println!(
+            "   Parse Latency (ms):     avg {:.2}  p50 {:.2}  p99 {:.2}",
+            report.prepare_latency.average_ms,
+            report.prepare_latency.p50_ms,
+            report.prepare_latency.p99_ms
+        );
+    }
+    println!(
+        "   Per Request:            {:>7.3}ms ({:.1}% of average latency)",
+        report.prepare_ms_per_request,
+        report.prepare_ms_per_request / average_latency_ms * 100.0
+    );
+    if !report.prepared {
+        println!("   💡 Roughly what --prepared saves per request");
+    }
+}
@@ -0,0 +1,244 @@
+use rand::Rng;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// Forward local connections to the database while injecting network conditions,
+/// e.g. to see how the workload behaves when the app is 80ms away
+#[derive(clap::Args, Debug)]
+pub struct ProxyArgs {
+    /// Local address to listen on; point --database-url at it
+    #[arg(long, default_value = "127.0.0.1:15432")]
+    listen: String,
+
+    /// Database address to forward to (host:port)
+    #[arg(long)]
+    upstream: String,
+
+    /// One-way delay added to each direction, or CLIENT_TO_SERVER/SERVER_TO_CLIENT
+    /// (e.g. 40ms or 10ms/70ms)
+    #[arg(long, value_parser = parse_duration_pair, default_value = "0ms")]
+    latency: DirectionPair<Duration>,
+
+    /// Uniform random variation applied on top of --latency, per direction as above
+    #[arg(long, value_parser = parse_duration_pair, default_value = "0ms")]
+    jitter: DirectionPair<Duration>,
+
+    /// Throughput cap (e.g. 10mbit, 512kb), per direction as above
+    #[arg(long, value_parser = parse_bandwidth_pair)]
+    bandwidth: Option<DirectionPair<f64>>,
+}
+
+/// A setting that may differ between the client→server and server→client directions
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionPair<T> {
+    to_server: T,
+    to_client: T,
+}
+
+/// Conditions applied to one direction of a proxied connection
+#[derive(Debug, Clone, Copy)]
+struct LinkShape {
+    latency: Duration,
+    jitter: Duration,
+    /// Bytes per second, or `None` for unlimited
+    bandwidth: Option<f64>,
+}
+
+const BUFFER_SIZE: usize = 16 * 1024;
+
+/// Chunks a direction holds in flight, at most 4 MiB; once they are queued the
+/// proxy stops reading, so a sender faster than the bandwidth cap is held back by
+/// TCP flow control instead of buffering the transfer in memory
+const QUEUED_CHUNKS: usize = 256;
+
+pub async fn run_proxy(args: &ProxyArgs) -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let listener = TcpListener::bind(&args.listen).await?;
+    let to_server = LinkShape {
+        latency: args.latency.to_server,
+        jitter: args.jitter.to_server,
+        bandwidth: args.bandwidth.map(|b| b.to_server),
+    };
+    let to_client = LinkShape {
+        latency: args.latency.to_client,
+        jitter: args.jitter.to_client,
+        bandwidth: args.bandwidth.map(|b| b.to_client),
+    };
+
+    info!(
+        "🔀 Proxying {} → {} (client→server {}, server→client {})",
+        args.listen,
+        args.upstream,
+        describe(&to_server),
+        describe(&to_client)
+    );
+
+    loop {
+        let (client, peer) = listener.accept().await?;
+        let upstream = args.upstream.clone();
+
+        tokio::spawn(async move {
+            let server = match TcpStream::connect(&upstream).await {
+                Ok(server) => server,
+                Err(e) => {
+                    warn!(
+                        "Failed to connect to upstream {} for {}: {}",
+                        upstream, peer, e
+                    );
+                    return;
+                }
+            };
+            let _ = client.set_nodelay(true);
+            let _ = server.set_nodelay(true);
+
+            let (client_read, client_write) = client.into_split();
+            let (server_read, server_write) = server.into_split();
+            let (sent, received) = tokio::join!(
+                shape_link(client_read, server_write, to_server),
+                shape_link(server_read, client_write, to_client)
+            );
+            info!(
+                "Connection from {} closed ({} bytes sent, {} bytes received)",
+                peer, sent, received
+            );
+        });
+    }
+}
+
+/// Copy one direction, holding each chunk until its delivery time. Delivery times
+/// never go backwards, so jitter cannot reorder the byte stream, and the bandwidth
+/// cap is modeled as serialization delay on the link.
+async fn shape_link(
+    mut reader: OwnedReadHalf,
+    mut writer: OwnedWriteHalf,
+    shape: LinkShape,
+) -> u64 {
+    let (tx, mut rx) = mpsc::channel::<(Instant, Vec<u8>)>(QUEUED_CHUNKS);
+
+    let read_side = async move {
+        let mut buffer = vec![0u8; BUFFER_SIZE];
+        let mut last_delivery = Instant::now();
+        let mut link_free_at = Instant::now();
+
+        loop {
+            let n = match reader.read(&mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+
+            let now = Instant::now();
+            let mut deliver_at = now + sample_delay(&shape);
+            if let Some(bytes_per_second) = shape.bandwidth {
+                let transmit = Duration::from_secs_f64(n as f64 / bytes_per_second);
+                link_free_at = link_free_at.max(now) + transmit;
+                deliver_at = deliver_at.max(link_free_at + shape.latency);
+            }
+            deliver_at = deliver_at.max(last_delivery);
+            last_delivery = deliver_at;
+
+            if tx.send((deliver_at, buffer[..n].to_vec())).await.is_err() {
+                break;
+            }
+        }
+    };
+
+    let write_side = async move {
+        let mut total = 0u64;
+        while let Some((deliver_at, chunk)) = rx.recv().await {
+            tokio::time::sleep_until(deliver_at).await;
+            if writer.write_all(&chunk).await.is_err() {
+                break;
+            }
+            total += chunk.len() as u64;
+        }
+        let _ = writer.shutdown().await;
+        total
+    };
+
+    let ((), total) = tokio::join!(read_side, write_side);
+    total
+}
+
+fn sample_delay(shape: &LinkShape) -> Duration {
+    if shape.jitter.is_zero() {
+        return shape.latency;
+    }
+    let jitter = shape.jitter.as_secs_f64();
+    let offset = rand::thread_rng().gen_range(-jitter..=jitter);
+    Duration::from_secs_f64((shape.latency.as_secs_f64() + offset).max(0.0))
+}
+
+fn describe(shape: &LinkShape) -> String {
+    let mut description = format!(
+        "{:.0}ms ±{:.0}ms",
+        shape.latency.as_secs_f64() * 1000.0,
+        shape.jitter.as_secs_f64() * 1000.0
+    );
+    if let Some(bandwidth) = shape.bandwidth {
+        description.push_str(&format!(", {:.0} kbit/s", bandwidth * 8.0 / 1000.0));
+    }
+    description
+}
+
+fn parse_pair<T: Copy>(
+    value: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<DirectionPair<T>, String> {
+    match value.split_once('/') {
+        Some((to_server, to_client)) => Ok(DirectionPair {
+            to_server: parse(to_server)?,
+            to_client: parse(to_client)?,
+        }),
+        None => {
+            let both = parse(value)?;
+            Ok(DirectionPair {
+                to_server: both,
+                to_client: both,
+            })
+        }
+    }
+}
+
+fn parse_duration_pair(value: &str) -> Result<DirectionPair<Duration>, String> {
+    parse_pair(value, crate::parse_duration)
+}
+
+fn parse_bandwidth_pair(value: &str) -> Result<DirectionPair<f64>, String> {
+    parse_pair(value, parse_bandwidth)
+}
+
+/// Parse a rate in bits (`kbit`, `mbit`, `gbit`) or bytes (`kb`, `mb`, `gb`) per
+/// second into bytes per second
+fn parse_bandwidth(value: &str) -> Result<f64, String> {
+    let value = value.trim().to_lowercase();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid bandwidth '{}'", value))?;
+
+    let bytes_per_second = match unit {
+        "bit" => number / 8.0,
+        "kbit" => number * 1000.0 / 8.0,
+        "mbit" => number * 1_000_000.0 / 8.0,
+        "gbit" => number * 1_000_000_000.0 / 8.0,
+        "b" | "" => number,
+        "kb" => number * 1024.0,
+        "mb" => number * 1024.0 * 1024.0,
+        "gb" => number * 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(format!("unknown bandwidth unit in '{}'", value)),
+    };
+    if bytes_per_second <= 0.0 {
+        return Err("bandwidth must be positive".to_string());
+    }
+
+    Ok(bytes_per_second)
+}
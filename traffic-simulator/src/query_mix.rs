@@ -0,0 +1,71 @@
+use crate::QueryType;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// `--query-mix`: the statements `--query-type mixed` picks from and how often,
+/// e.g. `select=60,catalog=20,inventory=15,insert=5`
+#[derive(Debug, Clone)]
+pub struct QueryMix {
+    types: Vec<(QueryType, u32)>,
+    weights: WeightedIndex<u32>,
+}
+
+/// Parse comma-separated `TYPE=WEIGHT` pairs; weights are relative, not percentages
+pub fn parse_query_mix(value: &str) -> Result<QueryMix, String> {
+    let mut types = Vec::new();
+    for entry in value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let (name, weight) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("expected TYPE=WEIGHT, got '{}'", entry))?;
+        let query_type = <QueryType as clap::ValueEnum>::from_str(name.trim(), true)
+            .map_err(|_| format!("unknown query type '{}'", name.trim()))?;
+        if query_type == QueryType::Mixed {
+            return Err("mixed cannot be part of a query mix".to_string());
+        }
+        if types.iter().any(|(existing, _)| *existing == query_type) {
+            return Err(format!("query type '{}' appears twice", name.trim()));
+        }
+        let weight: u32 = weight
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid weight '{}' for {}", weight.trim(), name.trim()))?;
+        types.push((query_type, weight));
+    }
+    let weights = WeightedIndex::new(types.iter().map(|(_, weight)| *weight))
+        .map_err(|_| "a query mix needs at least one positive weight".to_string())?;
+    Ok(QueryMix { types, weights })
+}
+
+impl QueryMix {
+    /// The statement a request with `seed` runs
+    pub fn pick(&self, seed: u64) -> QueryType {
+        let index = self.weights.sample(&mut StdRng::seed_from_u64(seed));
+        self.types[index].0.clone()
+    }
+
+    pub fn query_types(&self) -> impl Iterator<Item = &QueryType> {
+        self.types.iter().map(|(query_type, _)| query_type)
+    }
+
+    /// Each type's share of requests, e.g. `select 60%, catalog 20%, inventory 20%`
+    pub fn describe(&self) -> String {
+        let total: u32 = self.types.iter().map(|(_, weight)| weight).sum();
+        self.types
+            .iter()
+            .filter(|(_, weight)| *weight > 0)
+            .map(|(query_type, weight)| {
+                format!(
+                    "{} {:.0}%",
+                    query_type.name(),
+                    f64::from(*weight) / f64::from(total) * 100.0
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
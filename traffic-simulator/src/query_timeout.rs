@@ -0,0 +1,49 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use tokio_postgres::Config;
+
+/// Extra time the client waits beyond --query-timeout-ms, so the server's own
+/// cancellation, which leaves the connection usable, normally wins the race
+const CLIENT_GRACE: Duration = Duration::from_millis(250);
+
+/// A request outlived --query-timeout-ms without the database answering
+#[derive(Debug)]
+pub struct QueryTimeout(pub Duration);
+
+impl fmt::Display for QueryTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no response within {:?} (--query-timeout-ms)",
+            self.0 + CLIENT_GRACE
+        )
+    }
+}
+
+impl std::error::Error for QueryTimeout {}
+
+/// Have the server cancel any statement running longer than `timeout`. Set as a
+/// startup option, so it costs no round trip and applies from the first statement.
+pub fn configure(pg_config: &mut Config, timeout: Option<Duration>) {
+    let Some(timeout) = timeout else { return };
+    let options = match pg_config.get_options() {
+        Some(options) => format!("{} -c statement_timeout={}", options, timeout.as_millis()),
+        None => format!("-c statement_timeout={}", timeout.as_millis()),
+    };
+    pg_config.options(&options);
+}
+
+/// Run `request`, giving up shortly after `timeout` when the server has not
+/// answered, e.g. because the network dropped the connection without a reset
+pub async fn run<T>(
+    timeout: Option<Duration>,
+    request: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout + CLIENT_GRACE, request)
+            .await
+            .unwrap_or_else(|_| Err(QueryTimeout(timeout).into())),
+        None => request.await,
+    }
+}
@@ -0,0 +1,89 @@
+use crate::recorder::LatencyHistogram;
+use crate::{LatencySummary, QueryMetric, QueryType};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Queries of one type and the latency of the successful ones, mergeable across shards
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct QueryTypeTally {
+    queries: usize,
+    failed: usize,
+    latency: LatencyHistogram,
+}
+
+impl QueryTypeTally {
+    pub fn merge(&mut self, other: &QueryTypeTally) {
+        self.queries += other.queries;
+        self.failed += other.failed;
+        self.latency.merge(&other.latency);
+    }
+
+    /// Add one request, failed or not
+    pub fn record(&mut self, metric: &QueryMetric) {
+        self.queries += 1;
+        if metric.success {
+            self.latency.record(metric.latency);
+        } else {
+            self.failed += 1;
+        }
+    }
+
+    pub fn stats(&self) -> QueryTypeStats {
+        QueryTypeStats {
+            queries: self.queries,
+            failed_queries: self.failed,
+            error_rate_percent: self.failed as f64 / self.queries.max(1) as f64 * 100.0,
+            latency: self.latency.summary(),
+        }
+    }
+}
+
+/// Outcome of the requests of one query type
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct QueryTypeStats {
+    pub queries: usize,
+    pub failed_queries: usize,
+    pub error_rate_percent: f64,
+    /// Latency of the successful queries
+    pub latency: LatencySummary,
+}
+
+/// Add one request to the tally of the type it ran
+pub fn tally(tallies: &mut BTreeMap<QueryType, QueryTypeTally>, metric: &QueryMetric) {
+    tallies
+        .entry(metric.query_type.clone())
+        .or_default()
+        .record(metric);
+}
+
+/// Per-type breakdown; `None` unless the run executed more than one query type
+pub fn report(
+    tallies: &BTreeMap<QueryType, QueryTypeTally>,
+) -> Option<BTreeMap<QueryType, QueryTypeStats>> {
+    if tallies.len() < 2 {
+        return None;
+    }
+
+    let stats = tallies
+        .iter()
+        .map(|(query_type, tally)| (query_type.clone(), tally.stats()))
+        .collect();
+    Some(stats)
+}
+
+pub fn display_query_types(stats: &BTreeMap<QueryType, QueryTypeStats>) {
+    println!("\n🗂️  Latency by Query Type (ms):");
+    println!("   Type          Queries    Errors       p50       p95       p99");
+    for (query_type, stats) in stats {
+        println!(
+            "   {:<12}{:>9}{:>9.2}%{:>10.2}{:>10.2}{:>10.2}",
+            format!("{:?}", query_type).to_uppercase(),
+            stats.queries,
+            stats.error_rate_percent,
+            stats.latency.p50_ms,
+            stats.latency.p95_ms,
+            stats.latency.p99_ms
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+/// Linear ramp of the load at the start and end of the measured run: the target
+/// QPS and the number of active connections climb from zero over `up` and fall back
+/// to zero over the final `down` of the run
+#[derive(Debug, Clone, Copy)]
+pub struct Ramp {
+    up: Duration,
+    down: Duration,
+    duration: Duration,
+}
+
+impl Ramp {
+    /// `None` when neither ramp is configured
+    pub fn new(
+        up: Option<Duration>,
+        down: Option<Duration>,
+        duration: Duration,
+    ) -> anyhow::Result<Option<Self>> {
+        if up.is_none() && down.is_none() {
+            return Ok(None);
+        }
+        let up = up.unwrap_or_default();
+        let down = down.unwrap_or_default();
+        if up + down > duration {
+            return Err(anyhow::anyhow!(
+                "--ramp-up and --ramp-down together ({:?}) exceed --duration ({:?})",
+                up + down,
+                duration
+            ));
+        }
+        Ok(Some(Self { up, down, duration }))
+    }
+
+    /// Share of the full load offered `elapsed` into the run, from 0.0 to 1.0
+    pub fn factor(&self, elapsed: Duration) -> f64 {
+        let remaining = self.duration.saturating_sub(elapsed);
+        let rising = ratio(elapsed, self.up);
+        let falling = ratio(remaining, self.down);
+        rising.min(falling)
+    }
+
+    /// When worker `index` of `workers` joins and leaves the run. Workers join one
+    /// by one over the ramp-up and leave in reverse order over the ramp-down; the
+    /// first worker stays until the pool finishes so queued queries are always drained.
+    pub fn worker_window(&self, index: usize, workers: usize) -> (Duration, Duration) {
+        if index == 0 {
+            return (Duration::ZERO, Duration::MAX);
+        }
+        let share = index as f64 / workers as f64;
+        let joins = self.up.mul_f64(share);
+        let leaves = self.duration - self.down.mul_f64(share);
+        (joins, leaves)
+    }
+
+    /// Offset from the start of arrival `n` at `qps` with the rate scaled by the
+    /// ramp, i.e. where the ramped cumulative arrival count reaches `n`. `None`
+    /// once the schedule is past the end of the run.
+    pub fn arrival_offset(&self, n: u64, qps: f64) -> Option<Duration> {
+        let arrivals = n as f64 / qps;
+        let (up, down) = (self.up.as_secs_f64(), self.down.as_secs_f64());
+        let down_start = self.duration.as_secs_f64() - down;
+
+        // Arrivals due by the end of the ramp-up and by the start of the ramp-down,
+        // in seconds at the full rate
+        let ramped_up = up / 2.0;
+        let steady = ramped_up + (down_start - up);
+        let offset = if arrivals < ramped_up {
+            (2.0 * up * arrivals).sqrt()
+        } else if arrivals < steady {
+            up + (arrivals - ramped_up)
+        } else {
+            // Solve x - x²/(2·down) = arrivals - steady for x within the ramp-down
+            let into_down = arrivals - steady;
+            if into_down >= down / 2.0 {
+                return None;
+            }
+            down_start + down - (down * down - 2.0 * down * into_down).sqrt()
+        };
+        Some(Duration::from_secs_f64(offset))
+    }
+
+    pub fn describe(&self) -> String {
+        format!("up {:?}, down {:?}", self.up, self.down)
+    }
+}
+
+/// `part / whole`, capped at 1.0; a zero-length ramp is always at full load
+fn ratio(part: Duration, whole: Duration) -> f64 {
+    if whole.is_zero() {
+        1.0
+    } else {
+        (part.as_secs_f64() / whole.as_secs_f64()).min(1.0)
+    }
+}
@@ -0,0 +1,76 @@
+use crate::export::CsvLabels;
+use crate::QueryMetric;
+use anyhow::Context;
+use chrono::{SecondsFormat, Utc};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Mutex;
+use tracing::warn;
+
+const COLUMNS: &str =
+    "timestamp,query_type,latency_ms,connection_time_ms,execution_time_ms,success,error_class";
+
+/// Streams one CSV row per finished query to a file as the run goes, for analysis
+/// outside the simulator. Rows are buffered and written in blocks, so the file
+/// lags the run by a few kilobytes until `finish`.
+#[derive(Debug)]
+pub struct RawMetricsWriter {
+    path: String,
+    labels: CsvLabels,
+    /// `None` once a write has failed; the run carries on without the file
+    file: Mutex<Option<BufWriter<File>>>,
+}
+
+impl RawMetricsWriter {
+    pub fn create(path: &str, labels: CsvLabels) -> anyhow::Result<Self> {
+        let mut file = BufWriter::with_capacity(
+            64 * 1024,
+            File::create(path)
+                .with_context(|| format!("Failed to create --raw-metrics {}", path))?,
+        );
+        writeln!(file, "{}{}", COLUMNS, labels.header())?;
+        Ok(Self {
+            path: path.to_string(),
+            labels,
+            file: Mutex::new(Some(file)),
+        })
+    }
+
+    /// Append `metric`, stamped with the time it finished
+    pub fn record(&self, metric: &QueryMetric) {
+        let error_class = metric
+            .failure_kind
+            .and_then(|kind| serde_json::to_value(kind).ok())
+            .and_then(|kind| kind.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let row = format!(
+            "{},{},{:.3},{:.3},{:.3},{},{}{}\n",
+            Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+            format!("{:?}", metric.query_type).to_lowercase(),
+            metric.latency.as_secs_f64() * 1000.0,
+            metric.connection_time.as_secs_f64() * 1000.0,
+            metric.query_execution_time.as_secs_f64() * 1000.0,
+            metric.success,
+            error_class,
+            self.labels.values()
+        );
+
+        let mut file = self.file.lock().unwrap();
+        if let Some(writer) = file.as_mut() {
+            if let Err(e) = writer.write_all(row.as_bytes()) {
+                warn!("Stopped writing raw metrics to {}: {}", self.path, e);
+                *file = None;
+            }
+        }
+    }
+
+    /// Write out the buffered rows
+    pub fn finish(&self) -> anyhow::Result<()> {
+        match self.file.lock().unwrap().as_mut() {
+            Some(writer) => writer
+                .flush()
+                .with_context(|| format!("Failed to write --raw-metrics {}", self.path)),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use crate::failures::FailureKind;
+use crate::query_types::{self, QueryTypeTally};
+use crate::writes::{self, AffectedRowStats, WriteKind};
+use crate::{
+    FailureLatency, FailureStage, LatencySummary, QueryMetric, QueryType, RobustLatencyStats,
+};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hdrhistogram::serialization::{Deserializer, Serializer, V2DeflateSerializer};
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Significant decimal digits kept by every histogram: values are recorded to
+/// within 0.1%
+const SIGNIFICANT_DIGITS: u8 = 3;
+
+/// HdrHistogram of durations at nanosecond resolution. Memory is bounded by the
+/// value range rather than the sample count; sum, min and max are kept exactly so
+/// the mean and extremes are not quantized. Serialized with the histogram in
+/// HdrHistogram's compressed V2 format, so shard reports merge without losing
+/// precision.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "EncodedHistogram", into = "EncodedHistogram")]
+pub struct LatencyHistogram {
+    histogram: Histogram<u64>,
+    sum_ms: f64,
+    /// Sum of squares, for the standard deviation
+    sum_sq_ms: f64,
+    min_ms: f64,
+    max_ms: f64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            // Auto-resizing, so no upper bound has to be guessed up front
+            histogram: Histogram::new(SIGNIFICANT_DIGITS).expect("valid histogram precision"),
+            sum_ms: 0.0,
+            sum_sq_ms: 0.0,
+            min_ms: 0.0,
+            max_ms: 0.0,
+        }
+    }
+}
+
+/// Wire form of a [`LatencyHistogram`]
+#[derive(Serialize, Deserialize)]
+struct EncodedHistogram {
+    /// Base64 of the V2 deflate encoding
+    histogram: String,
+    sum_ms: f64,
+    sum_sq_ms: f64,
+    min_ms: f64,
+    max_ms: f64,
+}
+
+impl From<LatencyHistogram> for EncodedHistogram {
+    fn from(latency: LatencyHistogram) -> Self {
+        let mut bytes = Vec::new();
+        V2DeflateSerializer::new()
+            .serialize(&latency.histogram, &mut bytes)
+            .expect("writing to memory cannot fail");
+        Self {
+            histogram: BASE64.encode(bytes),
+            sum_ms: latency.sum_ms,
+            sum_sq_ms: latency.sum_sq_ms,
+            min_ms: latency.min_ms,
+            max_ms: latency.max_ms,
+        }
+    }
+}
+
+impl TryFrom<EncodedHistogram> for LatencyHistogram {
+    type Error = String;
+
+    fn try_from(encoded: EncodedHistogram) -> Result<Self, Self::Error> {
+        let bytes = BASE64
+            .decode(&encoded.histogram)
+            .map_err(|e| format!("invalid histogram encoding: {}", e))?;
+        let mut histogram: Histogram<u64> = Deserializer::new()
+            .deserialize(&mut bytes.as_slice())
+            .map_err(|e| format!("invalid histogram: {:?}", e))?;
+        histogram.auto(true);
+        Ok(Self {
+            histogram,
+            sum_ms: encoded.sum_ms,
+            sum_sq_ms: encoded.sum_sq_ms,
+            min_ms: encoded.min_ms,
+            max_ms: encoded.max_ms,
+        })
+    }
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, duration: Duration) {
+        self.record_ms(duration.as_secs_f64() * 1000.0);
+    }
+
+    /// Record a value already in milliseconds, e.g. a time reported by the server;
+    /// negative values count as zero in the quantiles
+    pub fn record_ms(&mut self, ms: f64) {
+        if self.count() == 0 || ms < self.min_ms {
+            self.min_ms = ms;
+        }
+        if self.count() == 0 || ms > self.max_ms {
+            self.max_ms = ms;
+        }
+        self.sum_ms += ms;
+        self.sum_sq_ms += ms * ms;
+        // Saturating: negative values become 0, and anything past u64 its maximum
+        let nanos = (ms * 1_000_000.0).round() as u64;
+        // `record` grows the histogram; only values beyond its largest possible
+        // range are clamped
+        if self.histogram.record(nanos).is_err() {
+            self.histogram.saturating_record(nanos);
+        }
+    }
+
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        if other.count() == 0 {
+            return;
+        }
+        if self.count() == 0 || other.min_ms < self.min_ms {
+            self.min_ms = other.min_ms;
+        }
+        if self.count() == 0 || other.max_ms > self.max_ms {
+            self.max_ms = other.max_ms;
+        }
+        self.sum_ms += other.sum_ms;
+        self.sum_sq_ms += other.sum_sq_ms;
+        self.histogram
+            .add(&other.histogram)
+            .expect("auto-resizing histograms always merge");
+    }
+
+    pub fn count(&self) -> u64 {
+        self.histogram.len()
+    }
+
+    pub fn mean_ms(&self) -> f64 {
+        if self.count() == 0 {
+            0.0
+        } else {
+            self.sum_ms / self.count() as f64
+        }
+    }
+
+    pub fn min_ms(&self) -> f64 {
+        self.min_ms
+    }
+
+    pub fn max_ms(&self) -> f64 {
+        self.max_ms
+    }
+
+    /// Population standard deviation, in milliseconds
+    pub fn std_dev_ms(&self) -> f64 {
+        if self.count() == 0 {
+            return 0.0;
+        }
+        let mean = self.mean_ms();
+        // Rounding can leave a hair below zero for constant latencies
+        (self.sum_sq_ms / self.count() as f64 - mean * mean)
+            .max(0.0)
+            .sqrt()
+    }
+
+    /// Latency at quantile `q` (e.g. 0.999 for p99.9), in milliseconds, by nearest
+    /// rank; see `crate::nearest_rank`
+    pub fn quantile_ms(&self, q: f64) -> f64 {
+        if self.count() == 0 {
+            return 0.0;
+        }
+        self.value_at_rank(crate::nearest_rank(self.count(), q))
+    }
+
+    pub fn summary(&self) -> LatencySummary {
+        LatencySummary {
+            count: self.count() as usize,
+            average_ms: self.mean_ms(),
+            min_ms: self.min_ms,
+            max_ms: self.max_ms,
+            p50_ms: self.quantile_ms(0.5),
+            p95_ms: self.quantile_ms(0.95),
+            p99_ms: self.quantile_ms(0.99),
+        }
+    }
+
+    /// Distinct recorded values in ascending order, in milliseconds, with how
+    /// many samples fell on each
+    fn values(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        self.histogram.iter_recorded().map(|v| {
+            let nanos = self.histogram.median_equivalent(v.value_iterated_to());
+            (
+                (nanos as f64 / 1_000_000.0).clamp(self.min_ms, self.max_ms),
+                v.count_at_value(),
+            )
+        })
+    }
+
+    /// Value at 0-based rank `rank` of the sorted samples
+    fn value_at_rank(&self, rank: u64) -> f64 {
+        let mut seen = 0;
+        for (value, count) in self.values() {
+            seen += count;
+            if seen > rank {
+                return value;
+            }
+        }
+        self.max_ms
+    }
+
+    /// Mean after discarding `fraction` of the samples from each end
+    fn trimmed_mean(&self, fraction: f64) -> f64 {
+        let n = self.count();
+        let cut = (n as f64 * fraction) as u64;
+        let (kept_from, kept_to) = (cut, n - cut);
+        if kept_to <= kept_from {
+            return 0.0;
+        }
+
+        let mut rank = 0;
+        let mut sum = 0.0;
+        for (value, count) in self.values() {
+            let kept = (rank + count)
+                .min(kept_to)
+                .saturating_sub(rank.max(kept_from));
+            sum += value * kept as f64;
+            rank += count;
+        }
+        sum / (kept_to - kept_from) as f64
+    }
+
+    /// Mean, standard deviation and maximum after clamping `fraction` of the
+    /// samples at each end to the nearest retained value
+    fn winsorized_stats(&self, fraction: f64) -> (f64, f64, f64) {
+        let n = self.count();
+        if n == 0 {
+            return (0.0, 0.0, 0.0);
+        }
+
+        let cut = ((n as f64 * fraction) as u64).min((n - 1) / 2);
+        let low = self.value_at_rank(cut);
+        let high = self.value_at_rank(n - 1 - cut);
+        let clamped = || self.values().map(|(v, count)| (v.clamp(low, high), count));
+
+        let mean = clamped().map(|(v, count)| v * count as f64).sum::<f64>() / n as f64;
+        let variance = clamped()
+            .map(|(v, count)| (v - mean).powi(2) * count as f64)
+            .sum::<f64>()
+            / n as f64;
+
+        (mean, variance.sqrt(), high)
+    }
+}
+
+/// Folds query metrics into histograms and counters as they arrive, so a run
+/// keeps constant memory however many queries it executes. Shards send theirs to
+/// the parent process, which merges them into one result.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MetricsRecorder {
+    pub total_queries: usize,
+    pub successful_queries: usize,
+    pub connection_recycles: usize,
+    /// Timings of successful queries
+    pub latency: LatencyHistogram,
+    pub connection_time: LatencyHistogram,
+    pub query_execution_time: LatencyHistogram,
+    pub recycled_acquire: LatencyHistogram,
+    pub pooled_acquire: LatencyHistogram,
+    /// Time to error of failed queries, by the stage they failed in
+    connection_failures: LatencyHistogram,
+    query_failures: LatencyHistogram,
+    pub affected_rows: BTreeMap<WriteKind, AffectedRowStats>,
+    /// Every query, failed ones included, by the type it ran
+    pub query_types: BTreeMap<QueryType, QueryTypeTally>,
+    /// Every query, failed ones included, by the tenant it ran for (--tenants)
+    pub tenants: BTreeMap<u32, QueryTypeTally>,
+    /// Failed queries by cause
+    pub failures: BTreeMap<FailureKind, u64>,
+}
+
+impl MetricsRecorder {
+    pub fn record(&mut self, metric: &QueryMetric) {
+        self.total_queries += 1;
+        query_types::tally(&mut self.query_types, metric);
+        if let Some(tenant) = metric.tenant {
+            self.tenants.entry(tenant).or_default().record(metric);
+        }
+        if metric.connection_recycled {
+            self.connection_recycles += 1;
+        }
+        if let Some(kind) = metric.failure_kind {
+            *self.failures.entry(kind).or_default() += 1;
+        }
+
+        if metric.success {
+            self.successful_queries += 1;
+            self.latency.record(metric.latency);
+            self.connection_time.record(metric.connection_time);
+            self.query_execution_time
+                .record(metric.query_execution_time);
+            if metric.connection_recycled {
+                self.recycled_acquire.record(metric.connection_time);
+            } else {
+                self.pooled_acquire.record(metric.connection_time);
+            }
+            writes::tally(&mut self.affected_rows, &metric.writes);
+        } else if metric.failure_stage == Some(FailureStage::Connection) {
+            self.connection_failures.record(metric.latency);
+        } else {
+            self.query_failures.record(metric.latency);
+        }
+    }
+
+    pub fn extend(&mut self, metrics: impl IntoIterator<Item = QueryMetric>) {
+        for metric in metrics {
+            self.record(&metric);
+        }
+    }
+
+    /// Add another recorder's queries, e.g. the warmup's with --include-warmup
+    pub fn merge(&mut self, other: &MetricsRecorder) {
+        self.total_queries += other.total_queries;
+        self.successful_queries += other.successful_queries;
+        self.connection_recycles += other.connection_recycles;
+        self.latency.merge(&other.latency);
+        self.connection_time.merge(&other.connection_time);
+        self.query_execution_time.merge(&other.query_execution_time);
+        self.recycled_acquire.merge(&other.recycled_acquire);
+        self.pooled_acquire.merge(&other.pooled_acquire);
+        self.connection_failures.merge(&other.connection_failures);
+        self.query_failures.merge(&other.query_failures);
+        for (kind, stats) in &other.affected_rows {
+            self.affected_rows.entry(*kind).or_default().merge(stats);
+        }
+        for (query_type, tally) in &other.query_types {
+            self.query_types
+                .entry(query_type.clone())
+                .or_default()
+                .merge(tally);
+        }
+        for (tenant, tally) in &other.tenants {
+            self.tenants.entry(*tenant).or_default().merge(tally);
+        }
+        for (kind, count) in &other.failures {
+            *self.failures.entry(*kind).or_default() += count;
+        }
+    }
+
+    /// How long failing requests held resources; `None` when nothing failed
+    pub fn failure_latency(&self) -> Option<FailureLatency> {
+        let mut overall = self.connection_failures.clone();
+        overall.merge(&self.query_failures);
+        if overall.count() == 0 {
+            return None;
+        }
+
+        Some(FailureLatency {
+            overall: overall.summary(),
+            connection_failures: self.connection_failures.count() as usize,
+            query_failures: self.query_failures.count() as usize,
+            average_time_to_connection_error_ms: self.connection_failures.mean_ms(),
+            average_time_to_query_error_ms: self.query_failures.mean_ms(),
+        })
+    }
+
+    pub fn robust_latency(&self) -> RobustLatencyStats {
+        let (winsorized_mean_1pct_ms, winsorized_std_dev_1pct_ms, winsorized_max_1pct_ms) =
+            self.latency.winsorized_stats(0.01);
+        let (winsorized_mean_5pct_ms, winsorized_std_dev_5pct_ms, winsorized_max_5pct_ms) =
+            self.latency.winsorized_stats(0.05);
+
+        RobustLatencyStats {
+            trimmed_mean_1pct_ms: self.latency.trimmed_mean(0.01),
+            trimmed_mean_5pct_ms: self.latency.trimmed_mean(0.05),
+            winsorized_mean_1pct_ms,
+            winsorized_mean_5pct_ms,
+            winsorized_std_dev_1pct_ms,
+            winsorized_std_dev_5pct_ms,
+            winsorized_max_1pct_ms,
+            winsorized_max_5pct_ms,
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::interval_sink::{self, IntervalAggregator};
+use crate::metadata::hostname;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Prefix of every metric name
+const METRIC_PREFIX: &str = "traffic_simulator";
+
+/// Pushes the metrics of every interval to a Prometheus remote-write endpoint
+/// (Grafana Cloud, Mimir, Cortex, Thanos receive, VictoriaMetrics, ...), for
+/// load generators that cannot be scraped
+#[derive(Debug)]
+pub struct RemoteWriteSink {
+    client: reqwest::Client,
+    url: String,
+    auth: Option<RemoteWriteAuth>,
+    /// Sorted label pairs every series carries, `__name__` excluded
+    labels: Vec<(String, String)>,
+    interval: IntervalAggregator,
+}
+
+#[derive(Debug)]
+enum RemoteWriteAuth {
+    Basic { username: String, password: String },
+    Bearer(String),
+}
+
+impl RemoteWriteSink {
+    /// Labels, the run id and the host become labels of every series. Credentials
+    /// are read from `REMOTE_WRITE_USERNAME` and `REMOTE_WRITE_PASSWORD` (basic
+    /// auth, as Grafana Cloud uses) or `REMOTE_WRITE_TOKEN` (a bearer token).
+    pub fn new(
+        url: &str,
+        run_id: &str,
+        shard: Option<usize>,
+        labels: &BTreeMap<String, String>,
+    ) -> Self {
+        let auth = match (
+            std::env::var("REMOTE_WRITE_USERNAME"),
+            std::env::var("REMOTE_WRITE_PASSWORD"),
+            std::env::var("REMOTE_WRITE_TOKEN"),
+        ) {
+            (Ok(username), Ok(password), _) => Some(RemoteWriteAuth::Basic { username, password }),
+            (_, _, Ok(token)) => Some(RemoteWriteAuth::Bearer(token)),
+            _ => None,
+        };
+
+        let mut all_labels: BTreeMap<String, String> = labels
+            .iter()
+            .map(|(key, value)| (label_name(key), value.clone()))
+            .collect();
+        all_labels.insert("job".to_string(), METRIC_PREFIX.to_string());
+        all_labels.insert("run_id".to_string(), run_id.to_string());
+        all_labels
+            .entry("instance".to_string())
+            .or_insert_with(hostname);
+        if let Some(shard) = shard {
+            all_labels.insert("shard".to_string(), shard.to_string());
+        }
+
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            auth,
+            labels: all_labels.into_iter().collect(),
+            interval: IntervalAggregator::new(),
+        }
+    }
+
+    pub fn record(&self, latency: Duration, success: bool) {
+        self.interval.record(latency, success);
+    }
+
+    /// Write the queries recorded since the last flush as one sample per series
+    pub async fn flush(&self, pending: usize) -> anyhow::Result<()> {
+        let summary = self.interval.take();
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+
+        let mut series = vec![
+            ("queries_total", None, summary.total_queries as f64),
+            ("failures_total", None, summary.total_failures as f64),
+            ("queries_per_second", None, summary.qps),
+            (
+                "error_rate",
+                None,
+                summary.failures as f64 / summary.queries.max(1) as f64,
+            ),
+            ("pending_queries", None, pending as f64),
+        ];
+        // Latency is left out of intervals without successful queries rather than
+        // reported as 0
+        if let Some(latency) = &summary.latency {
+            series.extend([
+                ("latency_seconds_avg", None, latency.average_ms / 1000.0),
+                ("latency_seconds_max", None, latency.max_ms / 1000.0),
+                ("latency_seconds", Some("0.5"), latency.p50_ms / 1000.0),
+                ("latency_seconds", Some("0.95"), latency.p95_ms / 1000.0),
+                ("latency_seconds", Some("0.99"), latency.p99_ms / 1000.0),
+            ]);
+        }
+
+        let mut request = Vec::new();
+        for (name, quantile, value) in series {
+            let mut labels = self.labels.clone();
+            labels.push((
+                "__name__".to_string(),
+                format!("{}_{}", METRIC_PREFIX, name),
+            ));
+            if let Some(quantile) = quantile {
+                labels.push(("quantile".to_string(), quantile.to_string()));
+            }
+            labels.sort();
+            write_message(&mut request, 1, &encode_series(&labels, value, timestamp));
+        }
+        let body = snap::raw::Encoder::new().compress_vec(&request)?;
+
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/x-protobuf")
+            .header("Content-Encoding", "snappy")
+            .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+            .body(body);
+        request = match &self.auth {
+            Some(RemoteWriteAuth::Basic { username, password }) => {
+                request.basic_auth(username, Some(password))
+            }
+            Some(RemoteWriteAuth::Bearer(token)) => request.bearer_auth(token),
+            None => request,
+        };
+        let response = request.timeout(Duration::from_secs(5)).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Remote write returned {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Push samples every `interval` until aborted
+pub async fn run_remote_writer(ctx: std::sync::Arc<crate::QueryContext>, interval: Duration) {
+    let Some(sink) = &ctx.remote_write else {
+        return;
+    };
+    interval_sink::flush_every(interval, "the remote-write endpoint", || {
+        sink.flush(ctx.pending_work.current())
+    })
+    .await;
+}
+
+/// A `TimeSeries` message of the remote-write protocol holding one sample
+fn encode_series(labels: &[(String, String)], value: f64, timestamp_ms: i64) -> Vec<u8> {
+    let mut series = Vec::new();
+    for (name, value) in labels {
+        let mut label = Vec::new();
+        write_message(&mut label, 1, name.as_bytes());
+        write_message(&mut label, 2, value.as_bytes());
+        write_message(&mut series, 1, &label);
+    }
+    let mut sample = Vec::new();
+    // Field 1, a double (wire type 1, 64-bit); field 2, an int64 (wire type 0, varint)
+    sample.push(1 << 3 | 1);
+    sample.extend_from_slice(&value.to_le_bytes());
+    sample.push(2 << 3);
+    write_varint(&mut sample, timestamp_ms as u64);
+    write_message(&mut series, 2, &sample);
+    series
+}
+
+/// A length-delimited field (wire type 2): strings, bytes and nested messages
+fn write_message(buffer: &mut Vec<u8>, field: u8, bytes: &[u8]) {
+    buffer.push(field << 3 | 2);
+    write_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// A valid Prometheus label name: letters, digits and underscores, not starting
+/// with a digit
+fn label_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
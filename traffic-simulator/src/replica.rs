@@ -0,0 +1,292 @@
+use crate::credentials::CredentialProvider;
+use crate::net::AddressPreference;
+use crate::pool_options::PoolOptions;
+use crate::recorder::LatencyHistogram;
+use crate::tls::TlsOptions;
+use crate::{create_connection_pool, LatencySummary};
+use deadpool_postgres::Pool;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Table on the primary that lag probes write their sentinel rows to
+const SENTINEL_TABLE: &str = "traffic_simulator_replication_sentinel";
+
+/// How long a sentinel write may take to show up on the replica before the probe
+/// counts as timed out
+const LAG_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pause between replica reads while waiting for a sentinel write
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A read replica (--read-url) that the order workload's reads are sent to
+#[derive(Debug)]
+pub struct ReadReplica {
+    pool: Pool,
+    reads: AtomicU64,
+    lag: Mutex<ReplicaStats>,
+}
+
+/// Mergeable replica routing and lag measurements, handed from shards to the parent
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ReplicaStats {
+    enabled: bool,
+    reads: u64,
+    timeouts: u64,
+    /// Time from a sentinel write committing on the primary until it was visible on
+    /// the replica, in milliseconds
+    lag: LatencyHistogram,
+}
+
+/// Read routing and replication lag measured while the workload ran
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ReplicaReport {
+    /// Requests sent to the replica instead of the primary
+    reads: u64,
+    /// Sentinel writes not visible on the replica within 10 seconds
+    lag_timeouts: u64,
+    replication_lag: LatencySummary,
+}
+
+impl ReadReplica {
+    /// Create the replica's pool and check that it accepts connections
+    pub async fn connect(
+        read_url: &str,
+        max_connections: usize,
+        preference: Option<AddressPreference>,
+        tls: &TlsOptions,
+        credentials: Option<&Arc<CredentialProvider>>,
+        query_timeout: Option<Duration>,
+        pool_options: &PoolOptions,
+    ) -> anyhow::Result<Self> {
+        let pool = create_connection_pool(
+            read_url,
+            max_connections,
+            preference,
+            tls,
+            credentials,
+            query_timeout,
+            pool_options,
+        )
+        .await?;
+        let client = pool
+            .get()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to connect to the read replica: {}", e))?;
+        let in_recovery: bool = client
+            .query_one("SELECT pg_is_in_recovery()", &[])
+            .await?
+            .get(0);
+        // The warmup and the measured run each connect, so warn only once
+        static NOT_IN_RECOVERY: Once = Once::new();
+        if !in_recovery {
+            NOT_IN_RECOVERY.call_once(|| warn!("--read-url points at a server that is not in recovery; replication lag will read as zero"));
+        }
+        drop(client);
+
+        Ok(Self {
+            pool,
+            reads: AtomicU64::new(0),
+            lag: Mutex::new(ReplicaStats {
+                enabled: true,
+                ..ReplicaStats::default()
+            }),
+        })
+    }
+
+    /// The pool a read is executed on; counts the read
+    pub fn route_read(&self) -> &Pool {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        &self.pool
+    }
+
+    /// The replica's pool, for reads that are not workload queries
+    pub fn pool(&self) -> &Pool {
+        &self.pool
+    }
+
+    pub fn stats(&self) -> ReplicaStats {
+        let mut stats = self.lag.lock().unwrap().clone();
+        stats.reads = self.reads.load(Ordering::Relaxed);
+        stats
+    }
+}
+
+/// Dedicated connections for measuring replication lag, so probes neither wait for
+/// nor hold up workload connections
+pub struct LagProbe {
+    primary: Pool,
+    replica: Pool,
+    /// Key of this run's sentinel row, so concurrent simulators don't collide
+    writer: String,
+}
+
+impl LagProbe {
+    pub async fn connect(
+        database_url: &str,
+        read_url: &str,
+        preference: Option<AddressPreference>,
+        tls: &TlsOptions,
+        credentials: Option<&Arc<CredentialProvider>>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            primary: create_connection_pool(
+                database_url,
+                1,
+                preference,
+                tls,
+                credentials,
+                None,
+                &PoolOptions::default(),
+            )
+            .await?,
+            replica: create_connection_pool(
+                read_url,
+                1,
+                preference,
+                tls,
+                credentials,
+                None,
+                &PoolOptions::default(),
+            )
+            .await?,
+            writer: uuid::Uuid::new_v4().to_string(),
+        })
+    }
+
+    /// Write a new token to this run's sentinel row on the primary and time how long
+    /// the replica takes to return it
+    async fn probe(&self, replica: &ReadReplica, token: i64) -> anyhow::Result<()> {
+        self.primary
+            .get()
+            .await?
+            .execute(
+                &format!(
+                    "INSERT INTO {} (writer, token, written_at) VALUES ($1, $2, now())
+                     ON CONFLICT (writer) DO UPDATE SET token = EXCLUDED.token, written_at = now()",
+                    SENTINEL_TABLE
+                ),
+                &[&self.writer, &token],
+            )
+            .await?;
+        let committed = Instant::now();
+
+        let client = self.replica.get().await?;
+        let lookup = format!("SELECT token FROM {} WHERE writer = $1", SENTINEL_TABLE);
+        while committed.elapsed() < LAG_TIMEOUT {
+            let visible = client
+                .query_opt(&lookup, &[&self.writer])
+                .await?
+                .is_some_and(|row| row.get::<_, i64>(0) == token);
+            if visible {
+                let mut stats = replica.lag.lock().unwrap();
+                stats.lag.record(committed.elapsed());
+                return Ok(());
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+
+        replica.lag.lock().unwrap().timeouts += 1;
+        Ok(())
+    }
+
+    /// Delete this run's sentinel row once the lag monitor has stopped
+    pub async fn remove_sentinel(&self) {
+        let removed = match self.primary.get().await {
+            Ok(client) => client
+                .execute(
+                    &format!("DELETE FROM {} WHERE writer = $1", SENTINEL_TABLE),
+                    &[&self.writer],
+                )
+                .await
+                .map(|_| ())
+                .map_err(anyhow::Error::from),
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = removed {
+            warn!("Failed to remove the replication sentinel row: {}", e);
+        }
+    }
+}
+
+/// Probe replication lag every `interval` until aborted. A failed probe is logged
+/// and the next one tried.
+pub async fn run_lag_monitor(probe: Arc<LagProbe>, replica: Arc<ReadReplica>, interval: Duration) {
+    let create = format!(
+        "CREATE TABLE IF NOT EXISTS {} (writer TEXT PRIMARY KEY, token BIGINT NOT NULL, written_at TIMESTAMPTZ NOT NULL)",
+        SENTINEL_TABLE
+    );
+    let created = match probe.primary.get().await {
+        Ok(client) => client
+            .batch_execute(&create)
+            .await
+            .map_err(anyhow::Error::from),
+        Err(e) => Err(e.into()),
+    };
+    if let Err(e) = created {
+        warn!(
+            "Not measuring replication lag: failed to create {}: {}",
+            SENTINEL_TABLE, e
+        );
+        return;
+    }
+
+    let mut ticker = tokio::time::interval(interval);
+    for token in 1.. {
+        ticker.tick().await;
+        if let Err(e) = probe.probe(&replica, token).await {
+            warn!("Replication lag probe failed: {}", e);
+        }
+    }
+}
+
+impl ReplicaStats {
+    pub fn merge(&mut self, other: &ReplicaStats) {
+        self.enabled |= other.enabled;
+        self.reads += other.reads;
+        self.timeouts += other.timeouts;
+        self.lag.merge(&other.lag);
+    }
+
+    /// `None` unless --read-url was given
+    pub fn report(&self) -> Option<ReplicaReport> {
+        self.enabled.then(|| ReplicaReport {
+            reads: self.reads,
+            lag_timeouts: self.timeouts,
+            replication_lag: self.lag.summary(),
+        })
+    }
+}
+
+pub fn display_read_replica(report: &ReplicaReport, total_queries: usize) {
+    println!("\n🪞 Read Replica:");
+    println!(
+        "   Reads Routed:           {:>8} ({:.1}% of queries)",
+        report.reads,
+        report.reads as f64 / total_queries.max(1) as f64 * 100.0
+    );
+    if report.replication_lag.count > 0 {
+        println!(
+            "   Replication Lag (ms):   avg {:.2}  p50 {:.2}  p95 {:.2}  p99 {:.2}  max {:.2}",
+            report.replication_lag.average_ms,
+            report.replication_lag.p50_ms,
+            report.replication_lag.p95_ms,
+            report.replication_lag.p99_ms,
+            report.replication_lag.max_ms
+        );
+        println!(
+            "   Lag Probes:             {:>8}",
+            report.replication_lag.count
+        );
+    }
+    if report.lag_timeouts > 0 {
+        println!(
+            "   ⚠️  Probes Timed Out:     {:>8} (not visible within {}s)",
+            report.lag_timeouts,
+            LAG_TIMEOUT.as_secs()
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use crate::schema;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Aggregate many exported run files into summary statistics and trend lines
+#[derive(clap::Args, Debug)]
+pub struct ReportArgs {
+    /// Glob pattern selecting run result files (e.g. "results/*.json")
+    #[arg(short, long)]
+    glob: String,
+
+    /// Group runs by the value of this label instead of by their full label set
+    #[arg(long)]
+    group_by: Option<String>,
+}
+
+/// The subset of an exported run file needed for aggregation. Results are upgraded
+/// to the current schema version first and unknown fields are ignored, so run
+/// files from older releases can be mixed in one report.
+#[derive(Debug, Deserialize)]
+struct RunFile {
+    #[serde(default)]
+    started_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+    result: RunFileResult,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunFileResult {
+    pub total_queries: usize,
+    pub failed_queries: usize,
+    pub queries_per_second: f64,
+    pub p95_latency_ms: f64,
+    pub p99_latency_ms: f64,
+    #[serde(default)]
+    pub average_latency_ms: f64,
+    #[serde(default)]
+    pub p50_latency_ms: f64,
+    #[serde(default)]
+    pub p999_latency_ms: f64,
+    #[serde(default)]
+    pub connection_efficiency: f64,
+    #[serde(default)]
+    pub average_pooled_acquire_ms: f64,
+}
+
+#[derive(Debug)]
+pub struct RunEntry {
+    path: PathBuf,
+    started_at: DateTime<Utc>,
+    labels: BTreeMap<String, String>,
+    pub result: RunFileResult,
+}
+
+pub fn run_report(args: &ReportArgs) -> anyhow::Result<()> {
+    let mut runs = Vec::new();
+    for path in glob::glob(&args.glob)? {
+        let path = path?;
+        match load_run(&path) {
+            Ok(run) => runs.push(run),
+            Err(e) => eprintln!("⚠️  Skipping {}: {}", path.display(), e),
+        }
+    }
+
+    if runs.is_empty() {
+        return Err(anyhow::anyhow!("No run files matched '{}'", args.glob));
+    }
+
+    runs.sort_by_key(|run| run.started_at);
+
+    let mut groups: BTreeMap<String, Vec<&RunEntry>> = BTreeMap::new();
+    for run in &runs {
+        groups
+            .entry(group_key(run, args.group_by.as_deref()))
+            .or_default()
+            .push(run);
+    }
+
+    println!("\n📚 MULTI-RUN AGGREGATE REPORT");
+    println!("===============================================");
+    println!("   Run Files:              {:>8}", runs.len());
+    println!("   Groups:                 {:>8}", groups.len());
+
+    for (key, group) in &groups {
+        display_group(key, group);
+    }
+
+    println!("===============================================\n");
+
+    Ok(())
+}
+
+pub fn load_run(path: &Path) -> anyhow::Result<RunEntry> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut run: serde_json::Value = serde_json::from_str(&contents)?;
+    match run.get_mut("result") {
+        Some(result) => schema::upgrade_result(result)?,
+        None => return Err(anyhow::anyhow!("missing field `result`")),
+    }
+    let run: RunFile = serde_json::from_value(run)?;
+
+    // Fall back to the file modification time for runs without a recorded start
+    let started_at = match run.started_at {
+        Some(started_at) => started_at,
+        None => DateTime::<Utc>::from(std::fs::metadata(path)?.modified()?),
+    };
+
+    Ok(RunEntry {
+        path: path.to_path_buf(),
+        started_at,
+        labels: run.labels,
+        result: run.result,
+    })
+}
+
+fn group_key(run: &RunEntry, group_by: Option<&str>) -> String {
+    match group_by {
+        Some(key) => format!(
+            "{}={}",
+            key,
+            run.labels.get(key).map(String::as_str).unwrap_or("(unset)")
+        ),
+        None if run.labels.is_empty() => "(unlabeled)".to_string(),
+        None => run
+            .labels
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(","),
+    }
+}
+
+fn display_group(key: &str, runs: &[&RunEntry]) {
+    let qps: Vec<f64> = runs.iter().map(|r| r.result.queries_per_second).collect();
+    let p95: Vec<f64> = runs.iter().map(|r| r.result.p95_latency_ms).collect();
+    let p99: Vec<f64> = runs.iter().map(|r| r.result.p99_latency_ms).collect();
+    let total_queries: usize = runs.iter().map(|r| r.result.total_queries).sum();
+    let failed_queries: usize = runs.iter().map(|r| r.result.failed_queries).sum();
+
+    // Trend lines are fitted against days since the first run in the group
+    let first = runs[0].started_at;
+    let days: Vec<f64> = runs
+        .iter()
+        .map(|r| (r.started_at - first).num_seconds() as f64 / 86400.0)
+        .collect();
+
+    println!("\n🏷️  Group: {}", key);
+    println!("   Runs:                   {:>8}", runs.len());
+    println!(
+        "   Period:                 {} → {}",
+        first.format("%Y-%m-%d %H:%M"),
+        runs[runs.len() - 1].started_at.format("%Y-%m-%d %H:%M")
+    );
+    println!(
+        "   Error Rate:             {:>7.2}%",
+        if total_queries > 0 {
+            failed_queries as f64 / total_queries as f64 * 100.0
+        } else {
+            0.0
+        }
+    );
+    println!("                             mean      min      max   stddev");
+    print_stat_row("Queries/Second:", &qps);
+    print_stat_row("P95 Latency (ms):", &p95);
+    print_stat_row("P99 Latency (ms):", &p99);
+
+    match (linear_slope(&days, &qps), linear_slope(&days, &p99)) {
+        (Some(qps_slope), Some(p99_slope)) => {
+            println!("   QPS Trend:              {:>+8.2}/day", qps_slope);
+            println!("   P99 Trend:              {:>+8.2}ms/day", p99_slope);
+        }
+        _ => println!("   Trend:                  (needs runs at 2+ distinct times)"),
+    }
+
+    println!("   Runs over time:");
+    for run in runs {
+        println!(
+            "     {}  {:>10.1} QPS  p99 {:>8.2}ms  {}",
+            run.started_at.format("%Y-%m-%d %H:%M"),
+            run.result.queries_per_second,
+            run.result.p99_latency_ms,
+            run.path.display()
+        );
+    }
+}
+
+fn print_stat_row(name: &str, values: &[f64]) {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    println!(
+        "   {:<21}{:>9.2}{:>9.2}{:>9.2}{:>9.2}",
+        name,
+        mean,
+        min,
+        max,
+        variance.sqrt()
+    );
+}
+
+/// Least-squares slope of `ys` against `xs`, or `None` when `xs` has no spread
+fn linear_slope(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let covariance: f64 = xs
+        .iter()
+        .zip(ys)
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = xs.iter().map(|x| (x - mean_x).powi(2)).sum();
+
+    if variance > f64::EPSILON {
+        Some(covariance / variance)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,224 @@
+use crate::failures::FailureKind;
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Longest delay exponential backoff may grow to between attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Transient failure classes that can be retried
+#[derive(
+    ValueEnum,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// Duplicate key (SQLSTATE 23505)
+    #[value(alias = "unique")]
+    UniqueViolation,
+    /// Serialization failure or deadlock (SQLSTATE 40001, 40P01)
+    #[value(alias = "serialization", alias = "deadlock")]
+    SerializationFailure,
+    /// Connection closed or reset by the server or network
+    ConnectionReset,
+    /// Statement or lock timeout (SQLSTATE 57014, 55P03), --query-timeout-ms or pool
+    /// checkout timeout
+    Timeout,
+}
+
+impl ErrorClass {
+    /// Retry class of a failure kind; `None` for failures no policy can retry
+    pub fn of(kind: FailureKind) -> Option<ErrorClass> {
+        match kind {
+            FailureKind::UniqueViolation => Some(ErrorClass::UniqueViolation),
+            FailureKind::SerializationFailure | FailureKind::Deadlock => {
+                Some(ErrorClass::SerializationFailure)
+            }
+            FailureKind::StatementTimeout
+            | FailureKind::QueryTimeout
+            | FailureKind::LockTimeout
+            | FailureKind::PoolWaitTimeout
+            | FailureKind::ConnectionTimeout => Some(ErrorClass::Timeout),
+            FailureKind::NetworkReset => Some(ErrorClass::ConnectionReset),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one; 1 disables retries
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub backoff: Duration,
+    /// Double the delay for each further retry instead of keeping it fixed
+    pub exponential: bool,
+    /// Record each failed attempt as its own (failed) query instead of folding
+    /// retries into the latency of a single query
+    pub count_as_new_query: bool,
+}
+
+impl RetryPolicy {
+    const NONE: RetryPolicy = RetryPolicy {
+        max_attempts: 1,
+        backoff: Duration::ZERO,
+        exponential: true,
+        count_as_new_query: false,
+    };
+
+    /// Delay before retry number `retry` (0-based)
+    pub fn delay(&self, retry: u32) -> Duration {
+        if !self.exponential {
+            return self.backoff;
+        }
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(MAX_BACKOFF)
+    }
+}
+
+/// How the delay between retries of --retry-on grows
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Backoff {
+    /// Double the delay for each further retry (capped at 5s)
+    #[value(name = "exp", alias = "exponential")]
+    Exponential,
+    /// Wait the same delay before every retry
+    Fixed,
+    /// Retry immediately
+    None,
+}
+
+/// The policy `--retry-on` applies to each of its classes
+pub fn shorthand_policy(max_retries: u32, backoff: Backoff, delay: Duration) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: max_retries.saturating_add(1),
+        backoff: if backoff == Backoff::None {
+            Duration::ZERO
+        } else {
+            delay
+        },
+        exponential: backoff == Backoff::Exponential,
+        count_as_new_query: false,
+    }
+}
+
+/// Parse `CLASS:ATTEMPTS[:BACKOFF][:new-query]`, e.g. `serialization-failure:3:50ms`
+pub fn parse_retry_policy(value: &str) -> Result<(ErrorClass, RetryPolicy), String> {
+    let mut parts = value.split(':');
+    let class = parts.next().unwrap_or_default();
+    let class = ErrorClass::from_str(&class.replace('_', "-"), true)
+        .map_err(|_| format!("unknown error class '{}'", class))?;
+
+    let max_attempts = parts
+        .next()
+        .ok_or_else(|| format!("missing attempt count in '{}'", value))?
+        .parse::<u32>()
+        .map_err(|_| format!("invalid attempt count in '{}'", value))?;
+    if max_attempts == 0 {
+        return Err("attempt count must be at least 1".to_string());
+    }
+
+    let mut policy = RetryPolicy {
+        max_attempts,
+        ..RetryPolicy::NONE
+    };
+    for part in parts {
+        if part == "new-query" {
+            policy.count_as_new_query = true;
+        } else {
+            policy.backoff = crate::parse_duration(part)?;
+        }
+    }
+
+    Ok((class, policy))
+}
+
+/// Retry policy per error class. Unique violations retry 5 times without backoff
+/// by default (order numbers are regenerated on each attempt); every other class
+/// fails immediately unless configured.
+#[derive(Debug)]
+pub struct RetryPolicies {
+    policies: BTreeMap<ErrorClass, RetryPolicy>,
+    stats: Mutex<BTreeMap<ErrorClass, RetryClassStats>>,
+}
+
+/// Retry outcomes for one error class
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RetryClassStats {
+    pub retries: u64,
+    pub recovered: u64,
+    /// Queries that still failed after their last attempt
+    pub abandoned: u64,
+}
+
+impl RetryPolicies {
+    pub fn new(overrides: &[(ErrorClass, RetryPolicy)]) -> Self {
+        let mut policies = BTreeMap::new();
+        policies.insert(
+            ErrorClass::UniqueViolation,
+            RetryPolicy {
+                max_attempts: 5,
+                ..RetryPolicy::NONE
+            },
+        );
+        policies.extend(overrides.iter().copied());
+
+        Self {
+            policies,
+            stats: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn policy(&self, class: ErrorClass) -> RetryPolicy {
+        self.policies
+            .get(&class)
+            .copied()
+            .unwrap_or(RetryPolicy::NONE)
+    }
+
+    pub fn record_retry(&self, class: ErrorClass) {
+        self.stats.lock().unwrap().entry(class).or_default().retries += 1;
+    }
+
+    /// Record how a query that was retried at least once finally ended
+    pub fn record_outcome(&self, class: ErrorClass, recovered: bool) {
+        let mut stats = self.stats.lock().unwrap();
+        let entry = stats.entry(class).or_default();
+        if recovered {
+            entry.recovered += 1;
+        } else {
+            entry.abandoned += 1;
+        }
+    }
+
+    pub fn stats(&self) -> BTreeMap<ErrorClass, RetryClassStats> {
+        self.stats.lock().unwrap().clone()
+    }
+}
+
+pub fn display_retry_stats(stats: &BTreeMap<ErrorClass, RetryClassStats>) {
+    println!("\n🔁 Retries:");
+    println!("   Error Class               Retries  Recovered  Abandoned");
+    for (class, stats) in stats {
+        println!(
+            "   {:<24}{:>9}{:>11}{:>11}",
+            class.to_possible_value().unwrap().get_name(),
+            stats.retries,
+            stats.recovered,
+            stats.abandoned
+        );
+    }
+}
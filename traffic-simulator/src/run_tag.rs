@@ -0,0 +1,91 @@
+use crate::stores::{self, StoreWeights};
+
+/// Longest run id accepted, keeping order numbers well within VARCHAR(50)
+const MAX_RUN_ID_LEN: usize = 16;
+
+/// What a run stamps on the rows it writes, so runs against the same database can
+/// be told apart and cleaned up one at a time: the run id is embedded in every
+/// order number, and with --tag-column also written to a column of the orders table
+/// on every insert and update
+#[derive(Debug)]
+pub struct RunTag {
+    pub run_id: String,
+    /// `, column` to append to an INSERT's column list, empty without --tag-column
+    insert_column: String,
+    /// `, 'run id'` to append to an INSERT's values
+    insert_value: String,
+    /// `, column = 'run id'` to append to an UPDATE's SET list
+    update_set: String,
+}
+
+impl RunTag {
+    /// The run id and column were validated by their parsers, so both can be
+    /// spliced into SQL directly and keep the statements' parameters unchanged
+    pub fn new(run_id: &str, column: Option<&str>) -> Self {
+        let (insert_column, insert_value, update_set) = match column {
+            Some(column) => (
+                format!(", {}", column),
+                format!(", '{}'", run_id),
+                format!(", {} = '{}'", column, run_id),
+            ),
+            None => Default::default(),
+        };
+        Self {
+            run_id: run_id.to_string(),
+            insert_column,
+            insert_value,
+            update_set,
+        }
+    }
+
+    /// Prefix of the order numbers an insert generates
+    pub fn order_number_prefix(&self, weights: Option<&StoreWeights>) -> String {
+        stores::order_number_prefix(&self.run_id, weights)
+    }
+
+    pub fn insert_column(&self) -> &str {
+        &self.insert_column
+    }
+
+    pub fn insert_value(&self) -> &str {
+        &self.insert_value
+    }
+
+    pub fn update_set(&self) -> &str {
+        &self.update_set
+    }
+}
+
+/// A fresh run id: 8 hex digits of a random UUID
+pub fn new_run_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..8].to_string()
+}
+
+/// Run ids are letters and digits only, so they can be embedded in order numbers
+/// and SQL literals
+pub fn parse_run_id(value: &str) -> Result<String, String> {
+    if value.is_empty()
+        || value.len() > MAX_RUN_ID_LEN
+        || !value.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Err(format!(
+            "run ids are 1 to {} letters and digits",
+            MAX_RUN_ID_LEN
+        ));
+    }
+    Ok(value.to_string())
+}
+
+/// An unquoted column name: a lowercase letter or underscore, then lowercase
+/// letters, digits and underscores
+pub fn parse_tag_column(value: &str) -> Result<String, String> {
+    let mut chars = value.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid {
+        return Err(format!("'{}' is not a plain lowercase column name", value));
+    }
+    Ok(value.to_string())
+}
@@ -0,0 +1,45 @@
+//! Random choices derived from a request's seed.
+//!
+//! Every feature that decides something per request (Mixed query type aside) draws
+//! from its own generator, seeded with the request's seed XOR a salt of its own, so
+//! its choices are reproducible with the run's seed yet independent of every other
+//! feature's: turning chaos faults on does not change which requests are explained.
+//! A salt is the feature's name in ASCII, zero-padded to 8 bytes (`0x6368_616f_7300_0000`
+//! is "chaos"). Decisions made per attempt also mix in the attempt number, so a
+//! retry draws again rather than repeating the first try's choice.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Generator for a choice made once per request
+pub fn request_rng(seed: u64, salt: u64) -> StdRng {
+    StdRng::seed_from_u64(seed ^ salt)
+}
+
+/// Generator for a choice made for each attempt of a request
+pub fn attempt_rng(seed: u64, salt: u64, attempt: u32) -> StdRng {
+    StdRng::seed_from_u64(seed ^ salt ^ (u64::from(attempt) << 32))
+}
+
+/// Picks a share of request attempts, e.g. the ones --explain-sample-rate explains
+#[derive(Debug, Clone, Copy)]
+pub struct Sampler {
+    rate: f64,
+    salt: u64,
+}
+
+impl Sampler {
+    pub fn new(rate: f64, salt: u64) -> Self {
+        Self { rate, salt }
+    }
+
+    /// Share of attempts picked
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Whether attempt `attempt` of the request with `seed` is picked
+    pub fn samples(&self, seed: u64, attempt: u32) -> bool {
+        attempt_rng(seed, self.salt, attempt).gen_bool(self.rate)
+    }
+}
@@ -0,0 +1,219 @@
+use crate::worker::WorkerPool;
+use crate::{
+    calculate_operational_result, Args, Backend, QueryContext, SimulationResult, TrafficIntensity,
+};
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Crontab's day-of-week numbers, 0 and 7 both being Sunday
+const WEEKDAYS: [&str; 8] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// How often the scheduled run checks which window it is in
+const WINDOW_CHECK: Duration = Duration::from_secs(1);
+
+/// One --schedule entry: each time `cron` fires, the load switches to `intensity`
+/// until another entry fires
+#[derive(Debug, Clone)]
+pub struct ScheduleWindow {
+    expression: String,
+    cron: cron::Schedule,
+    /// `None` for `off`, which pauses the traffic
+    intensity: Option<TrafficIntensity>,
+}
+
+/// Parse `CRON=INTENSITY`, e.g. `0 8 * * 1-5=high`. CRON is a crontab expression
+/// (minute hour day month weekday) in local time, or six or seven fields with
+/// seconds first; INTENSITY is `low`, `medium`, `high`, `peak` or `off`.
+pub fn parse_schedule_window(value: &str) -> Result<ScheduleWindow, String> {
+    let (expression, intensity) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected CRON=INTENSITY, got '{}'", value))?;
+    let expression = expression.trim();
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    // The cron crate wants seconds first and counts weekdays from 1 = Sunday, so
+    // crontab expressions get a zero seconds field and their weekdays by name
+    let full = match fields.as_slice() {
+        [minute, hour, day, month, weekday] => format!(
+            "0 {} {} {} {} {}",
+            minute,
+            hour,
+            day,
+            month,
+            weekday_names(weekday)?
+        ),
+        _ => expression.to_string(),
+    };
+    let cron = cron::Schedule::from_str(&full)
+        .map_err(|e| format!("invalid cron expression '{}': {}", expression, e))?;
+    let intensity = match intensity.trim() {
+        "off" => None,
+        other => Some(TrafficIntensity::from_str(other, true).map_err(|_| {
+            format!(
+                "unknown intensity '{}' (expected low, medium, high, peak or off)",
+                other
+            )
+        })?),
+    };
+
+    Ok(ScheduleWindow {
+        expression: expression.to_string(),
+        cron,
+        intensity,
+    })
+}
+
+/// Rewrite crontab weekday numbers (`1-5`, `0,6`, `*/2`) as names
+fn weekday_names(field: &str) -> Result<String, String> {
+    let name = |day: &str| match day.parse::<usize>() {
+        Ok(number) => WEEKDAYS
+            .get(number)
+            .map(|name| name.to_string())
+            .ok_or_else(|| format!("weekday {} is out of range 0-7", number)),
+        Err(_) => Ok(day.to_string()),
+    };
+    field
+        .split(',')
+        .map(|part| {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, Some(step)),
+                None => (part, None),
+            };
+            let mut rewritten = match range.split_once('-') {
+                Some((first, last)) => format!("{}-{}", name(first)?, name(last)?),
+                None => name(range)?,
+            };
+            if let Some(step) = step {
+                rewritten = format!("{}/{}", rewritten, step);
+            }
+            Ok(rewritten)
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map(|parts| parts.join(","))
+}
+
+impl ScheduleWindow {
+    fn describe(&self) -> String {
+        match self.intensity {
+            Some(intensity) => format!("{:?} from '{}'", intensity, self.expression),
+            None => format!("Off from '{}'", self.expression),
+        }
+    }
+}
+
+/// Index of the window in force at `now`: the entry that fired most recently.
+/// `None` before any entry has fired.
+fn current_window(windows: &[ScheduleWindow], now: DateTime<Local>) -> Option<usize> {
+    windows
+        .iter()
+        .enumerate()
+        .filter_map(|(index, window)| {
+            window
+                .cron
+                .after(&now)
+                .next_back()
+                .map(|fired| (fired, index))
+        })
+        .max_by_key(|(fired, _)| *fired)
+        .map(|(_, index)| index)
+}
+
+/// Set once SIGINT or SIGTERM arrives, so a --daemon run winds down and reports
+fn shutdown_signal() -> Arc<AtomicBool> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&stop);
+    tokio::spawn(async move {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            let Ok(mut terminate) = signal(SignalKind::terminate()) else {
+                return;
+            };
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = tokio::signal::ctrl_c().await;
+        info!("🛑 Shutdown requested; finishing queries in flight");
+        flag.store(true, Ordering::Relaxed);
+    });
+    stop
+}
+
+/// Generate load at the intensity of the --schedule window in force, switching as
+/// windows fire. Runs for --duration, or with --daemon until SIGINT or SIGTERM.
+pub async fn run_scheduled_simulation(
+    backend: &Backend,
+    args: &Args,
+    ctx: &Arc<QueryContext>,
+    baseline_latency: f64,
+) -> anyhow::Result<SimulationResult> {
+    let start_time = Instant::now();
+    let end_time = (!args.daemon).then(|| start_time + Duration::from_secs(args.duration));
+    let stop = shutdown_signal();
+    let workers = WorkerPool::start(backend, ctx, args.connections, ctx.ramp);
+    let mut rng = StdRng::seed_from_u64(ctx.seed_salt);
+
+    // `None` until the first check
+    let mut window: Option<Option<usize>> = None;
+    let mut next_check = start_time;
+    let mut query_count = 0;
+    while end_time.is_none_or(|end_time| Instant::now() < end_time)
+        && !stop.load(Ordering::Relaxed)
+        && !ctx.aborted()
+    {
+        if Instant::now() >= next_check {
+            let current = current_window(&args.schedule, Local::now());
+            if window != Some(current) {
+                let description = current
+                    .map_or("Off until the first window fires".to_string(), |index| {
+                        args.schedule[index].describe()
+                    });
+                if !args.disable_logging {
+                    info!("🕒 Schedule: {}", description);
+                }
+                ctx.show_phase(format!("Scheduled: {}", description), &[]);
+            }
+            window = Some(current);
+            next_check = Instant::now() + WINDOW_CHECK;
+        }
+
+        let Some(intensity) = window
+            .flatten()
+            .and_then(|index| args.schedule[index].intensity)
+        else {
+            tokio::time::sleep_until(next_check.into()).await;
+            continue;
+        };
+        let target_qps = intensity.base_qps(args.connections);
+        let qps = match ctx.ramp {
+            Some(ramp) => (target_qps * ramp.factor(start_time.elapsed())).max(1.0),
+            None => target_qps,
+        };
+
+        workers.submit(query_count).await;
+        query_count += 1;
+
+        let query_interval = args.arrival.next_interval(qps, &mut rng);
+        if !query_interval.is_zero() {
+            tokio::time::sleep(query_interval).await;
+        }
+    }
+
+    let (recorder, total_duration) = ctx.with_warmup(workers.finish().await, start_time.elapsed());
+    calculate_operational_result(
+        recorder,
+        total_duration,
+        args.connections,
+        baseline_latency,
+        args.robust_stats,
+    )
+}
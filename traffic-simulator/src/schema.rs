@@ -0,0 +1,71 @@
+use crate::export::RunFile;
+use crate::SimulationResult;
+use serde_json::Value;
+
+/// Layout version of exported `SimulationResult` documents. Adding an optional
+/// field does not change it; renaming, removing or re-defining a field does, and
+/// comes with a step in `upgrade_result` that rewrites the previous layout.
+pub const RESULT_SCHEMA_VERSION: u32 = 2;
+
+/// Print the JSON Schema of an export, generated from the Rust types
+#[derive(clap::Args, Debug)]
+pub struct SchemaArgs {
+    /// Document to describe
+    #[arg(value_enum, default_value_t = Document::Result)]
+    document: Document,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Document {
+    /// `SimulationResult`, the `result` object of a run file
+    Result,
+    /// The whole `--output-json` run file
+    RunFile,
+}
+
+pub fn run_schema(args: &SchemaArgs) -> anyhow::Result<()> {
+    let schema = match args.document {
+        Document::Result => schemars::schema_for!(SimulationResult),
+        Document::RunFile => schemars::schema_for!(RunFile),
+    };
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+/// Rewrite a serialized result from any earlier release into the current layout,
+/// so readers only ever deal with one version
+pub fn upgrade_result(result: &mut Value) -> anyhow::Result<()> {
+    let version = result
+        .get("schema_version")
+        .map(|v| {
+            v.as_u64()
+                .ok_or_else(|| anyhow::anyhow!("schema_version is not a number: {}", v))
+        })
+        .transpose()?
+        // Results exported before versioning have no field and the version 1 layout
+        .unwrap_or(1);
+
+    if version > u64::from(RESULT_SCHEMA_VERSION) {
+        return Err(anyhow::anyhow!(
+            "result schema version {} is newer than this release supports ({})",
+            version,
+            RESULT_SCHEMA_VERSION
+        ));
+    }
+
+    // Later layout changes add their upgrade step here, oldest first, guarded by
+    // `if version < N`
+    if version < 2 {
+        // Version 2 renamed a retry class's `exhausted` count to `abandoned`
+        if let Some(retries) = result.get_mut("retries").and_then(Value::as_object_mut) {
+            for stats in retries.values_mut().filter_map(Value::as_object_mut) {
+                if let Some(exhausted) = stats.remove("exhausted") {
+                    stats.insert("abandoned".to_string(), exhausted);
+                }
+            }
+        }
+    }
+
+    result["schema_version"] = RESULT_SCHEMA_VERSION.into();
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use crate::plugin::PluginStatement;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rhai::{Engine, Scope, AST};
+use std::cell::RefCell;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Operation budget for a single `generate` call, so a runaway loop fails the query
+/// instead of hanging the generator
+const OPERATIONS_PER_CALL: u64 = 1_000_000;
+
+/// Upper bound on any string a script builds, generated SQL included
+const MAX_STRING_BYTES: usize = 1024 * 1024;
+
+thread_local! {
+    /// Random source of the `generate` call running on this thread, seeded from the
+    /// request seed so a script's choices are as reproducible as the built-in workloads
+    static CALL_RNG: RefCell<StdRng> = RefCell::new(StdRng::seed_from_u64(0));
+}
+
+/// Query generator written in Rhai, a small embedded scripting language, so new
+/// schemas and access patterns need neither a rebuild nor a WebAssembly toolchain.
+///
+/// The script defines `fn generate(seed)` returning a map
+/// `#{ sql: "...", params: [...], think_ms: 0 }`, the same statement a `--plugin`
+/// produces. `rand_int(low, high)` (inclusive) and `rand_float()` draw from a random
+/// source seeded with the request seed. Scripts have no file, network or clock access.
+pub struct ScriptWorkload {
+    path: String,
+    engine: Engine,
+    ast: AST,
+}
+
+impl ScriptWorkload {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(OPERATIONS_PER_CALL)
+            .set_max_string_size(MAX_STRING_BYTES)
+            .on_print(|text| debug!("📜 {}", text))
+            .register_fn("rand_int", |low: i64, high: i64| {
+                CALL_RNG.with(|rng| rng.borrow_mut().gen_range(low..=high.max(low)))
+            })
+            .register_fn("rand_float", || {
+                CALL_RNG.with(|rng| rng.borrow_mut().gen::<f64>())
+            });
+
+        let ast = engine
+            .compile(&source)
+            .map_err(|e| anyhow::anyhow!("Invalid script '{}': {}", path, e))?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == "generate" && f.params.len() == 1)
+        {
+            return Err(anyhow::anyhow!(
+                "Script '{}' does not define `fn generate(seed)`",
+                path
+            ));
+        }
+
+        Ok(Self {
+            path: path.to_string(),
+            engine,
+            ast,
+        })
+    }
+
+    /// Ask the script for the statement to run for `seed` on the blocking pool, so a
+    /// slow script cannot stall the async workers driving the load
+    pub async fn generate_async(self: Arc<Self>, seed: u64) -> anyhow::Result<PluginStatement> {
+        tokio::task::spawn_blocking(move || self.generate(seed)).await?
+    }
+
+    fn generate(&self, seed: u64) -> anyhow::Result<PluginStatement> {
+        CALL_RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+        // Rhai integers are signed; keep the seed non-negative so `seed % n` is too
+        let seed = (seed & i64::MAX as u64) as i64;
+        let output = self
+            .engine
+            .call_fn::<rhai::Dynamic>(&mut Scope::new(), &self.ast, "generate", (seed,))
+            .map_err(|e| anyhow::anyhow!("Script failed: {}", e))?;
+
+        rhai::serde::from_dynamic(&output)
+            .map_err(|e| anyhow::anyhow!("Invalid script output: {}", e))
+    }
+}
+
+impl std::fmt::Debug for ScriptWorkload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptWorkload")
+            .field("path", &self.path)
+            .finish()
+    }
+}
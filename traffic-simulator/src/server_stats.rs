@@ -0,0 +1,175 @@
+use crate::create_connection_pool;
+use crate::credentials::CredentialProvider;
+use crate::net::AddressPreference;
+use crate::pool_options::PoolOptions;
+use crate::timeline::TimelinePoint;
+use crate::tls::TlsOptions;
+use deadpool_postgres::Pool;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Cumulative database counters and current backend and lock counts, in one round trip
+const SNAPSHOT_SQL: &str = "SELECT d.xact_commit, d.xact_rollback, d.blks_read, d.blks_hit, \
+            d.temp_files, d.temp_bytes, d.deadlocks, \
+            (SELECT count(*) FROM pg_stat_activity \
+              WHERE datname = current_database() AND state = 'active'), \
+            (SELECT count(*) FROM pg_stat_activity \
+              WHERE datname = current_database() AND state LIKE 'idle in transaction%'), \
+            (SELECT count(*) FROM pg_locks WHERE NOT granted) \
+     FROM pg_stat_database d WHERE d.datname = current_database()";
+
+/// Server-side statistics (--server-stats) sampled on a side connection every
+/// --server-stats-interval of the measured run, for the timeline export
+#[derive(Debug)]
+pub struct ServerMonitor {
+    start: Instant,
+    pool: Pool,
+    interval: Duration,
+    samples: Mutex<BTreeMap<u64, ServerSample>>,
+}
+
+/// What the server was doing at one second of the run. Counts of backends and
+/// lock waits are taken at the sample; rates and totals cover the time since the
+/// previous sample.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerSample {
+    /// Backends of the database running a statement
+    pub server_active: i64,
+    pub server_idle_in_transaction: i64,
+    /// Lock requests waiting to be granted, across all databases
+    pub server_lock_waits: i64,
+    pub server_commits_per_second: f64,
+    pub server_rollbacks_per_second: f64,
+    /// Share of block reads served from shared buffers; `None` without reads
+    pub server_cache_hit_ratio: Option<f64>,
+    pub server_temp_files: i64,
+    pub server_temp_bytes: i64,
+    pub server_deadlocks: i64,
+}
+
+/// One reading of `SNAPSHOT_SQL`
+struct Snapshot {
+    taken: Instant,
+    commits: i64,
+    rollbacks: i64,
+    blocks_read: i64,
+    blocks_hit: i64,
+    temp_files: i64,
+    temp_bytes: i64,
+    deadlocks: i64,
+    active: i64,
+    idle_in_transaction: i64,
+    lock_waits: i64,
+}
+
+impl ServerMonitor {
+    /// Open the side connection, so a target without statistics access fails the
+    /// run before it starts
+    pub async fn connect(
+        database_url: &str,
+        preference: Option<AddressPreference>,
+        tls: &TlsOptions,
+        credentials: Option<&Arc<CredentialProvider>>,
+        interval: Duration,
+    ) -> anyhow::Result<Self> {
+        let monitor = Self {
+            start: Instant::now(),
+            pool: create_connection_pool(
+                database_url,
+                1,
+                preference,
+                tls,
+                credentials,
+                None,
+                &PoolOptions::default(),
+            )
+            .await?,
+            interval,
+            samples: Mutex::new(BTreeMap::new()),
+        };
+        monitor
+            .snapshot()
+            .await
+            .map_err(|e| anyhow::anyhow!("Cannot read server statistics: {}", e))?;
+        Ok(monitor)
+    }
+
+    async fn snapshot(&self) -> anyhow::Result<Snapshot> {
+        let client = self.pool.get().await?;
+        let row = client.query_one(SNAPSHOT_SQL, &[]).await?;
+        Ok(Snapshot {
+            taken: Instant::now(),
+            commits: row.get(0),
+            rollbacks: row.get(1),
+            blocks_read: row.get(2),
+            blocks_hit: row.get(3),
+            temp_files: row.get(4),
+            temp_bytes: row.get(5),
+            deadlocks: row.get(6),
+            active: row.get(7),
+            idle_in_transaction: row.get(8),
+            lock_waits: row.get(9),
+        })
+    }
+
+    fn record(&self, previous: &Snapshot, current: &Snapshot) {
+        let elapsed = current
+            .taken
+            .duration_since(previous.taken)
+            .as_secs_f64()
+            .max(f64::EPSILON);
+        let blocks_read = current.blocks_read - previous.blocks_read;
+        let blocks_hit = current.blocks_hit - previous.blocks_hit;
+        let sample = ServerSample {
+            server_active: current.active,
+            server_idle_in_transaction: current.idle_in_transaction,
+            server_lock_waits: current.lock_waits,
+            server_commits_per_second: (current.commits - previous.commits) as f64 / elapsed,
+            server_rollbacks_per_second: (current.rollbacks - previous.rollbacks) as f64 / elapsed,
+            server_cache_hit_ratio: (blocks_read + blocks_hit > 0)
+                .then(|| blocks_hit as f64 / (blocks_read + blocks_hit) as f64),
+            server_temp_files: current.temp_files - previous.temp_files,
+            server_temp_bytes: current.temp_bytes - previous.temp_bytes,
+            server_deadlocks: current.deadlocks - previous.deadlocks,
+        };
+        let second = self.start.elapsed().as_secs();
+        self.samples.lock().unwrap().insert(second, sample);
+    }
+
+    /// Attach each sample to the timeline second it was taken in
+    pub fn annotate(&self, points: &mut [TimelinePoint]) {
+        let samples = self.samples.lock().unwrap();
+        for point in points {
+            if let Some(sample) = samples.get(&point.second) {
+                point.server = Some(sample.clone());
+            }
+        }
+    }
+}
+
+/// Sample every --server-stats-interval until aborted. A failed sample is logged
+/// and leaves a gap in the timeline.
+pub async fn run_server_sampler(monitor: Arc<ServerMonitor>) {
+    let mut previous = None;
+    let mut interval = tokio::time::interval(monitor.interval);
+    let mut warned = false;
+    loop {
+        interval.tick().await;
+        match monitor.snapshot().await {
+            Ok(current) => {
+                if let Some(previous) = &previous {
+                    monitor.record(previous, &current);
+                }
+                previous = Some(current);
+            }
+            Err(e) if !warned => {
+                warn!("Failed to sample server statistics: {}", e);
+                warned = true;
+            }
+            Err(_) => {}
+        }
+    }
+}
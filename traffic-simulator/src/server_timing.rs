@@ -0,0 +1,192 @@
+use crate::recorder::LatencyHistogram;
+use crate::sampling::Sampler;
+use crate::LatencySummary;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio_postgres::Error;
+
+/// "svrtm", see [`crate::sampling`]
+const SERVER_TIMING_SEED_SALT: u64 = 0x7376_7274_6d00_0000;
+
+/// The server's wall clock, in seconds since the epoch
+const CLOCK_SQL: &str = "SELECT extract(epoch FROM clock_timestamp())::float8";
+
+tokio::task_local! {
+    /// Set while a sampled request runs, so its workload statements get timed
+    static SAMPLED: Arc<ServerTiming>;
+}
+
+/// `--server-timing-sample-rate`: splits the round trip of a share of workload
+/// statements into the time the server spent on them and the rest, network transit
+/// and protocol handling, rather than estimating the latter once from a baseline ping
+#[derive(Debug)]
+pub struct ServerTiming {
+    sampler: Sampler,
+    stats: Mutex<TimingStats>,
+}
+
+#[derive(Debug, Default)]
+struct TimingStats {
+    samples: u64,
+    failures: u64,
+    /// Milliseconds
+    round_trip: LatencyHistogram,
+    server: LatencyHistogram,
+    network: LatencyHistogram,
+    round_trip_total_ms: f64,
+    network_total_ms: f64,
+}
+
+/// Where the time of the sampled statements went
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ServerTimingReport {
+    /// Share of requests whose statements were timed
+    pub sample_rate: f64,
+    /// Statements timed
+    pub samples: u64,
+    /// Statements left out because a clock read failed
+    pub failures: u64,
+    /// Each statement's round trip as the client saw it
+    pub round_trip: LatencySummary,
+    /// Between the server's clock reads around each statement: bind, execution and
+    /// sending the rows
+    pub server: LatencySummary,
+    /// Round trip minus server time: network transit, protocol overhead and
+    /// decoding on the client
+    pub network: LatencySummary,
+    /// Share of the summed round trips spent outside the server
+    pub network_share_percent: f64,
+}
+
+impl ServerTiming {
+    pub fn new(rate: f64) -> Self {
+        Self {
+            sampler: Sampler::new(rate, SERVER_TIMING_SEED_SALT),
+            stats: Mutex::new(TimingStats::default()),
+        }
+    }
+
+    /// Whether attempt `attempt` of the request with `seed` is timed
+    pub fn samples(&self, seed: u64, attempt: u32) -> bool {
+        self.sampler.samples(seed, attempt)
+    }
+
+    fn record(&self, round_trip_ms: f64, server_ms: f64) {
+        // Both clock reads are the server's; the clamp only guards against a
+        // clock step during the statement
+        let server_ms = server_ms.clamp(0.0, round_trip_ms);
+        let network_ms = round_trip_ms - server_ms;
+        let mut stats = self.stats.lock().unwrap();
+        stats.samples += 1;
+        stats.round_trip.record_ms(round_trip_ms);
+        stats.server.record_ms(server_ms);
+        stats.network.record_ms(network_ms);
+        stats.round_trip_total_ms += round_trip_ms;
+        stats.network_total_ms += network_ms;
+    }
+
+    pub fn report(&self) -> ServerTimingReport {
+        let stats = self.stats.lock().unwrap();
+        ServerTimingReport {
+            sample_rate: self.sampler.rate(),
+            samples: stats.samples,
+            failures: stats.failures,
+            round_trip: stats.round_trip.summary(),
+            server: stats.server.summary(),
+            network: stats.network.summary(),
+            network_share_percent: if stats.round_trip_total_ms > 0.0 {
+                stats.network_total_ms / stats.round_trip_total_ms * 100.0
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// Run `future`, one attempt of a sampled request, with its statements timed
+pub async fn scope<F: Future>(timing: Arc<ServerTiming>, future: F) -> F::Output {
+    SAMPLED.scope(timing, future).await
+}
+
+/// Run `run`, a query or execute of a prepared statement not yet polled, and time it
+/// on the server when the current request is sampled. The server's clock is read
+/// by statements pipelined right before and after it, so the statement itself runs
+/// unchanged and returns its rows, all in a single round trip.
+pub async fn time_if_sampled<T>(
+    client: &deadpool_postgres::Client,
+    run: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    let Ok(timing) = SAMPLED.try_with(Arc::clone) else {
+        return run.await;
+    };
+    // Prepared ahead, so the clock reads are sent without a parse round trip of
+    // their own and reach the server in order around the statement
+    let Ok(clock) = client.prepare_cached(CLOCK_SQL).await else {
+        timing.stats.lock().unwrap().failures += 1;
+        return run.await;
+    };
+
+    let start = Instant::now();
+    // futures::join! polls in order, so the three are sent in order
+    let (before, output, after) = futures::join!(
+        client.query_one(&clock, &[]),
+        run,
+        client.query_one(&clock, &[])
+    );
+    let round_trip = start.elapsed();
+    if output.is_ok() {
+        match (before, after) {
+            (Ok(before), Ok(after)) => {
+                let server_ms = (after.get::<_, f64>(0) - before.get::<_, f64>(0)) * 1000.0;
+                timing.record(round_trip.as_secs_f64() * 1000.0, server_ms);
+            }
+            // e.g. the clock read after a statement that aborted the transaction
+            _ => timing.stats.lock().unwrap().failures += 1,
+        }
+    }
+    output
+}
+
+pub fn display_server_timing(report: &ServerTimingReport, baseline_network_latency_ms: f64) {
+    println!(
+        "\n⏱️  Server vs Network Time ({:.2}% of requests, {} statements{}):",
+        report.sample_rate * 100.0,
+        report.samples,
+        if report.failures > 0 {
+            format!(", {} not timed", report.failures)
+        } else {
+            String::new()
+        }
+    );
+    if report.samples == 0 {
+        return;
+    }
+    for (label, summary) in [
+        ("Round Trip (ms):", &report.round_trip),
+        ("Server (ms):", &report.server),
+        ("Network (ms):", &report.network),
+    ] {
+        println!(
+            "   {:<24}avg {:.3}  p50 {:.3}  p95 {:.3}  p99 {:.3}  max {:.3}",
+            label,
+            summary.average_ms,
+            summary.p50_ms,
+            summary.p95_ms,
+            summary.p99_ms,
+            summary.max_ms
+        );
+    }
+    println!(
+        "   Network Share:          {:>7.1}% of statement time",
+        report.network_share_percent
+    );
+    if baseline_network_latency_ms > 0.0 {
+        println!(
+            "   Baseline Ping:          {:>7.3}ms (measured network p50 {:.3}ms)",
+            baseline_network_latency_ms, report.network.p50_ms
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use crate::worker::WorkerPool;
+use crate::{calculate_operational_result, Args, Backend, QueryContext, SimulationResult};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Deserialize;
+use std::f64::consts::TAU;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Waveform of a --shape run's target QPS
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShapeKind {
+    /// Rise smoothly from --min-qps to --max-qps and back once per period
+    Sine,
+    /// Climb linearly from --min-qps to --max-qps, then drop straight back, every period
+    Sawtooth,
+}
+
+/// Parametric load shape: the target QPS varies continuously between `min_qps` and
+/// `max_qps` instead of stepping between discrete phases
+#[derive(Debug, Clone, Copy)]
+pub struct LoadShape {
+    kind: ShapeKind,
+    period: Duration,
+    min_qps: f64,
+    max_qps: f64,
+}
+
+impl LoadShape {
+    pub fn new(
+        kind: ShapeKind,
+        period: Duration,
+        min_qps: f64,
+        max_qps: f64,
+    ) -> anyhow::Result<Self> {
+        if period.is_zero() {
+            return Err(anyhow::anyhow!("--period must be longer than zero"));
+        }
+        if min_qps > max_qps {
+            return Err(anyhow::anyhow!(
+                "--min-qps ({}) exceeds --max-qps ({})",
+                min_qps,
+                max_qps
+            ));
+        }
+        Ok(Self {
+            kind,
+            period,
+            min_qps,
+            max_qps,
+        })
+    }
+
+    /// Target QPS `elapsed` into the run; every period starts at `min_qps`
+    pub fn qps_at(&self, elapsed: Duration) -> f64 {
+        let cycle = (elapsed.as_secs_f64() / self.period.as_secs_f64()).fract();
+        let level = match self.kind {
+            ShapeKind::Sine => (1.0 - (TAU * cycle).cos()) / 2.0,
+            ShapeKind::Sawtooth => cycle,
+        };
+        self.min_qps + (self.max_qps - self.min_qps) * level
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{:?} between {} and {} queries/second every {:?}",
+            self.kind, self.min_qps, self.max_qps, self.period
+        )
+    }
+}
+
+/// Feed the workers at the shape's target QPS, recomputed for every query so the
+/// rate follows the curve smoothly. Queries are spaced by --arrival and scaled by
+/// any --ramp-up/--ramp-down like the real-world simulation's phases.
+pub async fn run_shaped_simulation(
+    backend: &Backend,
+    args: &Args,
+    ctx: &Arc<QueryContext>,
+    shape: LoadShape,
+    baseline_latency: f64,
+) -> anyhow::Result<SimulationResult> {
+    let start_time = Instant::now();
+    let end_time = start_time + Duration::from_secs(args.duration);
+    let workers = WorkerPool::start(backend, ctx, args.connections, ctx.ramp);
+    let mut rng = StdRng::seed_from_u64(ctx.seed_salt);
+    let mut next_report = start_time;
+
+    let mut query_count = 0;
+    while Instant::now() < end_time
+        && !ctx.aborted()
+        && (args.duration_only || query_count < args.total_queries.unwrap_or(usize::MAX))
+    {
+        let elapsed = start_time.elapsed();
+        let target_qps = shape.qps_at(elapsed);
+        if !args.disable_logging && Instant::now() >= next_report {
+            info!("📈 Target: {:.1} QPS", target_qps);
+            next_report += Duration::from_secs(5);
+        }
+        let qps = match ctx.ramp {
+            Some(ramp) => (target_qps * ramp.factor(elapsed)).max(1.0),
+            None => target_qps,
+        };
+
+        workers.submit(query_count as u64).await;
+        query_count += 1;
+
+        let query_interval = args.arrival.next_interval(qps, &mut rng);
+        if !query_interval.is_zero() {
+            tokio::time::sleep(query_interval).await;
+        }
+    }
+
+    let (recorder, total_duration) = ctx.with_warmup(workers.finish().await, start_time.elapsed());
+    calculate_operational_result(
+        recorder,
+        total_duration,
+        args.connections,
+        baseline_latency,
+        args.robust_stats,
+    )
+}
@@ -0,0 +1,398 @@
+use crate::abort::{self, AbortReport};
+use crate::baseline::Baseline;
+use crate::churn::ChurnStats;
+use crate::databricks::DatabricksPublisher;
+use crate::export::RunSpan;
+use crate::pool_stats::PoolStats;
+use crate::prepared::PrepareStats;
+use crate::recorder::MetricsRecorder;
+use crate::replica::ReplicaStats;
+use crate::transaction::TransactionStats;
+use crate::workflow::WorkflowStats;
+use crate::writes::{AffectedRowStats, WriteKind};
+use crate::{
+    display_operational_results, Args, ErrorClass, QueueDepthStats, RetryClassStats,
+    SimulationResult,
+};
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use tokio::process::Command;
+use tracing::info;
+
+/// Time given to every shard to connect and warm up before the common start line
+pub const SHARD_STARTUP_GRACE: chrono::Duration = chrono::Duration::seconds(10);
+
+/// Which slice of a `--processes` run this process generates
+#[derive(Debug, Clone, Copy)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+/// Parse `INDEX/COUNT`, e.g. `0/4`
+pub fn parse_shard(value: &str) -> Result<Shard, String> {
+    let (index, count) = value
+        .split_once('/')
+        .ok_or_else(|| format!("invalid shard '{}', expected INDEX/COUNT", value))?;
+    let index: usize = index
+        .parse()
+        .map_err(|_| format!("invalid shard index in '{}'", value))?;
+    let count: usize = count
+        .parse()
+        .map_err(|_| format!("invalid shard count in '{}'", value))?;
+    if index >= count {
+        return Err(format!(
+            "shard index must be below the count in '{}'",
+            value
+        ));
+    }
+    Ok(Shard { index, count })
+}
+
+impl Shard {
+    /// This shard's part of `total`; shares differ by at most one and sum to `total`
+    pub fn share(&self, total: usize) -> usize {
+        total / self.count + usize::from(self.index < total % self.count)
+    }
+
+    /// Mixed into every query seed so shards don't generate identical rows
+    pub fn seed_salt(&self) -> u64 {
+        (self.index as u64) << 48
+    }
+}
+
+/// What a shard hands back to the parent process
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ShardReport {
+    total_queries: usize,
+    successful_queries: usize,
+    failed_queries: usize,
+    duration_seconds: f64,
+    target_queries_per_second: Option<f64>,
+    connection_recycles: usize,
+    baseline_network_latency_ms: f64,
+    queue_peak: usize,
+    queue_average: f64,
+    retries: BTreeMap<ErrorClass, RetryClassStats>,
+    affected_rows: BTreeMap<WriteKind, AffectedRowStats>,
+    transactions: TransactionStats,
+    workflow: WorkflowStats,
+    statements: PrepareStats,
+    churn: ChurnStats,
+    replica: ReplicaStats,
+    pool: PoolStats,
+    aborted: Option<AbortReport>,
+    /// Set by the shard after the report is built from its result
+    pub server_version: Option<String>,
+    recorder: MetricsRecorder,
+}
+
+impl ShardReport {
+    pub fn from_result(
+        result: &SimulationResult,
+        transactions: TransactionStats,
+        workflow: WorkflowStats,
+        statements: PrepareStats,
+        churn: ChurnStats,
+        replica: ReplicaStats,
+        pool: PoolStats,
+    ) -> Self {
+        Self {
+            total_queries: result.total_queries,
+            successful_queries: result.successful_queries,
+            failed_queries: result.failed_queries,
+            duration_seconds: result.duration_seconds,
+            target_queries_per_second: result.target_queries_per_second,
+            connection_recycles: result.connection_recycles,
+            baseline_network_latency_ms: result.baseline_network_latency_ms,
+            queue_peak: result.queue_depth.peak,
+            queue_average: result.queue_depth.average,
+            retries: result.retries.clone().unwrap_or_default(),
+            affected_rows: result.affected_rows.clone().unwrap_or_default(),
+            transactions,
+            workflow,
+            statements,
+            churn,
+            replica,
+            pool,
+            aborted: result.aborted.clone(),
+            server_version: None,
+            recorder: result.recorder.clone(),
+        }
+    }
+
+    fn merge(&mut self, other: &ShardReport) {
+        self.total_queries += other.total_queries;
+        self.successful_queries += other.successful_queries;
+        self.failed_queries += other.failed_queries;
+        if self.server_version.is_none() {
+            self.server_version.clone_from(&other.server_version);
+        }
+        // Shards share a start line, so the slowest one bounds the run
+        self.duration_seconds = self.duration_seconds.max(other.duration_seconds);
+        self.target_queries_per_second = match (
+            self.target_queries_per_second,
+            other.target_queries_per_second,
+        ) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+        self.connection_recycles += other.connection_recycles;
+        self.baseline_network_latency_ms = self
+            .baseline_network_latency_ms
+            .max(other.baseline_network_latency_ms);
+        self.queue_peak += other.queue_peak;
+        self.queue_average += other.queue_average;
+        for (class, stats) in &other.retries {
+            let merged = self.retries.entry(*class).or_default();
+            merged.retries += stats.retries;
+            merged.recovered += stats.recovered;
+            merged.abandoned += stats.abandoned;
+        }
+        for (kind, stats) in &other.affected_rows {
+            self.affected_rows.entry(*kind).or_default().merge(stats);
+        }
+        self.transactions.merge(&other.transactions);
+        self.workflow.merge(&other.workflow);
+        self.statements.merge(&other.statements);
+        self.churn.merge(&other.churn);
+        self.replica.merge(&other.replica);
+        self.pool.merge(&other.pool);
+        abort::merge(&mut self.aborted, &other.aborted);
+        self.recorder.merge(&other.recorder);
+    }
+
+    fn into_result(self, concurrent_connections: usize) -> anyhow::Result<SimulationResult> {
+        if self.successful_queries == 0 {
+            return Err(anyhow::anyhow!("No successful queries executed"));
+        }
+
+        let latency = &self.recorder.latency;
+        let average_latency_ms = latency.mean_ms();
+        let avg_connection_time = self.recorder.connection_time.mean_ms();
+        let avg_query_time = self.recorder.query_execution_time.mean_ms();
+
+        Ok(SimulationResult {
+            schema_version: crate::schema::RESULT_SCHEMA_VERSION,
+            total_queries: self.total_queries,
+            successful_queries: self.successful_queries,
+            failed_queries: self.failed_queries,
+            duration_seconds: self.duration_seconds,
+            queries_per_second: self.successful_queries as f64 / self.duration_seconds,
+            target_queries_per_second: self.target_queries_per_second,
+            average_latency_ms,
+            min_latency_ms: latency.min_ms(),
+            max_latency_ms: latency.max_ms(),
+            p50_latency_ms: latency.quantile_ms(0.5),
+            p95_latency_ms: latency.quantile_ms(0.95),
+            p99_latency_ms: latency.quantile_ms(0.99),
+            p999_latency_ms: latency.quantile_ms(0.999),
+            p9999_latency_ms: latency.quantile_ms(0.9999),
+            latency_std_dev_ms: latency.std_dev_ms(),
+            latency_coefficient_of_variation: crate::coefficient_of_variation(
+                latency.std_dev_ms(),
+                average_latency_ms,
+            ),
+            concurrent_connections,
+            baseline_network_latency_ms: self.baseline_network_latency_ms,
+            database_processing_time_ms: average_latency_ms - self.baseline_network_latency_ms,
+            injected_latency: None,
+            connection_efficiency: avg_query_time / (avg_connection_time + avg_query_time) * 100.0,
+            connection_recycles: self.connection_recycles,
+            average_recycle_acquire_ms: self.recorder.recycled_acquire.mean_ms(),
+            average_pooled_acquire_ms: self.recorder.pooled_acquire.mean_ms(),
+            slo: None,
+            failure_latency: None,
+            failures: (!self.recorder.failures.is_empty()).then(|| self.recorder.failures.clone()),
+            queue_depth: QueueDepthStats {
+                peak: self.queue_peak,
+                average: self.queue_average,
+                samples: Vec::new(),
+            },
+            robust_latency: None,
+            clock_skew: None,
+            retries: (!self.retries.is_empty()).then_some(self.retries),
+            // Set by the caller, which knows the --isolation level
+            isolation: None,
+            verification: None,
+            user_journeys: None,
+            affected_rows: (!self.affected_rows.is_empty()).then_some(self.affected_rows),
+            transactions: self.transactions.report(),
+            workflow: self.workflow.report(),
+            query_types: crate::query_types::report(&self.recorder.query_types),
+            tenants: crate::tenants::report(&self.recorder.tenants, self.duration_seconds),
+            workloads: None,
+            prepared_statements: self.statements.report(self.total_queries),
+            connection_churn: self.churn.report(),
+            chaos: None,
+            explain: None,
+            server_timing: None,
+            connection_pool: self.pool.report(),
+            read_replica: self.replica.report(),
+            read_your_writes: None,
+            aborted: self.aborted,
+            preconnect: None,
+            storm: None,
+            warmup: None,
+            // Phases are not tracked across --processes shards
+            phases: None,
+            recorder: self.recorder,
+        })
+    }
+}
+
+/// Re-run this command as `processes` shard processes, each with its own runtime
+/// and slice of the connection pool, then merge their reports into one
+pub async fn run_sharded(
+    args: &Args,
+    baseline: Option<&Baseline>,
+    publisher: Option<&DatabricksPublisher>,
+) -> anyhow::Result<()> {
+    let count = usize::from(args.processes);
+    if args.connections < count {
+        return Err(anyhow::anyhow!(
+            "--processes {} needs at least as many --connections",
+            count
+        ));
+    }
+
+    let exe = std::env::current_exe()?;
+    let forwarded = strip_flags(std::env::args().skip(1), &["--processes", "--run-id"], &[]);
+    let start_at = args
+        .start_at
+        .unwrap_or_else(|| Utc::now() + SHARD_STARTUP_GRACE);
+    info!(
+        "🧩 Launching {} shard processes ({} connections total), starting at {}",
+        count,
+        args.connections,
+        start_at.to_rfc3339()
+    );
+
+    let report_dir = std::env::temp_dir();
+    let report_paths: Vec<_> = (0..count)
+        .map(|index| {
+            report_dir.join(format!(
+                "traffic-simulator-{}-shard-{}.json",
+                std::process::id(),
+                index
+            ))
+        })
+        .collect();
+
+    let mut children = Vec::with_capacity(count);
+    for (index, path) in report_paths.iter().enumerate() {
+        let mut command = Command::new(&exe);
+        command
+            .args(&forwarded)
+            .arg("--shard")
+            .arg(format!("{}/{}", index, count))
+            .arg("--shard-report")
+            .arg(path)
+            .arg("--run-id")
+            .arg(args.run_id());
+        if args.start_at.is_none() {
+            command.arg("--start-at").arg(start_at.to_rfc3339());
+        }
+        // Only the first shard narrates; the others would repeat the same lines
+        if index > 0 {
+            if !args.disable_logging {
+                command.arg("--disable-logging");
+            }
+            command.stdout(Stdio::null());
+        }
+        children.push(command.kill_on_drop(true).spawn()?);
+    }
+
+    let statuses = join_all(children.iter_mut().map(|child| child.wait())).await;
+    let mut reports = Vec::with_capacity(count);
+    for (index, (status, path)) in statuses.into_iter().zip(&report_paths).enumerate() {
+        let status = status?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("Shard {} exited with {}", index, status));
+        }
+        reports.push(read_report(path)?);
+    }
+
+    report_merged(&reports, args, start_at, baseline, publisher).await
+}
+
+/// Read and remove the report a shard process wrote
+pub fn read_report(path: &std::path::Path) -> anyhow::Result<ShardReport> {
+    let report = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let _ = std::fs::remove_file(path);
+    Ok(report)
+}
+
+/// Merge shard reports into one result, then display and export it like a
+/// single-process run
+pub async fn report_merged(
+    reports: &[ShardReport],
+    args: &Args,
+    start_at: DateTime<Utc>,
+    baseline: Option<&Baseline>,
+    publisher: Option<&DatabricksPublisher>,
+) -> anyhow::Result<()> {
+    let mut merged = ShardReport::default();
+    for report in reports {
+        merged.merge(report);
+    }
+
+    let server_version = merged.server_version.take();
+    let mut result = merged.into_result(args.connections)?;
+    crate::latency_injection::annotate(&mut result, args.inject_latency);
+    result.isolation = args.isolation.map(|level| {
+        crate::isolation::report(
+            level,
+            result.total_queries,
+            result.failures.as_ref(),
+            result.retries.as_ref(),
+        )
+    });
+    display_operational_results(&result, &args.label_map());
+    let span = RunSpan {
+        started_at: start_at,
+        finished_at: Utc::now(),
+        server_version,
+    };
+
+    if let Some(path) = &args.output_json {
+        crate::export::write_run_file(path, args, &span, &result)?;
+        info!("📝 Results written to {}", path);
+    }
+
+    // Shards keep no timeline, so only the run itself is published
+    if let Some(publisher) = publisher {
+        let run = crate::export::run_file(args, &span, &result);
+        let run_id = publisher.publish(&run, &[]).await?;
+        info!("🧱 Run {} published to {}", run_id, publisher.table());
+    }
+
+    if let Some(baseline) = baseline {
+        baseline.compare(&result, args)?;
+    }
+
+    abort::fail_if_aborted(result.aborted.as_ref())
+}
+
+/// `args` without the `valued` flags (and their values) and `switches`, which
+/// the processes they are forwarded to must not inherit
+pub fn strip_flags(
+    args: impl IntoIterator<Item = String>,
+    valued: &[&str],
+    switches: &[&str],
+) -> Vec<String> {
+    let mut forwarded = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let flag = arg.split_once('=').map_or(arg.as_str(), |(flag, _)| flag);
+        if valued.contains(&arg.as_str()) {
+            args.next();
+        } else if !valued.contains(&flag) && !switches.contains(&arg.as_str()) {
+            forwarded.push(arg);
+        }
+    }
+    forwarded
+}
@@ -0,0 +1,97 @@
+use crate::stores::STORE_COUNT;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand_distr::{Distribution, Zipf};
+
+/// Order ids the select and update workloads look up
+pub const ORDER_KEY_COUNT: i32 = 100_000;
+
+/// Products an insert orders
+pub const PRODUCT_COUNT: i32 = 50;
+
+/// Exponent of `--skew zipfian` without one, YCSB's zipfian constant
+const DEFAULT_ZIPFIAN_EXPONENT: f64 = 0.99;
+
+/// `--skew`: how the orders workload picks the stores, products and orders it
+/// touches. Under zipfian, key 1 is the hottest, key 2 the next, and so on, so a
+/// few hot rows and partitions take most of the traffic.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum KeySkew {
+    #[default]
+    Uniform,
+    /// Key `k` is picked with probability proportional to `1 / k^exponent`
+    Zipfian(f64),
+}
+
+/// Parse `uniform`, `zipfian` or `zipfian:EXPONENT`, e.g. `zipfian:1.2`
+pub fn parse_skew(value: &str) -> Result<KeySkew, String> {
+    match value.split_once(':') {
+        None if value == "uniform" => Ok(KeySkew::Uniform),
+        None if value == "zipfian" => Ok(KeySkew::Zipfian(DEFAULT_ZIPFIAN_EXPONENT)),
+        Some(("zipfian", exponent)) => {
+            let exponent: f64 = exponent
+                .parse()
+                .map_err(|_| format!("invalid zipfian exponent '{}'", exponent))?;
+            if !exponent.is_finite() || exponent <= 0.0 {
+                return Err(format!(
+                    "zipfian exponent must be positive, got {}",
+                    exponent
+                ));
+            }
+            Ok(KeySkew::Zipfian(exponent))
+        }
+        _ => Err(format!(
+            "unknown skew '{}' (expected uniform, zipfian or zipfian:EXPONENT)",
+            value
+        )),
+    }
+}
+
+impl KeySkew {
+    /// A key in `1..=count`
+    pub fn pick_i32(self, count: i32, rng: &mut StdRng) -> i32 {
+        match self {
+            KeySkew::Uniform => rng.gen_range(1..=count),
+            KeySkew::Zipfian(exponent) => zipf_key(count as u64, exponent, rng) as i32,
+        }
+    }
+
+    /// A key in `1..=count`, for backends whose keys are 64-bit
+    pub fn pick_i64(self, count: i64, rng: &mut StdRng) -> i64 {
+        match self {
+            KeySkew::Uniform => rng.gen_range(1..=count),
+            KeySkew::Zipfian(exponent) => zipf_key(count as u64, exponent, rng) as i64,
+        }
+    }
+
+    /// The distribution with how much of the traffic its hottest keys take, e.g.
+    /// `zipfian:1.2 (hottest store 40.5%, hottest product 30.2%, top 1% of orders 85.2%)`
+    pub fn describe(self) -> String {
+        match self {
+            KeySkew::Uniform => "uniform".to_string(),
+            KeySkew::Zipfian(exponent) => format!(
+                "zipfian:{} (hottest store {:.1}%, hottest product {:.1}%, top 1% of orders {:.1}%)",
+                exponent,
+                top_share(STORE_COUNT as u64, 1, exponent) * 100.0,
+                top_share(PRODUCT_COUNT as u64, 1, exponent) * 100.0,
+                top_share(ORDER_KEY_COUNT as u64, ORDER_KEY_COUNT as u64 / 100, exponent) * 100.0
+            ),
+        }
+    }
+}
+
+/// 1-based zipfian key in `1..=count`, key 1 being the most popular
+fn zipf_key(count: u64, exponent: f64, rng: &mut StdRng) -> u64 {
+    Zipf::new(count, exponent)
+        .map(|zipf| zipf.sample(rng) as u64)
+        .unwrap_or(1)
+        .clamp(1, count)
+}
+
+/// Share of picks that land on the `top` hottest of `count` keys
+fn top_share(count: u64, top: u64, exponent: f64) -> f64 {
+    let weight = |key: u64| (key as f64).powf(-exponent);
+    let hot: f64 = (1..=top).map(weight).sum();
+    let all: f64 = hot + (top + 1..=count).map(weight).sum::<f64>();
+    hot / all
+}
@@ -0,0 +1,258 @@
+use crate::events::{self, Event, EventLog};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Burn-rate windows reported during and after the run
+const SHORT_WINDOW: Duration = Duration::from_secs(5 * 60);
+const LONG_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Multi-window threshold at which SRE alerting policies typically page
+/// (2% of a 30-day error budget consumed within one hour)
+const FAST_BURN_THRESHOLD: f64 = 14.4;
+
+/// Latency SLO such as "99% of queries complete successfully within 50ms"
+#[derive(Debug, Clone, Copy)]
+pub struct SloSpec {
+    pub target: f64,
+    pub threshold: Duration,
+}
+
+impl SloSpec {
+    /// Fraction of queries allowed to miss the objective
+    fn error_budget(&self) -> f64 {
+        1.0 - self.target
+    }
+}
+
+/// Parse an SLO written as `<percent>%<<latency>`, e.g. `99%<50ms` or `99.9%<250ms`
+pub fn parse_slo(value: &str) -> Result<SloSpec, String> {
+    let (target, threshold) = value
+        .split_once('<')
+        .ok_or_else(|| format!("invalid SLO '{}', expected e.g. 99%<50ms", value))?;
+
+    let target: f64 = target
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("invalid SLO target in '{}'", value))?;
+    if !(0.0..100.0).contains(&target) || target == 0.0 {
+        return Err(format!(
+            "SLO target must be between 0 and 100% (exclusive), got {}",
+            target
+        ));
+    }
+
+    Ok(SloSpec {
+        target: target / 100.0,
+        threshold: crate::parse_duration(threshold)?,
+    })
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Bucket {
+    second: u64,
+    good: u64,
+    bad: u64,
+}
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    buckets: VecDeque<Bucket>,
+    total_good: u64,
+    total_bad: u64,
+    peak_short_burn: f64,
+    peak_long_burn: f64,
+}
+
+/// Per-second good/bad event counts used to compute rolling burn rates
+#[derive(Debug)]
+pub struct SloTracker {
+    spec: SloSpec,
+    start: Instant,
+    state: Mutex<TrackerState>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SloReport {
+    pub target_percent: f64,
+    pub threshold_ms: f64,
+    pub good_queries: u64,
+    pub bad_queries: u64,
+    pub compliance_percent: f64,
+    pub error_budget_consumed_percent: f64,
+    pub burn_rate_5m: f64,
+    pub burn_rate_1h: f64,
+    pub peak_burn_rate_5m: f64,
+    pub peak_burn_rate_1h: f64,
+}
+
+impl SloTracker {
+    pub fn new(spec: SloSpec) -> Self {
+        Self {
+            spec,
+            start: Instant::now(),
+            state: Mutex::new(TrackerState::default()),
+        }
+    }
+
+    /// Record a completed query; failures always count against the SLO
+    pub fn record(&self, latency: Duration, success: bool) {
+        let good = success && latency <= self.spec.threshold;
+        let second = self.start.elapsed().as_secs();
+        let mut state = self.state.lock().unwrap();
+
+        if good {
+            state.total_good += 1;
+        } else {
+            state.total_bad += 1;
+        }
+
+        match state.buckets.back_mut() {
+            Some(bucket) if bucket.second == second => {
+                if good {
+                    bucket.good += 1;
+                } else {
+                    bucket.bad += 1;
+                }
+            }
+            _ => {
+                state.buckets.push_back(Bucket {
+                    second,
+                    good: good as u64,
+                    bad: (!good) as u64,
+                });
+            }
+        }
+
+        // Only the long window is ever queried, so older buckets can go
+        while state
+            .buckets
+            .front()
+            .is_some_and(|b| b.second + LONG_WINDOW.as_secs() <= second)
+        {
+            state.buckets.pop_front();
+        }
+    }
+
+    /// Burn rates over the short and long windows, updating the observed peaks
+    pub fn sample(&self) -> (f64, f64) {
+        let now = self.start.elapsed().as_secs();
+        let mut state = self.state.lock().unwrap();
+        let short = self.burn_rate(&state.buckets, now, SHORT_WINDOW);
+        let long = self.burn_rate(&state.buckets, now, LONG_WINDOW);
+        state.peak_short_burn = state.peak_short_burn.max(short);
+        state.peak_long_burn = state.peak_long_burn.max(long);
+        (short, long)
+    }
+
+    fn burn_rate(&self, buckets: &VecDeque<Bucket>, now: u64, window: Duration) -> f64 {
+        let (good, bad) = buckets
+            .iter()
+            .rev()
+            .take_while(|b| b.second + window.as_secs() > now)
+            .fold((0u64, 0u64), |(good, bad), b| (good + b.good, bad + b.bad));
+
+        if good + bad == 0 {
+            return 0.0;
+        }
+        (bad as f64 / (good + bad) as f64) / self.spec.error_budget()
+    }
+
+    pub fn report(&self) -> SloReport {
+        let (burn_rate_5m, burn_rate_1h) = self.sample();
+        let state = self.state.lock().unwrap();
+        let total = state.total_good + state.total_bad;
+        let bad_fraction = if total > 0 {
+            state.total_bad as f64 / total as f64
+        } else {
+            0.0
+        };
+
+        SloReport {
+            target_percent: self.spec.target * 100.0,
+            threshold_ms: self.spec.threshold.as_secs_f64() * 1000.0,
+            good_queries: state.total_good,
+            bad_queries: state.total_bad,
+            compliance_percent: (1.0 - bad_fraction) * 100.0,
+            error_budget_consumed_percent: bad_fraction / self.spec.error_budget() * 100.0,
+            burn_rate_5m,
+            burn_rate_1h,
+            peak_burn_rate_5m: state.peak_short_burn,
+            peak_burn_rate_1h: state.peak_long_burn,
+        }
+    }
+}
+
+/// Periodically sample burn rates so peaks are captured and progress is logged. A
+/// fast burn is sent to the event log when it starts.
+pub async fn run_burn_rate_monitor(
+    tracker: Arc<SloTracker>,
+    disable_logging: bool,
+    events: Option<Arc<EventLog>>,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(10));
+    interval.tick().await;
+    let mut burning = false;
+
+    loop {
+        interval.tick().await;
+        let (short, long) = tracker.sample();
+        let fast_burn = short >= FAST_BURN_THRESHOLD && long >= FAST_BURN_THRESHOLD;
+        if fast_burn && !burning {
+            events::emit(events.as_deref(), || Event::ThresholdBreached {
+                threshold: format!("slo burn rate>{}x", FAST_BURN_THRESHOLD),
+                observed: short,
+                action: "alert",
+            });
+        }
+        burning = fast_burn;
+        if !disable_logging {
+            tracing::info!(
+                "🔥 SLO burn rate: {:.2}x (5m), {:.2}x (1h){}",
+                short,
+                long,
+                if fast_burn { " - FAST BURN" } else { "" }
+            );
+        }
+    }
+}
+
+pub fn display_slo_report(report: &SloReport) {
+    println!("\n🔥 SLO Burn Rate:");
+    println!(
+        "   Objective:              {:.2}% < {:.0}ms",
+        report.target_percent, report.threshold_ms
+    );
+    println!(
+        "   Compliance:             {:>7.3}%",
+        report.compliance_percent
+    );
+    println!(
+        "   Error Budget Consumed:  {:>7.1}%",
+        report.error_budget_consumed_percent
+    );
+    println!(
+        "   Burn Rate (5m / 1h):    {:>7.2}x / {:.2}x",
+        report.burn_rate_5m, report.burn_rate_1h
+    );
+    println!(
+        "   Peak Burn (5m / 1h):    {:>7.2}x / {:.2}x",
+        report.peak_burn_rate_5m, report.peak_burn_rate_1h
+    );
+
+    if report.peak_burn_rate_5m >= FAST_BURN_THRESHOLD
+        && report.peak_burn_rate_1h >= FAST_BURN_THRESHOLD
+    {
+        println!(
+            "   🚨 FAST BURN: both windows exceeded {:.1}x (would page on-call)",
+            FAST_BURN_THRESHOLD
+        );
+    } else if report.burn_rate_1h > 1.0 {
+        println!("   ⚠️  Budget burning faster than the SLO allows");
+    } else {
+        println!("   ✅ Within error budget");
+    }
+}
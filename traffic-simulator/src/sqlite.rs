@@ -0,0 +1,426 @@
+use crate::backdate::Backdate;
+use crate::driver::DatabaseDriver;
+use crate::run_tag::RunTag;
+use crate::skew::{self, KeySkew};
+use crate::stores::{self, StoreWeights};
+use crate::transaction::{self, TransactionTracker};
+use crate::writes::{WriteCounts, WriteKind};
+use crate::{FailureStage, QueryAttempt, QueryContext, QueryType, UUID_ORDER_NUMBER_ATTEMPT};
+use futures::future::BoxFuture;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Number of seeded orders, matching the order id range the workloads draw from
+const SEED_ORDERS: i64 = 100_000;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS orders (
+        order_id INTEGER PRIMARY KEY AUTOINCREMENT,
+        order_number TEXT UNIQUE NOT NULL,
+        from_store_id INTEGER,
+        to_store_id INTEGER NOT NULL,
+        product_id INTEGER NOT NULL,
+        quantity_cases INTEGER NOT NULL,
+        order_status TEXT NOT NULL DEFAULT 'pending_review'
+            CHECK (order_status IN ('pending_review', 'approved', 'fulfilled', 'cancelled')),
+        requested_by INTEGER NOT NULL,
+        approved_by INTEGER,
+        order_date TEXT DEFAULT CURRENT_TIMESTAMP,
+        approved_date TEXT,
+        fulfilled_date TEXT,
+        notes TEXT,
+        version INTEGER DEFAULT 1
+    );
+    CREATE INDEX IF NOT EXISTS idx_orders_status ON orders(order_status);
+    CREATE INDEX IF NOT EXISTS idx_orders_to_store ON orders(to_store_id);
+";
+
+/// Embedded SQLite stand-in for PostgreSQL, so workloads, scenarios and reporting
+/// can be exercised on a laptop without a database server. Each "connection" is a
+/// SQLite handle; time spent waiting for a free handle counts as acquisition time.
+#[derive(Clone)]
+pub struct SqliteBackend {
+    inner: Arc<SqliteInner>,
+}
+
+struct SqliteInner {
+    connections: Vec<Mutex<Connection>>,
+    next: AtomicUsize,
+    temp_path: Option<PathBuf>,
+}
+
+impl Drop for SqliteInner {
+    fn drop(&mut self) {
+        if let Some(path) = &self.temp_path {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+            }
+        }
+    }
+}
+
+impl SqliteBackend {
+    /// Open (or create) the database at `path` with `connections` handles. `:memory:`
+    /// uses a throwaway file so that multiple handles can share the data under WAL.
+    pub fn open(path: &str, connections: usize) -> anyhow::Result<Self> {
+        let (db_path, temp_path) = if path == ":memory:" {
+            let temp = std::env::temp_dir().join(format!(
+                "traffic-simulator-{}.sqlite",
+                uuid::Uuid::new_v4().simple()
+            ));
+            (temp.clone(), Some(temp))
+        } else {
+            (PathBuf::from(path), None)
+        };
+
+        let mut handles = Vec::with_capacity(connections.max(1));
+        for _ in 0..connections.max(1) {
+            let conn = Connection::open(&db_path)?;
+            conn.busy_timeout(Duration::from_secs(5))?;
+            conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")?;
+            handles.push(Mutex::new(conn));
+        }
+
+        let backend = Self {
+            inner: Arc::new(SqliteInner {
+                connections: handles,
+                next: AtomicUsize::new(0),
+                temp_path,
+            }),
+        };
+        backend.bootstrap()?;
+
+        Ok(backend)
+    }
+
+    /// Create the orders schema and seed it when empty
+    fn bootstrap(&self) -> anyhow::Result<()> {
+        let conn = self.inner.connections[0].lock().unwrap();
+        conn.execute_batch(SCHEMA)?;
+
+        let existing: Option<i64> = conn
+            .query_row("SELECT order_id FROM orders LIMIT 1", [], |row| row.get(0))
+            .optional()?;
+        if existing.is_none() {
+            conn.execute(
+                "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < ?1)
+                 INSERT INTO orders (order_number, to_store_id, product_id, quantity_cases, requested_by, order_status)
+                 SELECT 'SEED' || n, 1 + n % 10, 1 + n % 50, 1 + n % 20, 1 + n % 5,
+                        CASE n % 3 WHEN 0 THEN 'pending_review' WHEN 1 THEN 'approved' ELSE 'fulfilled' END
+                 FROM seq",
+                params![SEED_ORDERS],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn test_connection(&self) -> anyhow::Result<()> {
+        let inner = Arc::clone(&self.inner);
+        tokio::task::spawn_blocking(move || {
+            let conn = inner.connections[0].lock().unwrap();
+            conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0))?;
+            Ok(())
+        })
+        .await?
+    }
+
+    pub async fn server_version(&self) -> anyhow::Result<String> {
+        let inner = Arc::clone(&self.inner);
+        tokio::task::spawn_blocking(move || {
+            let conn = inner.connections[0].lock().unwrap();
+            Ok(conn.query_row("SELECT 'SQLite ' || sqlite_version()", [], |row| row.get(0))?)
+        })
+        .await?
+    }
+
+    /// Run one workload operation on the next handle, mirroring the PostgreSQL workloads
+    pub async fn execute_query(
+        &self,
+        ctx: &QueryContext,
+        query_type: &QueryType,
+        seed: u64,
+        attempt: u32,
+    ) -> QueryAttempt {
+        let inner = Arc::clone(&self.inner);
+        let query_type = query_type.clone();
+        let settings = RequestSettings {
+            backdate: ctx.backdate.clone(),
+            stores: ctx.stores_for(seed).cloned(),
+            skew: ctx.skew,
+            run_tag: Arc::clone(&ctx.run_tag),
+            transactions: Arc::clone(&ctx.transactions),
+        };
+
+        let attempt = tokio::task::spawn_blocking(move || {
+            let index = inner.next.fetch_add(1, Ordering::Relaxed) % inner.connections.len();
+
+            let connection_start = Instant::now();
+            let conn = inner.connections[index].lock().unwrap();
+            let connection_time = connection_start.elapsed();
+
+            let query_start = Instant::now();
+            let mut writes = WriteCounts::default();
+            let result = execute_operational_query(
+                &conn,
+                &query_type,
+                seed,
+                attempt,
+                &settings,
+                &mut writes,
+            );
+            let query_execution_time = query_start.elapsed();
+
+            QueryAttempt {
+                connection_time,
+                query_execution_time,
+                connection_recycled: false,
+                error: result.err().map(|e| (FailureStage::Query, e)),
+                writes,
+                think_time: Duration::ZERO,
+            }
+        })
+        .await;
+
+        attempt.unwrap_or_else(|e| QueryAttempt {
+            connection_time: Duration::ZERO,
+            query_execution_time: Duration::ZERO,
+            connection_recycled: false,
+            error: Some((FailureStage::Connection, e.into())),
+            writes: WriteCounts::default(),
+            think_time: Duration::ZERO,
+        })
+    }
+}
+
+impl DatabaseDriver for SqliteBackend {
+    fn test_connection(&self) -> BoxFuture<'_, anyhow::Result<()>> {
+        Box::pin(SqliteBackend::test_connection(self))
+    }
+
+    fn server_version(&self) -> BoxFuture<'_, anyhow::Result<String>> {
+        Box::pin(SqliteBackend::server_version(self))
+    }
+
+    fn execute_attempt<'a>(
+        &'a self,
+        ctx: &'a QueryContext,
+        query_type: &'a QueryType,
+        seed: u64,
+        attempt: u32,
+    ) -> BoxFuture<'a, QueryAttempt> {
+        Box::pin(self.execute_query(ctx, query_type, seed, attempt))
+    }
+}
+
+/// Run-wide settings an operation needs inside the blocking task
+struct RequestSettings {
+    backdate: Option<Arc<Backdate>>,
+    stores: Option<Arc<StoreWeights>>,
+    skew: KeySkew,
+    run_tag: Arc<RunTag>,
+    transactions: Arc<TransactionTracker>,
+}
+
+fn execute_operational_query(
+    conn: &Connection,
+    query_type: &QueryType,
+    seed: u64,
+    attempt: u32,
+    settings: &RequestSettings,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<usize> {
+    match query_type.resolve(seed) {
+        QueryType::Select => execute_select(conn, seed, settings.skew),
+        QueryType::Insert => execute_insert(conn, seed, attempt, settings, writes),
+        QueryType::Update => execute_update(conn, seed, settings.skew, writes),
+        QueryType::Search => execute_search(conn, seed, settings.stores.as_deref(), settings.skew),
+        QueryType::Mixed => unreachable!("Mixed resolves to a single statement"),
+        QueryType::Transaction => execute_transaction(conn, seed, attempt, settings, writes),
+        query_type @ (QueryType::Workflow
+        | QueryType::Inventory
+        | QueryType::Catalog
+        | QueryType::Store) => Err(anyhow::anyhow!(
+            "--query-type {} requires the PostgreSQL backend",
+            query_type.name()
+        )),
+    }
+}
+
+/// Same transaction shape as the PostgreSQL workload. `BEGIN IMMEDIATE` takes the
+/// write lock up front, so concurrent transactions queue on the busy timeout
+/// instead of failing at their first write.
+fn execute_transaction(
+    conn: &Connection,
+    seed: u64,
+    attempt: u32,
+    settings: &RequestSettings,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<usize> {
+    let tracker = &settings.transactions;
+    conn.execute_batch("BEGIN IMMEDIATE")?;
+
+    let mut rows = 0;
+    for (statement_seed, kind) in transaction::statement_plan(seed, tracker.statements) {
+        let result = match kind {
+            WriteKind::Insert => execute_insert(conn, statement_seed, attempt, settings, writes),
+            _ => execute_update(conn, statement_seed, settings.skew, writes),
+        };
+        match result {
+            Ok(statement_rows) => rows += statement_rows,
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                tracker.record_rollback(&e);
+                return Err(e);
+            }
+        }
+    }
+
+    let commit_start = Instant::now();
+    if let Err(e) = conn.execute_batch("COMMIT") {
+        let _ = conn.execute_batch("ROLLBACK");
+        let e = e.into();
+        tracker.record_rollback(&e);
+        return Err(e);
+    }
+    tracker.record_commit(commit_start.elapsed());
+
+    Ok(rows)
+}
+
+fn execute_select(conn: &Connection, seed: u64, skew: KeySkew) -> anyhow::Result<usize> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let order_id = skew.pick_i64(skew::ORDER_KEY_COUNT.into(), &mut rng);
+
+    let mut statement = conn.prepare_cached(
+        "SELECT order_id, order_number, order_status, quantity_cases FROM orders WHERE order_id = ?1",
+    )?;
+    let rows = statement.query_map(params![order_id], |_| Ok(()))?.count();
+
+    Ok(rows)
+}
+
+fn execute_search(
+    conn: &Connection,
+    seed: u64,
+    stores: Option<&StoreWeights>,
+    skew: KeySkew,
+) -> anyhow::Result<usize> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let to_store_id = stores::choose_store(stores, skew, &mut rng);
+    let status = ["pending_review", "approved", "fulfilled"][rng.gen_range(0..3)];
+
+    let mut statement = conn.prepare_cached(
+        "SELECT order_id, order_number, order_status, quantity_cases FROM orders
+         WHERE to_store_id = ?1 AND order_status = ?2 ORDER BY order_date DESC LIMIT 20",
+    )?;
+    let rows = statement
+        .query_map(params![to_store_id, status], |_| Ok(()))?
+        .count();
+
+    Ok(rows)
+}
+
+fn execute_insert(
+    conn: &Connection,
+    seed: u64,
+    attempt: u32,
+    settings: &RequestSettings,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<usize> {
+    let stores = settings.stores.as_deref();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let to_store_id = stores::choose_store(stores, settings.skew, &mut rng);
+    let product_id = settings.skew.pick_i64(skew::PRODUCT_COUNT.into(), &mut rng);
+    let quantity_cases = rng.gen_range(1..=20i64);
+    let requested_by = rng.gen_range(1..=5i64);
+
+    // Same order-number scheme as the PostgreSQL workload; collisions are retried
+    // by the unique_violation retry policy
+    let prefix = settings.run_tag.order_number_prefix(stores);
+    let order_number = if attempt >= UUID_ORDER_NUMBER_ATTEMPT {
+        let uuid_suffix = uuid::Uuid::new_v4().simple().to_string();
+        format!("{}ORD{}", prefix, uuid_suffix[..8].to_uppercase())
+    } else {
+        let timestamp_component = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let unique_component =
+            (seed.wrapping_mul(1000) + attempt as u64 + (timestamp_component % 10000)) % 999999;
+        format!("{}ORD{:06}", prefix, unique_component + 1)
+    };
+
+    let rows = match settings.backdate.as_deref().map(|backdate| backdate.sample(&mut rng)) {
+        Some(order_date) => conn
+            .prepare_cached(
+                "INSERT INTO orders (order_number, to_store_id, product_id, quantity_cases, requested_by, order_status, order_date)
+                 VALUES (?1, ?2, ?3, ?4, ?5, 'pending_review', datetime(?6, 'unixepoch'))",
+            )?
+            .execute(params![
+                order_number,
+                to_store_id,
+                product_id,
+                quantity_cases,
+                requested_by,
+                order_date
+            ])?,
+        None => conn
+            .prepare_cached(
+                "INSERT INTO orders (order_number, to_store_id, product_id, quantity_cases, requested_by, order_status)
+                 VALUES (?1, ?2, ?3, ?4, ?5, 'pending_review')",
+            )?
+            .execute(params![
+                order_number,
+                to_store_id,
+                product_id,
+                quantity_cases,
+                requested_by
+            ])?,
+    };
+    writes.record(WriteKind::Insert, rows as u64);
+
+    Ok(rows)
+}
+
+fn execute_update(
+    conn: &Connection,
+    seed: u64,
+    skew: KeySkew,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<usize> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let rows = match rng.gen_range(0..3) {
+        0 => conn.execute(
+            "UPDATE orders SET order_status = 'approved', approved_date = CURRENT_TIMESTAMP
+             WHERE order_status = 'pending_review' AND order_id IN (
+                 SELECT order_id FROM orders WHERE order_status = 'pending_review' LIMIT 1
+             )",
+            [],
+        )?,
+        1 => conn.execute(
+            "UPDATE orders SET order_status = 'fulfilled', fulfilled_date = CURRENT_TIMESTAMP
+             WHERE order_status = 'approved' AND order_id IN (
+                 SELECT order_id FROM orders WHERE order_status = 'approved' LIMIT 1
+             )",
+            [],
+        )?,
+        _ => {
+            let order_id = skew.pick_i64(skew::ORDER_KEY_COUNT.into(), &mut rng);
+            let new_quantity = rng.gen_range(1..=25i64);
+            conn.execute(
+                "UPDATE orders SET quantity_cases = ?1 WHERE order_id = ?2 AND order_status = 'pending_review'",
+                params![new_quantity, order_id],
+            )?
+        }
+    };
+    writes.record(WriteKind::Update, rows as u64);
+
+    Ok(rows)
+}
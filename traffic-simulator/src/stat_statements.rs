@@ -0,0 +1,404 @@
+use crate::plugin::PluginStatement;
+use crate::writes::WriteKind;
+use deadpool_postgres::Pool;
+use rand::distributions::WeightedIndex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::Value;
+use tokio_postgres::types::Type;
+use tracing::{info, warn};
+
+/// Statements of the current database that applications run, busiest first
+const OBSERVED_STATEMENTS_SQL: &str = "SELECT query, calls::int8 FROM pg_stat_statements \
+     WHERE dbid = (SELECT oid FROM pg_database WHERE datname = current_database()) \
+       AND query ~* '^\\s*(select|insert|update|delete|with)\\s' \
+       AND query !~* '(pg_stat_statements|pg_catalog|information_schema)' \
+       AND calls > 0 \
+     ORDER BY calls DESC";
+
+/// Value statistics of the column named `$1`, preferring tables the statement `$2`
+/// mentions over same-named columns elsewhere
+const COLUMN_STATS_SQL: &str = "SELECT most_common_vals::text::text[], most_common_freqs, \
+            histogram_bounds::text::text[], n_distinct = -1 \
+     FROM pg_stats \
+     WHERE attname = $1 AND schemaname NOT IN ('pg_catalog', 'information_schema') \
+     ORDER BY position(tablename IN $2) > 0 DESC, schemaname = 'public' DESC \
+     LIMIT 1";
+
+/// Characters in a SQL text value made up for a column without statistics
+const FALLBACK_TEXT: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Statements shown in the logged statement mix
+const LOGGED_STATEMENTS: usize = 5;
+
+/// Workload synthesized from `pg_stat_statements`: the database's busiest
+/// statements, run in proportion to their observed calls, with parameter values
+/// sampled from the planner statistics of the columns they are compared with
+#[derive(Debug)]
+pub struct ObservedWorkload {
+    statements: Vec<ObservedStatement>,
+    weights: WeightedIndex<f64>,
+}
+
+#[derive(Debug)]
+struct ObservedStatement {
+    /// Normalized statement text; placeholders of types the simulator cannot bind
+    /// directly are rewritten to cast from text
+    sql: String,
+    calls: i64,
+    params: Vec<ParamSource>,
+}
+
+/// How a placeholder's values are generated
+#[derive(Debug)]
+struct ParamSource {
+    kind: ParamKind,
+    stats: Option<ColumnStats>,
+    /// Inserted into a unique column, so existing values would only collide
+    fresh: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ParamKind {
+    Int,
+    Float,
+    Bool,
+    Text,
+    /// Bound as text and cast to the placeholder's type in the statement
+    Cast,
+}
+
+/// `pg_stats` view of a column's values
+#[derive(Debug)]
+struct ColumnStats {
+    common: Vec<String>,
+    frequencies: Vec<f32>,
+    /// Equi-depth bucket bounds of the values not in `common`
+    histogram: Vec<String>,
+    /// Every row has its own value, as with keys and order numbers
+    unique: bool,
+}
+
+impl ParamKind {
+    fn of(ty: &Type) -> Self {
+        match *ty {
+            Type::INT2 | Type::INT4 | Type::INT8 => ParamKind::Int,
+            Type::FLOAT4 | Type::FLOAT8 => ParamKind::Float,
+            Type::BOOL => ParamKind::Bool,
+            Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => ParamKind::Text,
+            _ => ParamKind::Cast,
+        }
+    }
+
+    /// Convert a value as `pg_stats` prints it
+    fn parse(self, text: &str) -> Option<Value> {
+        match self {
+            ParamKind::Int => text.parse::<i64>().ok().map(Value::from),
+            ParamKind::Float => text.parse::<f64>().ok().map(Value::from),
+            ParamKind::Bool => match text {
+                "t" | "true" => Some(Value::Bool(true)),
+                "f" | "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            ParamKind::Text | ParamKind::Cast => Some(Value::String(text.to_string())),
+        }
+    }
+}
+
+impl ObservedWorkload {
+    /// Read up to `top` of the busiest statements and the statistics of the columns
+    /// their parameters are compared with
+    pub async fn load(pool: &Pool, top: usize) -> anyhow::Result<Self> {
+        let client = pool.get().await?;
+        let rows = client.query(OBSERVED_STATEMENTS_SQL, &[]).await.map_err(|e| {
+            anyhow::anyhow!(
+                "Cannot read pg_stat_statements ({}); it needs `shared_preload_libraries = 'pg_stat_statements'` and `CREATE EXTENSION pg_stat_statements`",
+                db_message(&e)
+            )
+        })?;
+
+        let mut statements = Vec::new();
+        for row in rows {
+            if statements.len() == top {
+                break;
+            }
+            let query: String = row.get(0);
+            let calls: i64 = row.get(1);
+            // Statements on temporary tables, dropped objects or untyped
+            // placeholders cannot be prepared here
+            let prepared = match client.prepare(&query).await {
+                Ok(prepared) => prepared,
+                Err(e) => {
+                    warn!(
+                        "Skipping observed statement {}: {}",
+                        abbreviate(&query),
+                        db_message(&e)
+                    );
+                    continue;
+                }
+            };
+
+            let inserts = WriteKind::of_sql(&query) == Some(WriteKind::Insert);
+            let mut sql = query.clone();
+            let mut params = Vec::with_capacity(prepared.params().len());
+            for (index, ty) in prepared.params().iter().enumerate() {
+                let position = index + 1;
+                let kind = ParamKind::of(ty);
+                if kind == ParamKind::Cast {
+                    sql = cast_placeholder(&sql, position, ty.name());
+                }
+                let stats = match compared_column(&query, position) {
+                    Some(column) => column_stats(&client, &column, &query).await?,
+                    None => None,
+                };
+                let fresh = inserts && stats.as_ref().is_some_and(|stats| stats.unique);
+                params.push(ParamSource { kind, stats, fresh });
+            }
+            statements.push(ObservedStatement { sql, calls, params });
+        }
+
+        if statements.is_empty() {
+            return Err(anyhow::anyhow!(
+                "pg_stat_statements has recorded no usable SELECT, INSERT, UPDATE or DELETE statements for this database"
+            ));
+        }
+        let weights = WeightedIndex::new(statements.iter().map(|s| s.calls as f64))?;
+        Ok(Self {
+            statements,
+            weights,
+        })
+    }
+
+    /// Log the synthesized mix, busiest statements first
+    pub fn log_mix(&self) {
+        let total: i64 = self.statements.iter().map(|s| s.calls).sum();
+        info!(
+            "🔭 Observed workload: {} statements from pg_stat_statements ({} calls)",
+            self.statements.len(),
+            total
+        );
+        for statement in self.statements.iter().take(LOGGED_STATEMENTS) {
+            info!(
+                "   {:>5.1}%  {}",
+                statement.calls as f64 / total as f64 * 100.0,
+                abbreviate(&statement.sql)
+            );
+        }
+        if self.statements.len() > LOGGED_STATEMENTS {
+            info!(
+                "   ... and {} more",
+                self.statements.len() - LOGGED_STATEMENTS
+            );
+        }
+    }
+
+    /// Pick a statement in proportion to its observed calls and fill in its
+    /// parameters, deterministically for `seed`
+    pub fn generate(&self, seed: u64) -> PluginStatement {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let statement = &self.statements[rng.sample(&self.weights)];
+        PluginStatement {
+            sql: statement.sql.clone(),
+            params: statement
+                .params
+                .iter()
+                .map(|param| param.sample(&mut rng))
+                .collect(),
+            think_ms: 0,
+        }
+    }
+}
+
+impl ParamSource {
+    fn sample(&self, rng: &mut StdRng) -> Value {
+        let value = self
+            .stats
+            .as_ref()
+            .and_then(|stats| stats.sample(self.kind, rng))
+            .unwrap_or_else(|| fallback_value(self.kind, rng));
+        if self.fresh {
+            fresh_value(self.kind, value, rng)
+        } else {
+            value
+        }
+    }
+}
+
+impl ColumnStats {
+    /// A common value with its frequency, otherwise a value from a random
+    /// histogram bucket (numbers anywhere within it), so samples follow the
+    /// column's distribution
+    fn sample(&self, kind: ParamKind, rng: &mut StdRng) -> Option<Value> {
+        let common_share: f32 = self.frequencies.iter().sum();
+        let mut pick = rng.gen::<f32>();
+        if pick < common_share || self.histogram.len() < 2 {
+            for (value, frequency) in self.common.iter().zip(&self.frequencies) {
+                if pick < *frequency {
+                    return kind.parse(value);
+                }
+                pick -= frequency;
+            }
+            return self.common.last().and_then(|value| kind.parse(value));
+        }
+
+        let bucket = rng.gen_range(0..self.histogram.len() - 1);
+        let (low, high) = (&self.histogram[bucket], &self.histogram[bucket + 1]);
+        match (kind.parse(low)?, kind.parse(high)?) {
+            (Value::Number(low), Value::Number(high)) if kind == ParamKind::Int => {
+                let (low, high) = (low.as_i64()?, high.as_i64()?);
+                Some(Value::from(rng.gen_range(low.min(high)..=low.max(high))))
+            }
+            (Value::Number(low), Value::Number(high)) => {
+                let (low, high) = (low.as_f64()?, high.as_f64()?);
+                Some(Value::from(low + (high - low) * rng.gen::<f64>()))
+            }
+            (low, high) => Some(if rng.gen() { low } else { high }),
+        }
+    }
+}
+
+/// Turn an existing value of a unique column into one unlikely to exist: numbers
+/// move far past the sampled one, text gets a random suffix
+fn fresh_value(kind: ParamKind, value: Value, rng: &mut StdRng) -> Value {
+    match (kind, value) {
+        (ParamKind::Int, Value::Number(number)) => number.as_i64().map_or(Value::Null, |n| {
+            Value::from(n.saturating_add(rng.gen_range(1_000_000..i64::from(i32::MAX))))
+        }),
+        (ParamKind::Text | ParamKind::Cast, Value::String(text)) => {
+            Value::String(format!("{}-{}", text, random_text(rng)))
+        }
+        (_, value) => value,
+    }
+}
+
+fn random_text(rng: &mut StdRng) -> String {
+    (0..8)
+        .map(|_| FALLBACK_TEXT[rng.gen_range(0..FALLBACK_TEXT.len())] as char)
+        .collect()
+}
+
+/// Value for a placeholder whose column has no statistics
+fn fallback_value(kind: ParamKind, rng: &mut StdRng) -> Value {
+    match kind {
+        ParamKind::Int => Value::from(rng.gen_range(1..=100)),
+        ParamKind::Float => Value::from(rng.gen_range(0.0..100.0)),
+        ParamKind::Bool => Value::Bool(rng.gen()),
+        ParamKind::Text => Value::String(random_text(rng)),
+        // Any text would have to parse as the placeholder's type; NULL always does
+        ParamKind::Cast => Value::Null,
+    }
+}
+
+async fn column_stats(
+    client: &deadpool_postgres::Client,
+    column: &str,
+    query: &str,
+) -> anyhow::Result<Option<ColumnStats>> {
+    let row = client
+        .query_opt(COLUMN_STATS_SQL, &[&column, &query])
+        .await?;
+    Ok(row.map(|row| ColumnStats {
+        common: row.get::<_, Option<Vec<String>>>(0).unwrap_or_default(),
+        frequencies: row.get::<_, Option<Vec<f32>>>(1).unwrap_or_default(),
+        histogram: row.get::<_, Option<Vec<String>>>(2).unwrap_or_default(),
+        unique: row.get::<_, Option<bool>>(3).unwrap_or(false),
+    }))
+}
+
+/// Column placeholder `$position` is compared with or assigned to: the identifier
+/// before `=`, `<>`, `<`, `LIKE`, `IN (` and the like, or the matching column of an
+/// `INSERT ... (columns) VALUES (...)` list. `None` for e.g. `LIMIT $1`.
+fn compared_column(sql: &str, position: usize) -> Option<String> {
+    let index = placeholder_index(sql, position)?;
+    insert_column(sql, index).or_else(|| operand_column(&sql[..index]))
+}
+
+/// Byte offset of `$position` (not the start of e.g. `$12` for `$1`)
+fn placeholder_index(sql: &str, position: usize) -> Option<usize> {
+    let placeholder = format!("${}", position);
+    sql.match_indices(&placeholder)
+        .map(|(index, _)| index)
+        .find(|index| !sql[index + placeholder.len()..].starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// Identifier on the left of the comparison `before` ends with
+fn operand_column(before: &str) -> Option<String> {
+    let mut rest = before.trim_end();
+    rest = rest.strip_suffix('(').unwrap_or(rest).trim_end();
+    let upper = rest.to_ascii_uppercase();
+    let operator = ["ILIKE", "LIKE", " IN"]
+        .iter()
+        .find(|keyword| upper.ends_with(*keyword))
+        .map(|keyword| keyword.len())
+        .unwrap_or_else(|| rest.len() - rest.trim_end_matches(['=', '<', '>', '!']).len());
+    if operator == 0 {
+        return None;
+    }
+    let rest = rest[..rest.len() - operator].trim_end();
+    let start = rest
+        .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '"'))
+        .map_or(0, |index| index + 1);
+    let column = rest[start..].trim_matches('"');
+    (!column.is_empty() && !column.starts_with(|c: char| c.is_ascii_digit()))
+        .then(|| column.to_string())
+}
+
+/// Column of an `INSERT INTO table (columns) VALUES (...)` whose value is exactly
+/// the placeholder at `index`
+fn insert_column(sql: &str, index: usize) -> Option<String> {
+    let upper = sql.to_ascii_uppercase();
+    if !upper.trim_start().starts_with("INSERT") {
+        return None;
+    }
+    let values = upper.find("VALUES")?;
+    let columns = &sql[sql.find('(').filter(|open| *open < values)?..values];
+    let columns = columns.trim().strip_prefix('(')?.strip_suffix(')')?;
+    let tuple_start = values + sql[values..].find('(')? + 1;
+    if index < tuple_start {
+        return None;
+    }
+    let tuple_end = tuple_start + sql[tuple_start..].find(')')?;
+    let mut offset = tuple_start;
+    for (column, value) in columns
+        .split(',')
+        .zip(sql[tuple_start..tuple_end].split(','))
+    {
+        let trimmed = offset + (value.len() - value.trim_start().len());
+        if trimmed == index {
+            return Some(column.trim().trim_matches('"').to_string());
+        }
+        offset += value.len() + 1;
+    }
+    None
+}
+
+/// Rewrite every `$position` to `$position::text::ty`
+fn cast_placeholder(sql: &str, position: usize, ty: &str) -> String {
+    let placeholder = format!("${}", position);
+    let mut rewritten = String::with_capacity(sql.len());
+    let mut rest = sql;
+    while let Some(index) = rest.find(&placeholder) {
+        let end = index + placeholder.len();
+        rewritten.push_str(&rest[..end]);
+        if !rest[end..].starts_with(|c: char| c.is_ascii_digit()) {
+            rewritten.push_str(&format!("::text::{}", ty));
+        }
+        rest = &rest[end..];
+    }
+    rewritten.push_str(rest);
+    rewritten
+}
+
+fn db_message(e: &tokio_postgres::Error) -> String {
+    e.as_db_error()
+        .map_or_else(|| e.to_string(), |db| db.message().to_string())
+}
+
+/// Statement on one line, shortened for logs
+fn abbreviate(sql: &str) -> String {
+    let flat = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+    match flat.char_indices().nth(100) {
+        Some((index, _)) => format!("{}…", &flat[..index]),
+        None => flat,
+    }
+}
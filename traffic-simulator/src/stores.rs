@@ -0,0 +1,266 @@
+use crate::skew::KeySkew;
+use crate::{Args, Backend};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Stores the orders workload targets (`to_store_id` 1..=10), matching the seeded
+/// demo data
+pub const STORE_COUNT: i32 = 10;
+
+/// Relative traffic weight for a store or an inclusive range of stores
+#[derive(Debug, Clone)]
+pub struct StoreWeight {
+    first: i32,
+    last: i32,
+    weight: f64,
+}
+
+/// Relative traffic weight for every store in a region
+#[derive(Debug, Clone)]
+pub struct RegionWeight {
+    region: String,
+    weight: f64,
+}
+
+/// Parse `STORE=WEIGHT` or `FIRST-LAST=WEIGHT`, e.g. `3=2` or `1-5=1.5`
+pub fn parse_store_weight(value: &str) -> Result<StoreWeight, String> {
+    let (stores, weight) = split_weight(value)?;
+    let parse = |store: &str| {
+        store
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| format!("invalid store id '{}' in '{}'", store, value))
+    };
+    let (first, last) = match stores.split_once('-') {
+        Some((first, last)) => (parse(first)?, parse(last)?),
+        None => (parse(stores)?, parse(stores)?),
+    };
+    if first < 1 || last > STORE_COUNT || first > last {
+        return Err(format!(
+            "invalid stores '{}': ids must be in 1-{}",
+            stores, STORE_COUNT
+        ));
+    }
+    Ok(StoreWeight {
+        first,
+        last,
+        weight,
+    })
+}
+
+/// Parse `REGION=WEIGHT`, e.g. `West=2`
+pub fn parse_region_weight(value: &str) -> Result<RegionWeight, String> {
+    let (region, weight) = split_weight(value)?;
+    Ok(RegionWeight {
+        region: region.trim().to_string(),
+        weight,
+    })
+}
+
+fn split_weight(value: &str) -> Result<(&str, f64), String> {
+    let (key, weight) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("invalid weight '{}', expected KEY=WEIGHT", value))?;
+    let weight: f64 = weight
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid weight in '{}'", value))?;
+    if !weight.is_finite() || weight < 0.0 {
+        return Err(format!("weight must be zero or positive in '{}'", value));
+    }
+    Ok((key, weight))
+}
+
+/// `--store-weights` file: the same weights as the flags, as YAML maps
+#[derive(Debug, Default, Deserialize)]
+struct WeightsFile {
+    #[serde(default)]
+    stores: serde_yaml::Mapping,
+    #[serde(default)]
+    regions: serde_yaml::Mapping,
+}
+
+impl WeightsFile {
+    fn load(path: &str) -> anyhow::Result<(Vec<StoreWeight>, Vec<RegionWeight>)> {
+        let invalid = |e: String| anyhow::anyhow!("Invalid store weights '{}': {}", path, e);
+        let contents = std::fs::read_to_string(path)?;
+        let file: WeightsFile =
+            serde_yaml::from_str(&contents).map_err(|e| invalid(e.to_string()))?;
+
+        let entries = |map: &serde_yaml::Mapping| -> anyhow::Result<Vec<String>> {
+            map.iter()
+                .map(
+                    |(key, weight)| match (yaml_scalar(key), yaml_scalar(weight)) {
+                        (Some(key), Some(weight)) => Ok(format!("{}={}", key, weight)),
+                        _ => Err(invalid(format!("expected KEY: WEIGHT, got {:?}", key))),
+                    },
+                )
+                .collect()
+        };
+        let stores = entries(&file.stores)?
+            .iter()
+            .map(|entry| parse_store_weight(entry).map_err(invalid))
+            .collect::<anyhow::Result<_>>()?;
+        let regions = entries(&file.regions)?
+            .iter()
+            .map(|entry| parse_region_weight(entry).map_err(invalid))
+            .collect::<anyhow::Result<_>>()?;
+        Ok((stores, regions))
+    }
+}
+
+fn yaml_scalar(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// Skewed choice of the store an order or store search targets
+#[derive(Debug)]
+pub struct StoreWeights {
+    weights: Vec<f64>,
+    index: WeightedIndex<f64>,
+    /// With --tenants, the tenant that owns these stores
+    tenant: Option<u32>,
+}
+
+impl StoreWeights {
+    /// Combine the `--store-weights` file with the `--store-weight` and
+    /// `--region-weight` flags (flags win). Stores not mentioned keep weight 1;
+    /// region and store weights multiply. `None` when no weighting is configured.
+    pub async fn resolve(args: &Args, backend: &Backend) -> anyhow::Result<Option<Self>> {
+        let (mut store_weights, mut region_weights) = match &args.store_weights {
+            Some(path) => WeightsFile::load(path)?,
+            None => (Vec::new(), Vec::new()),
+        };
+        store_weights.extend(args.store_weight.iter().cloned());
+        region_weights.extend(args.region_weight.iter().cloned());
+        if store_weights.is_empty() && region_weights.is_empty() {
+            return Ok(None);
+        }
+
+        let mut weights = vec![1.0; STORE_COUNT as usize];
+        if !region_weights.is_empty() {
+            let regions = load_regions(backend).await?;
+            let mut by_region = BTreeMap::new();
+            for RegionWeight { region, weight } in region_weights {
+                if !regions.values().any(|r| r.eq_ignore_ascii_case(&region)) {
+                    let known: std::collections::BTreeSet<_> = regions.values().collect();
+                    return Err(anyhow::anyhow!(
+                        "Unknown region '{}' (stores 1-{} are in {:?})",
+                        region,
+                        STORE_COUNT,
+                        known
+                    ));
+                }
+                by_region.insert(region.to_lowercase(), weight);
+            }
+            for (store, region) in &regions {
+                if let Some(weight) = by_region.get(&region.to_lowercase()) {
+                    weights[(*store - 1) as usize] *= weight;
+                }
+            }
+        }
+
+        // Later entries override earlier ones for the same store
+        let mut overrides = vec![None; STORE_COUNT as usize];
+        for StoreWeight {
+            first,
+            last,
+            weight,
+        } in store_weights
+        {
+            for store in first..=last {
+                overrides[(store - 1) as usize] = Some(weight);
+            }
+        }
+        for (weight, store_weight) in weights.iter_mut().zip(overrides) {
+            if let Some(store_weight) = store_weight {
+                *weight *= store_weight;
+            }
+        }
+
+        let index = WeightedIndex::new(&weights)
+            .map_err(|e| anyhow::anyhow!("Invalid store weights: {}", e))?;
+        Ok(Some(Self {
+            weights,
+            index,
+            tenant: None,
+        }))
+    }
+
+    /// Tenant `tenant` (1-based) of `tenants`: stores are dealt out round robin,
+    /// so tenant 1 of 3 owns stores 1, 4, 7 and 10
+    pub fn for_tenant(tenant: u32, tenants: u32) -> anyhow::Result<Self> {
+        let weights: Vec<f64> = (0..STORE_COUNT as u32)
+            .map(|store| f64::from(u8::from(store % tenants == tenant - 1)))
+            .collect();
+        let index = WeightedIndex::new(&weights)
+            .map_err(|e| anyhow::anyhow!("Tenant {} owns no stores: {}", tenant, e))?;
+        Ok(Self {
+            weights,
+            index,
+            tenant: Some(tenant),
+        })
+    }
+
+    pub fn choose(&self, rng: &mut StdRng) -> i32 {
+        self.index.sample(rng) as i32 + 1
+    }
+
+    /// Each store's share of traffic, e.g. `1: 25.0%, 2: 8.3%, ...`
+    pub fn describe(&self) -> String {
+        let total: f64 = self.weights.iter().sum();
+        self.weights
+            .iter()
+            .enumerate()
+            .map(|(i, weight)| format!("{}: {:.1}%", i + 1, weight / total * 100.0))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// The store an order or search targets: weighted when configured, else by the
+/// --skew distribution
+pub fn choose_store(weights: Option<&StoreWeights>, skew: KeySkew, rng: &mut StdRng) -> i32 {
+    match weights {
+        Some(weights) => weights.choose(rng),
+        None => skew.pick_i32(STORE_COUNT, rng),
+    }
+}
+
+/// Start of every order number the simulator inserts, setting them apart from the
+/// demo application's own `ORD000123` orders so `cleanup` can find them
+pub const SIMULATOR_ORDER_PREFIX: &str = "SIM-";
+
+/// Prefix of the order numbers run `run_id` inserts, e.g. `SIM-3f9c01ab-T02-` for
+/// tenant 2
+pub fn order_number_prefix(run_id: &str, weights: Option<&StoreWeights>) -> String {
+    match weights.and_then(|weights| weights.tenant) {
+        Some(tenant) => format!("{}{}-T{:02}-", SIMULATOR_ORDER_PREFIX, run_id, tenant),
+        None => format!("{}{}-", SIMULATOR_ORDER_PREFIX, run_id),
+    }
+}
+
+async fn load_regions(backend: &Backend) -> anyhow::Result<BTreeMap<i32, String>> {
+    let Backend::Postgres(pool) = backend else {
+        return Err(anyhow::anyhow!(
+            "--region-weight needs the stores table, which only the PostgreSQL backend has"
+        ));
+    };
+    let client = pool.get().await?;
+    let rows = client
+        .query(
+            "SELECT store_id, region FROM stores WHERE store_id BETWEEN 1 AND $1",
+            &[&STORE_COUNT],
+        )
+        .await?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| Some((row.get(0), row.get::<_, Option<String>>(1)?)))
+        .collect())
+}
@@ -0,0 +1,174 @@
+use crate::failures::FailureKind;
+use crate::plugin::WasmPlugin;
+use crate::pool_options::PoolOptions;
+use crate::stat_statements::ObservedWorkload;
+use crate::ycsb::YcsbWorkload;
+use crate::{
+    create_connection_pool, execute_query_from, summarize_latencies, Args, Backend, LatencySummary,
+    QueryContext,
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Barrier;
+use tracing::info;
+
+/// Seeds of the storm's queries start here, far from those of the warmup and run,
+/// so its inserts do not take their order numbers
+const STORM_SEED_OFFSET: u64 = 1 << 48;
+
+/// A cold thundering herd: `--storm` clients that all open a connection and send a
+/// query at the same instant, as after a deploy, a failover or a pooler restart
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StormReport {
+    pub clients: usize,
+    pub successful_queries: usize,
+    pub failed_queries: usize,
+    /// Until the last client got its answer or gave up
+    pub duration_ms: f64,
+    /// Opening each client's connection: TCP connect, TLS handshake and
+    /// startup/authentication, queued behind the others
+    pub connect_latency: LatencySummary,
+    /// The query alone, once connected, of successful clients
+    pub query_latency: LatencySummary,
+    /// Connecting and querying, of successful clients
+    pub latency: LatencySummary,
+    /// Failed clients by cause
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub failures: BTreeMap<FailureKind, u64>,
+}
+
+/// Open `args.storm` fresh connections and send the run's workload on all of them at
+/// once, on a pool of their own that is closed afterwards
+pub async fn run_storm(
+    args: &Args,
+    clients: usize,
+    plugin: Option<Arc<WasmPlugin>>,
+    ycsb: Option<Arc<YcsbWorkload>>,
+    observed: Option<Arc<ObservedWorkload>>,
+) -> anyhow::Result<StormReport> {
+    let pool = create_connection_pool(
+        &args.database_url,
+        clients,
+        args.address_preference(),
+        &args.tls_options(),
+        args.credentials.as_ref(),
+        args.query_timeout(),
+        &PoolOptions {
+            min_idle: 0,
+            ..args.pool_options()
+        },
+    )
+    .await?;
+    let backend = Backend::Postgres(pool.clone());
+    let mut ctx = QueryContext::new(args, plugin, ycsb, None, None, None, None).await?;
+    ctx.observed = observed;
+    let ctx = Arc::new(ctx);
+
+    // Every client waits at the barrier, so none gets a head start while the rest
+    // are still being spawned
+    let barrier = Arc::new(Barrier::new(clients));
+    let tasks: Vec<_> = (0..clients as u64)
+        .map(|client| {
+            let backend = backend.clone();
+            let ctx = Arc::clone(&ctx);
+            let barrier = Arc::clone(&barrier);
+            tokio::spawn(async move {
+                barrier.wait().await;
+                let seed = STORM_SEED_OFFSET + client;
+                execute_query_from(&backend, &ctx, &ctx.query_type, seed, Instant::now()).await
+            })
+        })
+        .collect();
+    let start = Instant::now();
+    let mut metrics = Vec::with_capacity(clients);
+    for task in tasks {
+        metrics.extend(task.await?);
+    }
+    let duration = start.elapsed();
+    pool.close();
+
+    let mut failures = BTreeMap::new();
+    let (mut connect_ms, mut query_ms, mut latency_ms) = (Vec::new(), Vec::new(), Vec::new());
+    for metric in &metrics {
+        connect_ms.push(metric.connection_time.as_secs_f64() * 1000.0);
+        if metric.success {
+            query_ms.push(metric.query_execution_time.as_secs_f64() * 1000.0);
+            latency_ms.push(metric.latency.as_secs_f64() * 1000.0);
+        } else if let Some(kind) = metric.failure_kind {
+            *failures.entry(kind).or_insert(0) += 1;
+        }
+    }
+    let successful_queries = latency_ms.len();
+    let report = StormReport {
+        clients,
+        successful_queries,
+        failed_queries: metrics.len() - successful_queries,
+        duration_ms: duration.as_secs_f64() * 1000.0,
+        connect_latency: summarize_latencies(connect_ms),
+        query_latency: summarize_latencies(query_ms),
+        latency: summarize_latencies(latency_ms),
+        failures,
+    };
+    if !args.disable_logging {
+        info!(
+            "🌩️  Storm: {} clients connected and queried at once in {:.0}ms, {} failed (connect p99 {:.2}ms)",
+            report.clients,
+            report.duration_ms,
+            report.failed_queries,
+            report.connect_latency.p99_ms
+        );
+    }
+
+    // Let the server and any pooler settle before the warmup
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    Ok(report)
+}
+
+pub fn display_storm(report: &StormReport) {
+    println!("🌩️  Connection Storm (before the warmup):");
+    println!(
+        "   Clients:                {:>7} in {:.0}ms",
+        report.clients, report.duration_ms
+    );
+    println!(
+        "   Successful:             {:>7} ({:.2}%)",
+        report.successful_queries,
+        report.successful_queries as f64 / report.clients.max(1) as f64 * 100.0
+    );
+    println!(
+        "   Connect + Handshake (ms): avg {:.2}  p50 {:.2}  p99 {:.2}  max {:.2}",
+        report.connect_latency.average_ms,
+        report.connect_latency.p50_ms,
+        report.connect_latency.p99_ms,
+        report.connect_latency.max_ms
+    );
+    if report.latency.count > 0 {
+        println!(
+            "   First Query (ms):       avg {:.2}  p50 {:.2}  p99 {:.2}  max {:.2}",
+            report.query_latency.average_ms,
+            report.query_latency.p50_ms,
+            report.query_latency.p99_ms,
+            report.query_latency.max_ms
+        );
+        println!(
+            "   Connect + Query (ms):   avg {:.2}  p50 {:.2}  p99 {:.2}  max {:.2}",
+            report.latency.average_ms,
+            report.latency.p50_ms,
+            report.latency.p99_ms,
+            report.latency.max_ms
+        );
+    }
+    for (kind, count) in &report.failures {
+        println!("   ⚠️  {:<21}{:>7}", kind.name(), count);
+    }
+    if report
+        .failures
+        .contains_key(&FailureKind::TooManyConnections)
+    {
+        println!("   💡 The server ran out of connection slots: raise max_connections or put a pooler in front");
+    }
+    println!();
+}
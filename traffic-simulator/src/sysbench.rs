@@ -0,0 +1,199 @@
+use crate::prepared::Statements;
+use crate::writes::{WriteCounts, WriteKind};
+use deadpool_postgres::Pool;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio_postgres::Row;
+use tracing::info;
+
+/// Statement counts per transaction, as in sysbench's oltp_read_write defaults
+const POINT_SELECTS: usize = 10;
+const RANGE_SIZE: i32 = 100;
+
+/// Create and load the `sbtest` tables (the equivalent of
+/// `sysbench oltp_read_write --tables=N --table-size=M prepare`)
+pub async fn prepare(
+    pool: &Pool,
+    tables: u32,
+    table_size: u32,
+    disable_logging: bool,
+) -> anyhow::Result<()> {
+    let client = pool.get().await?;
+
+    if !disable_logging {
+        info!(
+            "🧮 Preparing {} sysbench table(s) with {} rows each...",
+            tables, table_size
+        );
+    }
+
+    for table in 1..=tables {
+        client
+            .batch_execute(&format!(
+                "DROP TABLE IF EXISTS sbtest{table};
+                 CREATE TABLE sbtest{table} (
+                     id serial PRIMARY KEY,
+                     k integer NOT NULL DEFAULT 0,
+                     c char(120) NOT NULL DEFAULT '',
+                     pad char(60) NOT NULL DEFAULT ''
+                 );"
+            ))
+            .await?;
+        client
+            .execute(
+                &format!(
+                    "INSERT INTO sbtest{table} (k, c, pad)
+                     SELECT floor(random() * $1::bigint)::int + 1,
+                            substr(repeat(md5(id::text), 4), 1, 119),
+                            substr(repeat(md5((id * 7)::text), 2), 1, 59)
+                     FROM generate_series(1, $1::bigint) AS id"
+                ),
+                &[&(table_size as i64)],
+            )
+            .await?;
+        client
+            .batch_execute(&format!("CREATE INDEX k_{table} ON sbtest{table} (k)"))
+            .await?;
+        client
+            .batch_execute(&format!("VACUUM ANALYZE sbtest{table}"))
+            .await?;
+    }
+
+    if !disable_logging {
+        info!("✅ sysbench tables prepared");
+    }
+
+    Ok(())
+}
+
+/// Check that every `sbtest` table exists and holds `table_size` rows
+pub async fn verify(pool: &Pool, tables: u32, table_size: u32) -> anyhow::Result<()> {
+    let client = pool.get().await?;
+
+    for table in 1..=tables {
+        let rows: i64 = client
+            .query_one(&format!("SELECT count(*) FROM sbtest{table}"), &[])
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "sysbench table sbtest{} not found ({}); rerun with --sysbench-prepare",
+                    table,
+                    e
+                )
+            })?
+            .get(0);
+
+        if rows != table_size as i64 {
+            return Err(anyhow::anyhow!(
+                "sysbench table sbtest{} has {} rows, not {}; rerun with --sysbench-prepare",
+                table,
+                rows,
+                table_size
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// One oltp_read_write transaction: 10 point selects, the four range queries,
+/// an index update, a non-index update and a delete/insert of the same row
+pub async fn execute_transaction(
+    client: &deadpool_postgres::Client,
+    statements: &Statements,
+    tables: u32,
+    table_size: u32,
+    seed: u64,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<Vec<Row>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let table = format!("sbtest{}", rng.gen_range(1..=tables));
+    let table_size = table_size as i32;
+    let mut random_id = || rng.gen_range(1..=table_size);
+
+    let point_ids: Vec<i32> = (0..POINT_SELECTS).map(|_| random_id()).collect();
+    let range_starts: Vec<i32> = (0..4)
+        .map(|_| random_id().min(table_size - RANGE_SIZE + 1).max(1))
+        .collect();
+    let index_update_id = random_id();
+    let non_index_update_id = random_id();
+    let delete_id = random_id();
+    let c = format!("{:0>119}", seed);
+    let pad = format!("{:0>59}", seed % 1_000_000);
+
+    client.batch_execute("BEGIN").await?;
+    let result = async {
+        let mut rows = Vec::new();
+        for id in &point_ids {
+            rows.extend(
+                statements
+                    .query(
+                        client,
+                        &format!("SELECT c FROM {table} WHERE id = $1"),
+                        &[id],
+                    )
+                    .await?,
+            );
+        }
+
+        let range_queries = [
+            format!("SELECT c FROM {table} WHERE id BETWEEN $1 AND $2"),
+            format!("SELECT SUM(k) FROM {table} WHERE id BETWEEN $1 AND $2"),
+            format!("SELECT c FROM {table} WHERE id BETWEEN $1 AND $2 ORDER BY c"),
+            format!("SELECT DISTINCT c FROM {table} WHERE id BETWEEN $1 AND $2 ORDER BY c"),
+        ];
+        for (sql, start) in range_queries.iter().zip(&range_starts) {
+            let end = start + RANGE_SIZE - 1;
+            statements
+                .query(client, sql.as_str(), &[start, &end])
+                .await?;
+        }
+
+        let updated = statements
+            .execute(
+                client,
+                &format!("UPDATE {table} SET k = k + 1 WHERE id = $1"),
+                &[&index_update_id],
+            )
+            .await?;
+        writes.record(WriteKind::Update, updated);
+        let updated = statements
+            .execute(
+                client,
+                &format!("UPDATE {table} SET c = $1 WHERE id = $2"),
+                &[&c, &non_index_update_id],
+            )
+            .await?;
+        writes.record(WriteKind::Update, updated);
+        let deleted = statements
+            .execute(
+                client,
+                &format!("DELETE FROM {table} WHERE id = $1"),
+                &[&delete_id],
+            )
+            .await?;
+        writes.record(WriteKind::Delete, deleted);
+        let inserted = statements
+            .execute(
+                client,
+                &format!("INSERT INTO {table} (id, k, c, pad) VALUES ($1, $2, $3, $4)"),
+                &[&delete_id, &random_id(), &c, &pad],
+            )
+            .await?;
+        writes.record(WriteKind::Insert, inserted);
+
+        Ok::<_, tokio_postgres::Error>(rows)
+    }
+    .await;
+
+    match result {
+        Ok(rows) => {
+            client.batch_execute("COMMIT").await?;
+            Ok(rows)
+        }
+        Err(e) => {
+            let _ = client.batch_execute("ROLLBACK").await;
+            Err(e.into())
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use crate::query_types::{QueryTypeStats, QueryTypeTally};
+use crate::sampling;
+use crate::stores::{StoreWeights, STORE_COUNT};
+use rand::distributions::{Distribution, WeightedIndex};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// "tenant", see [`crate::sampling`]
+const TENANT_SEED_SALT: u64 = 0x7465_6e61_6e74_0000;
+
+/// Parse a tenant count between 1 and the number of stores, which are split
+/// between the tenants
+pub fn parse_tenant_count(value: &str) -> Result<u32, String> {
+    let count: u32 = value
+        .parse()
+        .map_err(|_| format!("invalid tenant count '{}'", value))?;
+    if count == 0 || count > STORE_COUNT as u32 {
+        return Err(format!(
+            "tenant count must be between 1 and {} (one store each at least), got {}",
+            STORE_COUNT, count
+        ));
+    }
+    Ok(count)
+}
+
+/// Relative traffic weight of one tenant
+#[derive(Debug, Clone, Copy)]
+pub struct TenantWeight {
+    tenant: u32,
+    weight: f64,
+}
+
+/// Parse `TENANT=WEIGHT`, e.g. `1=5` for a tenant sending five times the traffic
+pub fn parse_tenant_weight(value: &str) -> Result<TenantWeight, String> {
+    let (tenant, weight) = value
+        .split_once('=')
+        .ok_or_else(|| format!("invalid tenant weight '{}', expected TENANT=WEIGHT", value))?;
+    let tenant: u32 = tenant
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid tenant in '{}'", value))?;
+    let weight: f64 = weight
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid weight in '{}'", value))?;
+    if tenant == 0 || !weight.is_finite() || weight < 0.0 {
+        return Err(format!(
+            "tenants start at 1 and weights must be zero or positive in '{}'",
+            value
+        ));
+    }
+    Ok(TenantWeight { tenant, weight })
+}
+
+/// `--tenants`: every request belongs to one tenant, which owns a share of the
+/// stores and prefixes the order numbers it inserts
+#[derive(Debug)]
+pub struct Tenants {
+    weights: Vec<f64>,
+    index: WeightedIndex<f64>,
+    stores: Vec<Arc<StoreWeights>>,
+}
+
+impl Tenants {
+    pub fn new(count: u32, weights: &[TenantWeight]) -> anyhow::Result<Self> {
+        let mut tenant_weights = vec![1.0; count as usize];
+        for TenantWeight { tenant, weight } in weights {
+            if *tenant > count {
+                return Err(anyhow::anyhow!(
+                    "--tenant-weight names tenant {}, but there are only {} tenants",
+                    tenant,
+                    count
+                ));
+            }
+            tenant_weights[(*tenant - 1) as usize] = *weight;
+        }
+        let index = WeightedIndex::new(&tenant_weights)
+            .map_err(|e| anyhow::anyhow!("Invalid tenant weights: {}", e))?;
+        let stores = (1..=count)
+            .map(|tenant| StoreWeights::for_tenant(tenant, count).map(Arc::new))
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            weights: tenant_weights,
+            index,
+            stores,
+        })
+    }
+
+    /// The tenant (1-based) the request with `seed` belongs to
+    pub fn tenant(&self, seed: u64) -> u32 {
+        let mut rng = sampling::request_rng(seed, TENANT_SEED_SALT);
+        self.index.sample(&mut rng) as u32 + 1
+    }
+
+    pub fn stores(&self, tenant: u32) -> &Arc<StoreWeights> {
+        &self.stores[(tenant - 1) as usize]
+    }
+
+    /// Each tenant's share of traffic, e.g. `1: 62.5%, 2: 12.5%, ...`
+    pub fn describe(&self) -> String {
+        let total: f64 = self.weights.iter().sum();
+        self.weights
+            .iter()
+            .enumerate()
+            .map(|(i, weight)| format!("{}: {:.1}%", i + 1, weight / total * 100.0))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Outcome of one tenant's requests
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TenantStats {
+    #[serde(flatten)]
+    stats: QueryTypeStats,
+    queries_per_second: f64,
+    /// Share of all requests
+    share_percent: f64,
+}
+
+/// Per-tenant breakdown; `None` unless --tenants was given
+pub fn report(
+    tallies: &BTreeMap<u32, QueryTypeTally>,
+    duration_seconds: f64,
+) -> Option<BTreeMap<u32, TenantStats>> {
+    if tallies.is_empty() {
+        return None;
+    }
+
+    let total: usize = tallies.values().map(|tally| tally.stats().queries).sum();
+    let stats = tallies
+        .iter()
+        .map(|(tenant, tally)| {
+            let stats = tally.stats();
+            let tenant_stats = TenantStats {
+                queries_per_second: (stats.queries - stats.failed_queries) as f64
+                    / duration_seconds,
+                share_percent: stats.queries as f64 / total.max(1) as f64 * 100.0,
+                stats,
+            };
+            (*tenant, tenant_stats)
+        })
+        .collect();
+    Some(stats)
+}
+
+pub fn display_tenants(tenants: &BTreeMap<u32, TenantStats>) {
+    println!("\n🏢 Tenants (latency in ms):");
+    println!("   Tenant    Share       QPS    Errors       p50       p95       p99");
+    for (tenant, stats) in tenants {
+        println!(
+            "   {:<6}{:>8.1}%{:>10.1}{:>9.2}%{:>10.2}{:>10.2}{:>10.2}",
+            tenant,
+            stats.share_percent,
+            stats.queries_per_second,
+            stats.stats.error_rate_percent,
+            stats.stats.latency.p50_ms,
+            stats.stats.latency.p95_ms,
+            stats.stats.latency.p99_ms
+        );
+    }
+
+    // How much worse the worst-served tenant fares than the best-served one
+    let p99s = tenants
+        .values()
+        .filter(|stats| stats.stats.latency.count > 0)
+        .map(|stats| stats.stats.latency.p99_ms);
+    let (fastest, slowest) = p99s.fold((f64::MAX, 0.0f64), |(min, max), p99| {
+        (min.min(p99), max.max(p99))
+    });
+    if tenants.len() > 1 && slowest > 0.0 && fastest > 0.0 {
+        println!(
+            "   ⚖️  Slowest tenant p99 is {:.2}x the fastest",
+            slowest / fastest
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use crate::sampling;
+use rand::Rng;
+use rand_distr::{Distribution, Exp};
+use std::time::Duration;
+
+/// "think", see [`crate::sampling`]
+const THINK_SEED_SALT: u64 = 0x7468_696e_6b00_0000;
+
+/// How think times spread around their mean
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ThinkTimeDistribution {
+    /// Exactly the mean after every query
+    Fixed,
+    /// Uniform between zero and twice the mean
+    Uniform,
+    /// Exponentially distributed, like the gaps between a person's independent actions
+    Exponential,
+}
+
+/// Pause a worker takes after each of its queries before issuing the next, so a
+/// worker paces like an application session rather than firing back to back
+#[derive(Debug, Clone, Copy)]
+pub struct ThinkTime {
+    mean: Duration,
+    distribution: ThinkTimeDistribution,
+}
+
+impl ThinkTime {
+    pub fn new(mean_ms: u64, distribution: ThinkTimeDistribution) -> Self {
+        Self {
+            mean: Duration::from_millis(mean_ms),
+            distribution,
+        }
+    }
+
+    /// Think time after the query with `seed`
+    pub fn sample(&self, seed: u64) -> Duration {
+        let mut rng = sampling::request_rng(seed, THINK_SEED_SALT);
+        let mean = self.mean.as_secs_f64();
+        match self.distribution {
+            ThinkTimeDistribution::Fixed => self.mean,
+            ThinkTimeDistribution::Uniform => {
+                Duration::from_secs_f64(rng.gen_range(0.0..=2.0 * mean))
+            }
+            ThinkTimeDistribution::Exponential => Exp::new(1.0 / mean)
+                .map(|exp| Duration::from_secs_f64(exp.sample(&mut rng)))
+                .unwrap_or(self.mean),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{:?} {:?} mean", self.distribution, self.mean).to_lowercase()
+    }
+}
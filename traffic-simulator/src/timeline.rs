@@ -0,0 +1,241 @@
+use crate::export::CsvLabels;
+use crate::recorder::LatencyHistogram;
+use crate::server_stats::ServerSample;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Per-second throughput and latency over the measured run, bucketed by the
+/// second each query completed in
+#[derive(Debug)]
+pub struct Timeline {
+    start: Instant,
+    seconds: Mutex<Seconds>,
+    phases: Mutex<Vec<PhaseMark>>,
+}
+
+/// A phase of the run starting, e.g. the next real-world simulation intensity
+#[derive(Debug, Clone)]
+pub struct PhaseMark {
+    /// Time into the run the phase started at
+    pub second: f64,
+    pub name: String,
+}
+
+/// Only the second in progress keeps a histogram; finished seconds are reduced
+/// to their percentiles, so memory grows by a few numbers per second of the run
+#[derive(Debug, Default)]
+struct Seconds {
+    current: Option<(u64, SecondStats)>,
+    finished: BTreeMap<u64, SecondSummary>,
+}
+
+#[derive(Debug, Default)]
+struct SecondStats {
+    latency: LatencyHistogram,
+    failures: u64,
+}
+
+#[derive(Debug, Default)]
+struct SecondSummary {
+    queries: u64,
+    failures: u64,
+    p50_ms: f64,
+    p95_ms: f64,
+    p99_ms: f64,
+}
+
+impl Seconds {
+    /// Close the second in progress, if any
+    fn finish_current(&mut self) {
+        if let Some((second, stats)) = self.current.take() {
+            let latency = stats.latency.summary();
+            self.finished.insert(
+                second,
+                SecondSummary {
+                    queries: latency.count as u64 + stats.failures,
+                    failures: stats.failures,
+                    p50_ms: latency.p50_ms,
+                    p95_ms: latency.p95_ms,
+                    p99_ms: latency.p99_ms,
+                },
+            );
+        }
+    }
+}
+
+/// One second of the timeline. Latency percentiles cover successful queries.
+#[derive(Debug, Serialize)]
+pub struct TimelinePoint {
+    pub second: u64,
+    pub queries: u64,
+    pub failures: u64,
+    pub qps: f64,
+    pub error_rate: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    /// Connections checked out and requests waiting for one, sampled during the second
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool_in_use: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool_waiting: Option<usize>,
+    /// --server-stats sample taken during the second, if any
+    #[serde(flatten)]
+    pub server: Option<ServerSample>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            seconds: Mutex::new(Seconds::default()),
+            phases: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn mark_phase(&self, name: String) {
+        self.phases.lock().unwrap().push(PhaseMark {
+            second: self.start.elapsed().as_secs_f64(),
+            name,
+        });
+    }
+
+    pub fn phases(&self) -> Vec<PhaseMark> {
+        self.phases.lock().unwrap().clone()
+    }
+
+    pub fn record(&self, latency: Duration, success: bool) {
+        let mut seconds = self.seconds.lock().unwrap();
+        // Read under the lock, so seconds only ever move forward
+        let second = self.start.elapsed().as_secs();
+        if seconds
+            .current
+            .as_ref()
+            .is_some_and(|(current, _)| *current != second)
+        {
+            seconds.finish_current();
+        }
+        let (_, stats) = seconds
+            .current
+            .get_or_insert_with(|| (second, SecondStats::default()));
+        if success {
+            stats.latency.record(latency);
+        } else {
+            stats.failures += 1;
+        }
+    }
+
+    /// Every second from the start of the run to now, including idle ones
+    pub fn points(&self) -> Vec<TimelinePoint> {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let mut seconds = std::mem::take(&mut *self.seconds.lock().unwrap());
+        seconds.finish_current();
+        let mut seconds = seconds.finished;
+        let last = seconds.keys().next_back().copied().unwrap_or(0);
+
+        (0..=last)
+            .map(|second| {
+                let summary = seconds.remove(&second).unwrap_or_default();
+                let queries = summary.queries;
+                // The final second is usually cut short by the end of the run
+                let width = (elapsed - second as f64).clamp(f64::EPSILON, 1.0);
+                TimelinePoint {
+                    second,
+                    queries,
+                    failures: summary.failures,
+                    qps: queries as f64 / width,
+                    error_rate: if queries == 0 {
+                        0.0
+                    } else {
+                        summary.failures as f64 / queries as f64
+                    },
+                    p50_ms: summary.p50_ms,
+                    p95_ms: summary.p95_ms,
+                    p99_ms: summary.p99_ms,
+                    pool_in_use: None,
+                    pool_waiting: None,
+                    server: None,
+                }
+            })
+            .collect()
+    }
+}
+
+const SERVER_COLUMNS: &str = ",server_active,server_idle_in_transaction,server_lock_waits,\
+server_commits_per_second,server_rollbacks_per_second,server_cache_hit_ratio,server_temp_files,\
+server_temp_bytes,server_deadlocks";
+
+/// Write the timeline as JSON when `path` ends in `.json`, otherwise as CSV with
+/// the run's label columns
+pub fn write_timeline(
+    path: &str,
+    points: &[TimelinePoint],
+    labels: &CsvLabels,
+) -> anyhow::Result<()> {
+    let contents = if path.to_ascii_lowercase().ends_with(".json") {
+        serde_json::to_string_pretty(points)?
+    } else {
+        // Pool columns only when the pool was sampled (not for SQLite), server
+        // columns only with --server-stats
+        let pool = points.iter().any(|point| point.pool_in_use.is_some());
+        let server = points.iter().any(|point| point.server.is_some());
+        let mut csv = String::from("second,queries,failures,qps,error_rate,p50_ms,p95_ms,p99_ms");
+        if pool {
+            csv.push_str(",pool_in_use,pool_waiting");
+        }
+        if server {
+            csv.push_str(SERVER_COLUMNS);
+        }
+        csv.push_str(labels.header());
+        csv.push('\n');
+        for point in points {
+            csv.push_str(&format!(
+                "{},{},{},{:.3},{:.4},{:.3},{:.3},{:.3}",
+                point.second,
+                point.queries,
+                point.failures,
+                point.qps,
+                point.error_rate,
+                point.p50_ms,
+                point.p95_ms,
+                point.p99_ms
+            ));
+            if pool {
+                let column =
+                    |value: Option<usize>| value.map(|v| v.to_string()).unwrap_or_default();
+                csv.push_str(&format!(
+                    ",{},{}",
+                    column(point.pool_in_use),
+                    column(point.pool_waiting)
+                ));
+            }
+            if server {
+                match &point.server {
+                    Some(sample) => csv.push_str(&format!(
+                        ",{},{},{},{:.3},{:.3},{},{},{},{}",
+                        sample.server_active,
+                        sample.server_idle_in_transaction,
+                        sample.server_lock_waits,
+                        sample.server_commits_per_second,
+                        sample.server_rollbacks_per_second,
+                        sample
+                            .server_cache_hit_ratio
+                            .map(|ratio| format!("{:.4}", ratio))
+                            .unwrap_or_default(),
+                        sample.server_temp_files,
+                        sample.server_temp_bytes,
+                        sample.server_deadlocks
+                    )),
+                    None => csv.push_str(&",".repeat(9)),
+                }
+            }
+            csv.push_str(labels.values());
+            csv.push('\n');
+        }
+        csv
+    };
+    std::fs::write(path, contents)
+        .map_err(|e| anyhow::anyhow!("Failed to write timeline to '{}': {}", path, e))
+}
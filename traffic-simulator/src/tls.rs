@@ -0,0 +1,84 @@
+use anyhow::Context;
+use native_tls::{Certificate, Identity, TlsConnector};
+use postgres_native_tls::MakeTlsConnector;
+use tokio_postgres::config::SslMode;
+use tokio_postgres::Config;
+
+/// How a connection uses TLS, with libpq's sslmode semantics
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum TlsMode {
+    /// Plain TCP
+    Disable,
+    /// Encrypt without checking the server's certificate, unless --tls-root-cert is
+    /// given, in which case the chain is checked as with verify-ca
+    Require,
+    /// Encrypt and check that the server's certificate chains to a trusted root
+    VerifyCa,
+    /// verify-ca, and check that the certificate is issued to the host connected to
+    VerifyFull,
+}
+
+/// TLS settings shared by every connection the simulator opens
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+    /// `None` keeps the connection string's sslmode, with full verification when
+    /// TLS is used
+    pub mode: Option<TlsMode>,
+    /// PEM file of a CA to trust in addition to the system's roots
+    pub root_cert: Option<String>,
+    /// PEM certificate and PKCS#8 key presented to the server (mutual TLS)
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+}
+
+impl TlsOptions {
+    /// Apply the mode to a connection's settings
+    pub fn configure(&self, pg_config: &mut Config) {
+        match self.mode {
+            Some(TlsMode::Disable) => {
+                pg_config.ssl_mode(SslMode::Disable);
+            }
+            Some(_) => {
+                pg_config.ssl_mode(SslMode::Require);
+            }
+            None => {}
+        }
+    }
+
+    /// Connector checking server certificates as the mode asks and presenting the
+    /// client certificate, if any
+    pub fn connector(&self) -> anyhow::Result<MakeTlsConnector> {
+        let mut builder = TlsConnector::builder();
+        if let Some(path) = &self.root_cert {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("Failed to read --tls-root-cert {}", path))?;
+            let certificate = Certificate::from_pem(&pem)
+                .with_context(|| format!("Invalid certificate in --tls-root-cert {}", path))?;
+            builder.add_root_certificate(certificate);
+        }
+        if let (Some(cert_path), Some(key_path)) = (&self.client_cert, &self.client_key) {
+            let cert = std::fs::read(cert_path)
+                .with_context(|| format!("Failed to read --tls-client-cert {}", cert_path))?;
+            let key = std::fs::read(key_path)
+                .with_context(|| format!("Failed to read --tls-client-key {}", key_path))?;
+            let identity = Identity::from_pkcs8(&cert, &key).with_context(|| {
+                format!(
+                    "Invalid client certificate or key ({}, {}); the key must be PKCS#8 PEM",
+                    cert_path, key_path
+                )
+            })?;
+            builder.identity(identity);
+        }
+
+        let (check_chain, check_hostname) = match self.mode {
+            Some(TlsMode::Require) => (self.root_cert.is_some(), false),
+            Some(TlsMode::VerifyCa) => (true, false),
+            Some(TlsMode::VerifyFull) | Some(TlsMode::Disable) | None => (true, true),
+        };
+        builder
+            .danger_accept_invalid_certs(!check_chain)
+            .danger_accept_invalid_hostnames(!check_hostname);
+
+        Ok(MakeTlsConnector::new(builder.build()?))
+    }
+}
@@ -0,0 +1,196 @@
+use crate::failures::{self, FailureKind};
+use crate::recorder::LatencyHistogram;
+use crate::writes::{WriteCounts, WriteKind};
+use crate::FailureStage;
+use crate::{LatencySummary, QueryContext};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio_postgres::Row;
+
+/// Commit, rollback and deadlock counts of `--query-type transaction` requests
+#[derive(Debug)]
+pub struct TransactionTracker {
+    /// DML statements between BEGIN and COMMIT
+    pub statements: u32,
+    stats: Mutex<TransactionStats>,
+}
+
+/// Mergeable transaction outcomes, handed from shards to the parent process
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TransactionStats {
+    statements: u32,
+    commits: u64,
+    rollbacks: u64,
+    deadlocks: u64,
+    /// Time spent in COMMIT alone, in milliseconds
+    commit_latency: LatencyHistogram,
+}
+
+/// Outcome of multi-statement transactions, separate from the per-request metrics
+/// (a request's latency covers its whole transaction)
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TransactionReport {
+    statements_per_transaction: u32,
+    /// Transactions that committed or rolled back, including retried attempts
+    transactions: u64,
+    commits: u64,
+    /// Transactions rolled back because a statement or the COMMIT failed
+    rollbacks: u64,
+    rollback_rate_percent: f64,
+    /// Rollbacks caused by the server breaking a deadlock (SQLSTATE 40P01)
+    deadlocks: u64,
+    commit_latency: LatencySummary,
+}
+
+impl TransactionTracker {
+    pub fn new(statements: u32) -> Self {
+        Self {
+            statements,
+            stats: Mutex::new(TransactionStats {
+                statements,
+                ..TransactionStats::default()
+            }),
+        }
+    }
+
+    pub fn record_commit(&self, commit_time: Duration) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.commits += 1;
+        stats.commit_latency.record(commit_time);
+    }
+
+    pub fn record_rollback(&self, error: &anyhow::Error) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.rollbacks += 1;
+        if failures::classify(FailureStage::Query, error) == FailureKind::Deadlock {
+            stats.deadlocks += 1;
+        }
+    }
+
+    pub fn stats(&self) -> TransactionStats {
+        self.stats.lock().unwrap().clone()
+    }
+}
+
+impl TransactionStats {
+    pub fn merge(&mut self, other: &TransactionStats) {
+        self.statements = self.statements.max(other.statements);
+        self.commits += other.commits;
+        self.rollbacks += other.rollbacks;
+        self.deadlocks += other.deadlocks;
+        self.commit_latency.merge(&other.commit_latency);
+    }
+
+    /// `None` when the run executed no transactions
+    pub fn report(&self) -> Option<TransactionReport> {
+        let transactions = self.commits + self.rollbacks;
+        if transactions == 0 {
+            return None;
+        }
+
+        Some(TransactionReport {
+            statements_per_transaction: self.statements,
+            transactions,
+            commits: self.commits,
+            rollbacks: self.rollbacks,
+            rollback_rate_percent: self.rollbacks as f64 / transactions as f64 * 100.0,
+            deadlocks: self.deadlocks,
+            commit_latency: self.commit_latency.summary(),
+        })
+    }
+}
+
+/// Seed and kind of each DML statement of the transaction for `seed`: a mix of
+/// new orders and workflow updates
+pub fn statement_plan(seed: u64, statements: u32) -> Vec<(u64, WriteKind)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..statements)
+        .map(|_| {
+            let kind = if rng.gen_bool(0.5) {
+                WriteKind::Insert
+            } else {
+                WriteKind::Update
+            };
+            (rng.gen(), kind)
+        })
+        .collect()
+}
+
+/// Run one transaction: BEGIN, the planned statements, COMMIT. A failing statement
+/// rolls the transaction back and fails the request with its error.
+pub async fn execute_transaction(
+    client: &deadpool_postgres::Client,
+    ctx: &QueryContext,
+    seed: u64,
+    attempt: u32,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<Vec<Row>> {
+    let tracker = &ctx.transactions;
+    client.batch_execute("BEGIN").await?;
+
+    let mut rows = Vec::new();
+    for (statement_seed, kind) in statement_plan(seed, tracker.statements) {
+        let result = match kind {
+            WriteKind::Insert => {
+                crate::execute_operational_insert_query(
+                    client,
+                    ctx,
+                    statement_seed,
+                    attempt,
+                    ctx.stores_for(seed).map(|stores| stores.as_ref()),
+                    writes,
+                )
+                .await
+            }
+            _ => crate::execute_operational_update_query(client, ctx, statement_seed, writes).await,
+        };
+        match result {
+            Ok(statement_rows) => rows.extend(statement_rows),
+            Err(e) => {
+                // Keep the statement's error; the rollback only has to leave the
+                // connection usable for the next checkout
+                let _ = client.batch_execute("ROLLBACK").await;
+                tracker.record_rollback(&e);
+                return Err(e);
+            }
+        }
+    }
+
+    let commit_start = Instant::now();
+    if let Err(e) = client.batch_execute("COMMIT").await {
+        // A failed COMMIT leaves the transaction rolled back
+        let e = e.into();
+        tracker.record_rollback(&e);
+        return Err(e);
+    }
+    tracker.record_commit(commit_start.elapsed());
+
+    Ok(rows)
+}
+
+pub fn display_transactions(report: &TransactionReport) {
+    println!(
+        "\n📦 Transactions ({} statements each):",
+        report.statements_per_transaction
+    );
+    println!("   Executed:               {:>8}", report.transactions);
+    println!("   Committed:              {:>8}", report.commits);
+    println!(
+        "   Rolled Back:            {:>8} ({:.2}%)",
+        report.rollbacks, report.rollback_rate_percent
+    );
+    println!("   Deadlocks:              {:>8}", report.deadlocks);
+    println!("   Commit Latency (ms):");
+    println!(
+        "     Average: {:.2}  p50: {:.2}  p95: {:.2}  p99: {:.2}  max: {:.2}",
+        report.commit_latency.average_ms,
+        report.commit_latency.p50_ms,
+        report.commit_latency.p95_ms,
+        report.commit_latency.p99_ms,
+        report.commit_latency.max_ms
+    );
+}
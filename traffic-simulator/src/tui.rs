@@ -0,0 +1,474 @@
+use crate::recorder::LatencyHistogram;
+use crate::{Backend, QueryContext};
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::{cursor, execute, terminal};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Gauge, Paragraph, Sparkline, Wrap};
+use ratatui::{Frame, Terminal};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, IsTerminal, Stdout, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Seconds of throughput history kept for the chart
+const HISTORY_SECONDS: u64 = 120;
+
+/// Window of the rolling latency percentiles and error rate
+const ROLLING_SECONDS: u64 = 10;
+
+/// Most recent log lines shown in the log panel
+const LOG_LINES: usize = 4;
+
+/// Set while the dashboard owns the terminal
+static SHOWING: AtomicBool = AtomicBool::new(false);
+
+/// Log lines written while the dashboard is on screen
+static LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Live view of the measured run for `--tui`: throughput, rolling latency,
+/// errors, pool saturation and the current traffic phase
+#[derive(Debug)]
+pub struct Dashboard {
+    start: Instant,
+    duration: Duration,
+    state: Mutex<DashboardState>,
+}
+
+#[derive(Debug, Default)]
+struct DashboardState {
+    phase: String,
+    notes: Vec<String>,
+    queries: u64,
+    failures: u64,
+    /// Per-second buckets of the last `HISTORY_SECONDS`, keyed by second of the run
+    seconds: BTreeMap<u64, SecondStats>,
+}
+
+#[derive(Debug, Default)]
+struct SecondStats {
+    queries: u64,
+    failures: u64,
+    latency: LatencyHistogram,
+}
+
+/// Progress of the measured run so far; rolling figures cover the last 10 seconds
+#[derive(Debug, Serialize)]
+pub struct LiveMetrics {
+    pub elapsed_seconds: f64,
+    pub phase: String,
+    /// Talking points of the current phase, empty when it has none
+    pub notes: Vec<String>,
+    pub queries: u64,
+    pub failures: u64,
+    /// Queries completed in the last complete second
+    pub queries_per_second: f64,
+    pub rolling_error_rate_percent: f64,
+    pub rolling_p50_ms: f64,
+    pub rolling_p95_ms: f64,
+    pub rolling_p99_ms: f64,
+}
+
+/// Pool connections checked out, the pool's size limit and queued checkouts
+struct PoolUsage {
+    in_use: usize,
+    max_size: usize,
+    waiting: usize,
+}
+
+/// Everything one frame shows, gathered under a single lock
+struct Snapshot {
+    elapsed: Duration,
+    duration: Duration,
+    phase: String,
+    notes: Vec<String>,
+    queries: u64,
+    failures: u64,
+    qps: f64,
+    qps_history: Vec<u64>,
+    rolling_error_rate: f64,
+    p50_ms: f64,
+    p95_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+    pool: Option<PoolUsage>,
+    connections: usize,
+    pending: usize,
+    log: Vec<String>,
+}
+
+impl Dashboard {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            start: Instant::now(),
+            duration,
+            state: Mutex::new(DashboardState::default()),
+        }
+    }
+
+    pub fn record(&self, latency: Duration, success: bool) {
+        let second = self.start.elapsed().as_secs();
+        let mut state = self.state.lock().unwrap();
+        state.queries += 1;
+        let stats = state.seconds.entry(second).or_default();
+        stats.queries += 1;
+        if success {
+            stats.latency.record(latency);
+        } else {
+            stats.failures += 1;
+            state.failures += 1;
+        }
+        if second >= HISTORY_SECONDS {
+            state.seconds = state.seconds.split_off(&(second - HISTORY_SECONDS + 1));
+        }
+    }
+
+    /// Show `phase` as the current traffic phase, with optional talking points
+    pub fn set_phase(&self, phase: impl Into<String>, notes: &[String]) {
+        let mut state = self.state.lock().unwrap();
+        state.phase = phase.into();
+        state.notes = notes.to_vec();
+    }
+
+    /// The headline numbers of the dashboard, for --control-port's status endpoint
+    pub fn live_metrics(&self) -> LiveMetrics {
+        let snapshot = self.snapshot(None, 0, 0);
+        LiveMetrics {
+            elapsed_seconds: snapshot.elapsed.as_secs_f64(),
+            phase: snapshot.phase,
+            notes: snapshot.notes,
+            queries: snapshot.queries,
+            failures: snapshot.failures,
+            queries_per_second: snapshot.qps,
+            rolling_error_rate_percent: snapshot.rolling_error_rate,
+            rolling_p50_ms: snapshot.p50_ms,
+            rolling_p95_ms: snapshot.p95_ms,
+            rolling_p99_ms: snapshot.p99_ms,
+        }
+    }
+
+    fn snapshot(&self, pool: Option<PoolUsage>, connections: usize, pending: usize) -> Snapshot {
+        let elapsed = self.start.elapsed();
+        let now = elapsed.as_secs();
+        let state = self.state.lock().unwrap();
+
+        let first = now.saturating_sub(HISTORY_SECONDS - 1);
+        let qps_history: Vec<u64> = (first..now)
+            .map(|second| state.seconds.get(&second).map_or(0, |s| s.queries))
+            .collect();
+        // The current second is still filling, so show the last complete one
+        let qps = match now {
+            0 => {
+                let current = state.seconds.get(&0).map_or(0, |s| s.queries);
+                current as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+            }
+            _ => qps_history.last().copied().unwrap_or(0) as f64,
+        };
+
+        let mut latency = LatencyHistogram::default();
+        let (mut queries, mut failures) = (0, 0);
+        for stats in state
+            .seconds
+            .range(now.saturating_sub(ROLLING_SECONDS - 1)..)
+            .map(|(_, stats)| stats)
+        {
+            latency.merge(&stats.latency);
+            queries += stats.queries;
+            failures += stats.failures;
+        }
+
+        Snapshot {
+            elapsed,
+            duration: self.duration,
+            phase: state.phase.clone(),
+            notes: state.notes.clone(),
+            queries: state.queries,
+            failures: state.failures,
+            qps,
+            qps_history,
+            rolling_error_rate: if queries == 0 {
+                0.0
+            } else {
+                failures as f64 / queries as f64 * 100.0
+            },
+            p50_ms: latency.quantile_ms(0.5),
+            p95_ms: latency.quantile_ms(0.95),
+            p99_ms: latency.quantile_ms(0.99),
+            max_ms: latency.max_ms(),
+            pool,
+            connections,
+            pending,
+            log: LOG.lock().unwrap().iter().cloned().collect(),
+        }
+    }
+}
+
+/// Log output for the tracing subscriber: stdout, except while the dashboard is on
+/// screen, when lines are kept for its log panel instead of tearing through it
+pub fn log_writer() -> Box<dyn Write> {
+    if SHOWING.load(Ordering::Relaxed) {
+        Box::new(LogCapture)
+    } else {
+        Box::new(io::stdout())
+    }
+}
+
+struct LogCapture;
+
+impl Write for LogCapture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut log = LOG.lock().unwrap();
+        for line in String::from_utf8_lossy(buf).lines() {
+            if log.len() == LOG_LINES {
+                log.pop_front();
+            }
+            log.push_back(line.to_string());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Fail early when stdout cannot host the dashboard (e.g. redirected to a file)
+pub fn check_terminal() -> anyhow::Result<()> {
+    if io::stdout().is_terminal() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "--tui needs stdout to be an interactive terminal"
+        ))
+    }
+}
+
+/// Owns the alternate screen; leaving it on drop restores the terminal whether the
+/// dashboard is aborted at the end of the run or the process is interrupted
+struct Screen {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl Screen {
+    fn enter() -> anyhow::Result<Self> {
+        let mut stdout = io::stdout();
+        execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+        SHOWING.store(true, Ordering::Relaxed);
+        Ok(Self {
+            terminal: Terminal::new(CrosstermBackend::new(stdout))?,
+        })
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        SHOWING.store(false, Ordering::Relaxed);
+        let _ = execute!(
+            self.terminal.backend_mut(),
+            terminal::LeaveAlternateScreen,
+            cursor::Show
+        );
+    }
+}
+
+/// Redraw the dashboard every second until aborted. Ctrl+C restores the terminal
+/// before exiting, as the run would otherwise leave it on the alternate screen.
+pub async fn run_dashboard(ctx: Arc<QueryContext>, backend: Backend, connections: usize) {
+    let Some(dashboard) = &ctx.dashboard else {
+        return;
+    };
+    let mut screen = match Screen::enter() {
+        Ok(screen) => screen,
+        Err(e) => {
+            tracing::warn!("Failed to start the dashboard: {}", e);
+            return;
+        }
+    };
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => {
+                drop(screen);
+                std::process::exit(130);
+            }
+        }
+
+        let pool = match &backend {
+            Backend::Postgres(pool) => {
+                let status = pool.status();
+                Some(PoolUsage {
+                    in_use: status.size.saturating_sub(status.available),
+                    max_size: status.max_size,
+                    waiting: status.waiting,
+                })
+            }
+            Backend::Sqlite(_) | Backend::Mysql(_) | Backend::DatabricksSql(_) => None,
+        };
+        let snapshot = dashboard.snapshot(pool, connections, ctx.pending_work.current());
+        if screen
+            .terminal
+            .draw(|frame| draw(frame, &snapshot))
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, snapshot: &Snapshot) {
+    let [header, progress, stats, chart, notes] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(3),
+        Constraint::Length(7),
+        Constraint::Min(6),
+        Constraint::Length(LOG_LINES as u16 + 2),
+    ])
+    .areas(frame.area());
+
+    let title = Line::styled(
+        format!(
+            " 🚀 PostgreSQL Traffic Simulator — {}",
+            if snapshot.phase.is_empty() {
+                "Starting"
+            } else {
+                &snapshot.phase
+            }
+        ),
+        Style::default().add_modifier(Modifier::BOLD),
+    );
+    frame.render_widget(Paragraph::new(title), header);
+
+    let ratio =
+        (snapshot.elapsed.as_secs_f64() / snapshot.duration.as_secs_f64().max(1.0)).clamp(0.0, 1.0);
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::default().borders(Borders::ALL).title(" Progress "))
+            .gauge_style(Style::default().fg(Color::Cyan))
+            .ratio(ratio)
+            .label(format!(
+                "{}s / {}s",
+                snapshot.elapsed.as_secs(),
+                snapshot.duration.as_secs()
+            )),
+        progress,
+    );
+
+    draw_stats(frame, stats, snapshot);
+
+    let max_qps = snapshot.qps_history.iter().copied().max().unwrap_or(0);
+    let history_width = usize::from(chart.width.saturating_sub(2));
+    let visible = &snapshot.qps_history[snapshot.qps_history.len().saturating_sub(history_width)..];
+    frame.render_widget(
+        Sparkline::default()
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" Queries/second (peak {}) ", max_qps)),
+            )
+            .style(Style::default().fg(Color::Green))
+            .data(visible.iter().copied()),
+        chart,
+    );
+
+    // Demo talking points take the bottom panel while a phase has them
+    let (title, lines) = if snapshot.notes.is_empty() {
+        (" Log ", snapshot.log.clone())
+    } else {
+        (" Talking Points ", snapshot.notes.clone())
+    };
+    frame.render_widget(
+        Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<_>>())
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .wrap(Wrap { trim: true }),
+        notes,
+    );
+}
+
+fn draw_stats(frame: &mut Frame, area: Rect, snapshot: &Snapshot) {
+    let [throughput, latency, errors, pool] =
+        Layout::horizontal([Constraint::Ratio(1, 4); 4]).areas(area);
+    let panel = |title: &'static str, lines: Vec<String>| {
+        Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<_>>())
+            .block(Block::default().borders(Borders::ALL).title(title))
+    };
+
+    frame.render_widget(
+        panel(
+            " Throughput ",
+            vec![
+                format!("QPS:      {:>10.0}", snapshot.qps),
+                format!("Queries:  {:>10}", snapshot.queries),
+                format!("Pending:  {:>10}", snapshot.pending),
+            ],
+        ),
+        throughput,
+    );
+    frame.render_widget(
+        panel(
+            " Latency, last 10s (ms) ",
+            vec![
+                format!("p50: {:>8.2}", snapshot.p50_ms),
+                format!("p95: {:>8.2}", snapshot.p95_ms),
+                format!("p99: {:>8.2}", snapshot.p99_ms),
+                format!("max: {:>8.2}", snapshot.max_ms),
+            ],
+        ),
+        latency,
+    );
+
+    let error_style = if snapshot.rolling_error_rate > 0.0 {
+        Style::default().fg(Color::Red)
+    } else {
+        Style::default()
+    };
+    frame.render_widget(
+        panel(
+            " Errors ",
+            vec![
+                format!("Failed:   {:>8}", snapshot.failures),
+                format!("Last 10s: {:>7.2}%", snapshot.rolling_error_rate),
+            ],
+        )
+        .style(error_style),
+        errors,
+    );
+
+    match &snapshot.pool {
+        Some(usage) => {
+            let [gauge, detail] =
+                Layout::vertical([Constraint::Length(3), Constraint::Min(1)]).areas(pool);
+            let saturation = usage.in_use as f64 / usage.max_size.max(1) as f64;
+            let color = if usage.waiting > 0 {
+                Color::Red
+            } else if saturation >= 0.8 {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            frame.render_widget(
+                Gauge::default()
+                    .block(Block::default().borders(Borders::ALL).title(" Pool "))
+                    .gauge_style(Style::default().fg(color))
+                    .ratio(saturation.clamp(0.0, 1.0))
+                    .label(format!("{} / {} in use", usage.in_use, usage.max_size)),
+                gauge,
+            );
+            frame.render_widget(
+                Paragraph::new(format!(" Waiting for a connection: {}", usage.waiting)),
+                detail,
+            );
+        }
+        None => frame.render_widget(
+            panel(
+                " Pool ",
+                vec![format!("{} SQLite handles", snapshot.connections)],
+            ),
+            pool,
+        ),
+    }
+}
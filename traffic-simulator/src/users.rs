@@ -0,0 +1,326 @@
+use crate::recorder::{LatencyHistogram, MetricsRecorder};
+use crate::{
+    calculate_operational_result, execute_query_with_timing, Args, Backend, LatencySummary,
+    QueryContext, QueryType, SimulationResult,
+};
+use futures::future::join_all;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Exp};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Markov chain of user actions: each state runs one query, waits an exponentially
+/// distributed think time, then moves to a next state by probability. Probability
+/// mass not assigned in `next` ends the session; the user then starts a new one.
+#[derive(Debug, Deserialize)]
+pub struct UserModel {
+    pub start: String,
+    pub states: BTreeMap<String, UserState>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserState {
+    pub query: QueryType,
+    /// Mean think time after the action completes
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub dwell: Duration,
+    #[serde(default)]
+    pub next: BTreeMap<String, f64>,
+}
+
+fn deserialize_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    crate::parse_duration(&value).map_err(serde::de::Error::custom)
+}
+
+/// Per-action latency and session counts for a virtual-user run
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UserJourneyReport {
+    pub virtual_users: usize,
+    /// Sessions started, including ones cut off by the end of the run
+    pub sessions: usize,
+    /// Sessions that reached their end state
+    pub completed_sessions: usize,
+    /// Sessions each user was limited to (--user-iterations)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iterations_per_user: Option<u32>,
+    /// Users that completed all their iterations before the run ended
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_users: Option<usize>,
+    /// Time from the first action of a completed session to the end of its last,
+    /// think times between actions included; the dwell after the last action is not
+    pub session_duration: LatencySummary,
+    pub actions: BTreeMap<String, LatencySummary>,
+}
+
+impl UserModel {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let model: UserModel = serde_yaml::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Invalid user model '{}': {}", path, e))?;
+        model.validate()?;
+        Ok(model)
+    }
+
+    /// Browse → search → order → approve storefront journey
+    pub fn builtin() -> Self {
+        let state = |query, dwell_ms, next: &[(&str, f64)]| UserState {
+            query,
+            dwell: Duration::from_millis(dwell_ms),
+            next: next.iter().map(|(k, p)| (k.to_string(), *p)).collect(),
+        };
+
+        let mut states = BTreeMap::new();
+        states.insert(
+            "browse".to_string(),
+            state(
+                QueryType::Select,
+                2000,
+                &[("browse", 0.4), ("search", 0.4), ("order", 0.15)],
+            ),
+        );
+        states.insert(
+            "search".to_string(),
+            state(
+                QueryType::Search,
+                3000,
+                &[("browse", 0.5), ("search", 0.2), ("order", 0.25)],
+            ),
+        );
+        states.insert(
+            "order".to_string(),
+            state(
+                QueryType::Insert,
+                5000,
+                &[("approve", 0.3), ("browse", 0.4)],
+            ),
+        );
+        states.insert(
+            "approve".to_string(),
+            state(QueryType::Update, 1000, &[("browse", 0.6)]),
+        );
+
+        Self {
+            start: "browse".to_string(),
+            states,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if !self.states.contains_key(&self.start) {
+            return Err(anyhow::anyhow!(
+                "User model start state '{}' is not defined",
+                self.start
+            ));
+        }
+        for (name, state) in &self.states {
+            if let Some(target) = state.next.keys().find(|t| !self.states.contains_key(*t)) {
+                return Err(anyhow::anyhow!(
+                    "User model state '{}' transitions to undefined state '{}'",
+                    name,
+                    target
+                ));
+            }
+            let total: f64 = state.next.values().sum();
+            if state.next.values().any(|p| *p < 0.0) || total > 1.0 + 1e-9 {
+                return Err(anyhow::anyhow!(
+                    "User model state '{}' transition probabilities must be non-negative and sum to at most 1.0 (got {:.2})",
+                    name,
+                    total
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Next state after `current`, or `None` when the session ends
+    fn transition(&self, current: &str, rng: &mut StdRng) -> Option<&str> {
+        let mut roll: f64 = rng.gen();
+        for (target, probability) in &self.states[current].next {
+            if roll < *probability {
+                return Some(target);
+            }
+            roll -= probability;
+        }
+        None
+    }
+}
+
+/// Everything the users have done so far, recorded as each action finishes so
+/// memory does not grow with the run's length
+#[derive(Default)]
+struct JourneyTotals {
+    sessions: usize,
+    session_durations: LatencyHistogram,
+    finished_users: usize,
+    recorder: MetricsRecorder,
+    latencies_by_action: BTreeMap<String, LatencyHistogram>,
+}
+
+/// Drive `virtual_users` independent users through the model until the run ends
+pub async fn run_virtual_user_simulation(
+    backend: &Backend,
+    args: &Args,
+    ctx: &Arc<QueryContext>,
+    model: Arc<UserModel>,
+    virtual_users: usize,
+    baseline_latency: f64,
+) -> anyhow::Result<SimulationResult> {
+    let start_time = Instant::now();
+    let end_time = start_time + Duration::from_secs(args.duration);
+    let totals = Arc::new(Mutex::new(JourneyTotals::default()));
+
+    let users = (0..virtual_users).map(|user| {
+        let backend = backend.clone();
+        let ctx = Arc::clone(ctx);
+        let model = Arc::clone(&model);
+        let totals = Arc::clone(&totals);
+        let iterations = args.user_iterations;
+        tokio::spawn(async move {
+            run_user(
+                &backend,
+                &ctx,
+                &model,
+                user as u64,
+                iterations,
+                end_time,
+                &totals,
+            )
+            .await
+        })
+    });
+    join_all(users).await;
+
+    let JourneyTotals {
+        sessions,
+        session_durations,
+        finished_users,
+        recorder,
+        latencies_by_action,
+    } = std::mem::take(&mut *totals.lock().unwrap());
+    let (recorder, total_duration) = ctx.with_warmup(recorder, start_time.elapsed());
+    let mut result = calculate_operational_result(
+        recorder,
+        total_duration,
+        args.connections,
+        baseline_latency,
+        args.robust_stats,
+    )?;
+    result.user_journeys = Some(UserJourneyReport {
+        virtual_users,
+        sessions,
+        completed_sessions: session_durations.count() as usize,
+        iterations_per_user: args.user_iterations,
+        finished_users: args.user_iterations.map(|_| finished_users),
+        session_duration: session_durations.summary(),
+        actions: latencies_by_action
+            .into_iter()
+            .map(|(action, latencies)| (action, latencies.summary()))
+            .collect(),
+    });
+
+    Ok(result)
+}
+
+/// Walk one user through sessions until the run ends or, with `iterations`, until
+/// the user has completed that many
+async fn run_user(
+    backend: &Backend,
+    ctx: &QueryContext,
+    model: &UserModel,
+    user: u64,
+    iterations: Option<u32>,
+    end_time: Instant,
+    totals: &Mutex<JourneyTotals>,
+) {
+    let mut rng = StdRng::seed_from_u64(user ^ ctx.seed_salt);
+    let mut completed_sessions = 0;
+    totals.lock().unwrap().sessions += 1;
+    let mut state = model.start.as_str();
+
+    // Stagger arrivals so users don't fire their first action in lockstep
+    let first_dwell = model.states[state].dwell.mul_f64(rng.gen::<f64>());
+    tokio::time::sleep(first_dwell).await;
+    let mut session_start = Instant::now();
+
+    while Instant::now() < end_time && !ctx.aborted() {
+        let current = &model.states[state];
+
+        ctx.pending_work.task_spawned();
+        let metrics = execute_query_with_timing(backend, ctx, &current.query, rng.gen()).await;
+        ctx.pending_work.task_finished();
+        let next = model.transition(state, &mut rng);
+        {
+            let mut totals = totals.lock().unwrap();
+            let action = totals
+                .latencies_by_action
+                .entry(state.to_string())
+                .or_default();
+            for metric in &metrics {
+                action.record(metric.latency);
+            }
+            totals.recorder.extend(metrics);
+            if next.is_none() {
+                totals.session_durations.record(session_start.elapsed());
+                completed_sessions += 1;
+                if iterations.is_some_and(|n| completed_sessions >= n) {
+                    totals.finished_users += 1;
+                    break;
+                }
+            }
+        }
+
+        if current.dwell > Duration::ZERO {
+            let dwell = Exp::new(1.0 / current.dwell.as_secs_f64())
+                .map(|exp| Duration::from_secs_f64(exp.sample(&mut rng)))
+                .unwrap_or(current.dwell);
+            let remaining = end_time.saturating_duration_since(Instant::now());
+            tokio::time::sleep(dwell.min(remaining)).await;
+        }
+
+        state = match next {
+            Some(next) => next,
+            None => {
+                totals.lock().unwrap().sessions += 1;
+                session_start = Instant::now();
+                model.start.as_str()
+            }
+        };
+    }
+}
+
+pub fn display_user_journeys(report: &UserJourneyReport) {
+    println!("\n🧭 User Journeys:");
+    println!("   Virtual Users:          {:>8}", report.virtual_users);
+    println!("   Sessions:               {:>8}", report.sessions);
+    println!(
+        "   Completed Sessions:     {:>8}",
+        report.completed_sessions
+    );
+    if let (Some(iterations), Some(finished)) = (report.iterations_per_user, report.finished_users)
+    {
+        println!(
+            "   Users Finished:         {:>8} of {} ({} iterations each)",
+            finished, report.virtual_users, iterations
+        );
+    }
+    if report.completed_sessions > 0 {
+        println!(
+            "   Session Duration (ms):  avg {:.0}  p50 {:.0}  p95 {:.0}",
+            report.session_duration.average_ms,
+            report.session_duration.p50_ms,
+            report.session_duration.p95_ms
+        );
+    }
+    println!("   Action             Count   Avg (ms)   P95 (ms)   P99 (ms)");
+    for (action, summary) in &report.actions {
+        println!(
+            "   {:<15}{:>9}{:>11.2}{:>11.2}{:>11.2}",
+            action, summary.count, summary.average_ms, summary.p95_ms, summary.p99_ms
+        );
+    }
+}
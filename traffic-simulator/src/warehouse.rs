@@ -0,0 +1,338 @@
+use crate::backdate::Backdate;
+use crate::databricks::{Parameter, StatementClient};
+use crate::driver::DatabaseDriver;
+use crate::run_tag::RunTag;
+use crate::skew::{self, KeySkew};
+use crate::stores::{self, StoreWeights};
+use crate::writes::{WriteCounts, WriteKind};
+use crate::{FailureStage, QueryAttempt, QueryContext, QueryType};
+use futures::future::BoxFuture;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Number of seeded orders, matching the order id range the workloads draw from
+const SEED_ORDERS: i64 = 100_000;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS orders (
+        order_id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 100001),
+        order_number STRING NOT NULL,
+        from_store_id INT,
+        to_store_id INT NOT NULL,
+        product_id INT NOT NULL,
+        quantity_cases INT NOT NULL,
+        order_status STRING NOT NULL,
+        requested_by INT NOT NULL,
+        approved_by INT,
+        order_date TIMESTAMP,
+        approved_date TIMESTAMP,
+        fulfilled_date TIMESTAMP,
+        notes STRING,
+        version INT
+    ) COMMENT 'Orders of the Postgres traffic simulator workload'
+";
+
+/// Whether `--database-url` names a SQL warehouse, which selects this backend
+pub fn is_warehouse_url(database_url: &str) -> bool {
+    database_url.starts_with("databricks://")
+}
+
+/// Databricks SQL warehouse, driven through the Statement Execution API with the
+/// same requests as the OLTP backends, to show how an analytical endpoint fares
+/// under operational traffic. There are no connections: each request is one
+/// HTTPS call, so its whole time counts as execution.
+#[derive(Clone)]
+pub struct WarehouseBackend {
+    statements: Arc<StatementClient>,
+}
+
+impl WarehouseBackend {
+    /// Connect to `databricks://HOST/sql/1.0/warehouses/ID[?catalog=C&schema=S]`,
+    /// the warehouse's HTTP path under its workspace host, with the access token
+    /// from `DATABRICKS_TOKEN`. Creates the orders table, seeded when empty.
+    pub async fn connect(database_url: &str) -> anyhow::Result<Self> {
+        let url = reqwest::Url::parse(database_url)?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow::anyhow!("SQL warehouse URL {} has no host", database_url))?;
+        let warehouse_id = url
+            .path()
+            .strip_prefix("/sql/1.0/warehouses/")
+            .filter(|id| !id.is_empty() && !id.contains('/'))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "expected databricks://HOST/sql/1.0/warehouses/ID, got {}",
+                    database_url
+                )
+            })?;
+        let query = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+        let token = std::env::var("DATABRICKS_TOKEN").map_err(|_| {
+            anyhow::anyhow!("A databricks:// --database-url needs DATABRICKS_TOKEN")
+        })?;
+
+        let backend = Self {
+            statements: Arc::new(StatementClient::new(
+                host,
+                token,
+                warehouse_id,
+                query("catalog"),
+                query("schema"),
+            )),
+        };
+        backend.bootstrap().await?;
+
+        Ok(backend)
+    }
+
+    async fn bootstrap(&self) -> anyhow::Result<()> {
+        self.statements.execute(SCHEMA, &[]).await?;
+        let existing = self
+            .statements
+            .execute("SELECT 1 FROM orders LIMIT 1", &[])
+            .await?;
+        if existing.row_count() == 0 {
+            self.statements
+                .execute(
+                    "INSERT INTO orders (order_id, order_number, to_store_id, product_id, quantity_cases,
+                                         requested_by, order_status, order_date, version)
+                     SELECT id, concat('SEED', id), 1 + id % 10, 1 + id % 50, 1 + id % 20, 1 + id % 5,
+                            CASE id % 3 WHEN 0 THEN 'pending_review' WHEN 1 THEN 'approved' ELSE 'fulfilled' END,
+                            current_timestamp(), 1
+                     FROM range(1, :seed_orders + 1)",
+                    &[Parameter::typed("seed_orders", SEED_ORDERS, "BIGINT")],
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn attempt(&self, ctx: &QueryContext, query_type: &QueryType, seed: u64) -> QueryAttempt {
+        let query_start = Instant::now();
+        let mut writes = WriteCounts::default();
+        let result = self
+            .execute_operational_query(ctx, query_type, seed, &mut writes)
+            .await;
+
+        QueryAttempt {
+            connection_time: Duration::ZERO,
+            query_execution_time: query_start.elapsed(),
+            connection_recycled: false,
+            error: result.err().map(|e| (FailureStage::Query, e)),
+            writes,
+            think_time: Duration::ZERO,
+        }
+    }
+
+    async fn execute_operational_query(
+        &self,
+        ctx: &QueryContext,
+        query_type: &QueryType,
+        seed: u64,
+        writes: &mut WriteCounts,
+    ) -> anyhow::Result<u64> {
+        let stores = ctx.stores_for(seed).map(Arc::as_ref);
+        match query_type.resolve(seed) {
+            QueryType::Select => self.execute_select(seed, ctx.skew).await,
+            QueryType::Insert => {
+                self.execute_insert(
+                    seed,
+                    ctx.backdate.as_deref(),
+                    stores,
+                    &ctx.run_tag,
+                    ctx.skew,
+                    writes,
+                )
+                .await
+            }
+            QueryType::Update => self.execute_update(seed, ctx.skew, writes).await,
+            QueryType::Search => self.execute_search(seed, stores, ctx.skew).await,
+            QueryType::Mixed => unreachable!("Mixed resolves to a single statement"),
+            QueryType::Transaction => Err(anyhow::anyhow!(
+                "SQL warehouses do not run multi-statement transactions"
+            )),
+            query_type @ (QueryType::Workflow
+            | QueryType::Inventory
+            | QueryType::Catalog
+            | QueryType::Store) => Err(anyhow::anyhow!(
+                "--query-type {} requires the PostgreSQL backend",
+                query_type.name()
+            )),
+        }
+    }
+
+    async fn execute_select(&self, seed: u64, skew: KeySkew) -> anyhow::Result<u64> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let order_id = skew.pick_i64(skew::ORDER_KEY_COUNT.into(), &mut rng);
+
+        let response = self
+            .statements
+            .execute(
+                "SELECT order_id, order_number, order_status, quantity_cases FROM orders WHERE order_id = :order_id",
+                &[Parameter::typed("order_id", order_id, "BIGINT")],
+            )
+            .await?;
+
+        Ok(response.row_count())
+    }
+
+    async fn execute_search(
+        &self,
+        seed: u64,
+        stores: Option<&StoreWeights>,
+        skew: KeySkew,
+    ) -> anyhow::Result<u64> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let to_store_id = stores::choose_store(stores, skew, &mut rng);
+        let status = ["pending_review", "approved", "fulfilled"][rng.gen_range(0..3)];
+
+        let response = self
+            .statements
+            .execute(
+                "SELECT order_id, order_number, order_status, quantity_cases FROM orders
+                 WHERE to_store_id = :to_store_id AND order_status = :status
+                 ORDER BY order_date DESC LIMIT 20",
+                &[
+                    Parameter::typed("to_store_id", to_store_id, "INT"),
+                    Parameter::string("status", status),
+                ],
+            )
+            .await?;
+
+        Ok(response.row_count())
+    }
+
+    async fn execute_insert(
+        &self,
+        seed: u64,
+        backdate: Option<&Backdate>,
+        stores: Option<&StoreWeights>,
+        tag: &RunTag,
+        skew: KeySkew,
+        writes: &mut WriteCounts,
+    ) -> anyhow::Result<u64> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let to_store_id = stores::choose_store(stores, skew, &mut rng);
+        let product_id = skew.pick_i32(skew::PRODUCT_COUNT, &mut rng);
+        let quantity_cases = rng.gen_range(1..=20i32);
+        let requested_by = rng.gen_range(1..=5i32);
+
+        // Delta tables do not enforce uniqueness: a colliding order number would be
+        // stored twice rather than rejected and retried, so use one that cannot collide
+        let order_number = format!(
+            "{}ORD{}",
+            tag.order_number_prefix(stores),
+            uuid::Uuid::new_v4().simple().to_string().to_uppercase()
+        );
+        let mut parameters = vec![
+            Parameter::string("order_number", order_number),
+            Parameter::typed("to_store_id", to_store_id, "INT"),
+            Parameter::typed("product_id", product_id, "INT"),
+            Parameter::typed("quantity_cases", quantity_cases, "INT"),
+            Parameter::typed("requested_by", requested_by, "INT"),
+        ];
+        let statement = match backdate.map(|backdate| backdate.sample(&mut rng)) {
+            Some(order_date) => {
+                parameters.push(Parameter::typed("order_date", order_date, "DOUBLE"));
+                "INSERT INTO orders (order_number, to_store_id, product_id, quantity_cases, requested_by,
+                                     order_status, order_date, version)
+                 VALUES (:order_number, :to_store_id, :product_id, :quantity_cases, :requested_by,
+                         'pending_review', timestamp_seconds(:order_date), 1)"
+            }
+            None => {
+                "INSERT INTO orders (order_number, to_store_id, product_id, quantity_cases, requested_by,
+                                     order_status, order_date, version)
+                 VALUES (:order_number, :to_store_id, :product_id, :quantity_cases, :requested_by,
+                         'pending_review', current_timestamp(), 1)"
+            }
+        };
+
+        let response = self.statements.execute(statement, &parameters).await?;
+        let rows = affected_rows(&response);
+        writes.record(WriteKind::Insert, rows);
+
+        Ok(rows)
+    }
+
+    async fn execute_update(
+        &self,
+        seed: u64,
+        skew: KeySkew,
+        writes: &mut WriteCounts,
+    ) -> anyhow::Result<u64> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let transition = rng.gen_range(0..3);
+        let order_id = skew.pick_i64(skew::ORDER_KEY_COUNT.into(), &mut rng);
+
+        // Delta's UPDATE cannot pick its row with a subquery, so the status
+        // transitions target a random order instead of the next one in line
+        let statement = match transition {
+            0 => {
+                "UPDATE orders SET order_status = 'approved', approved_date = current_timestamp()
+                 WHERE order_id = :order_id AND order_status = 'pending_review'"
+            }
+            1 => {
+                "UPDATE orders SET order_status = 'fulfilled', fulfilled_date = current_timestamp()
+                 WHERE order_id = :order_id AND order_status = 'approved'"
+            }
+            _ => {
+                "UPDATE orders SET quantity_cases = :new_quantity
+                 WHERE order_id = :order_id AND order_status = 'pending_review'"
+            }
+        };
+        let mut parameters = vec![Parameter::typed("order_id", order_id, "BIGINT")];
+        if transition == 2 {
+            let new_quantity = rng.gen_range(1..=25i32);
+            parameters.push(Parameter::typed("new_quantity", new_quantity, "INT"));
+        }
+
+        let response = self.statements.execute(statement, &parameters).await?;
+        let rows = affected_rows(&response);
+        writes.record(WriteKind::Update, rows);
+
+        Ok(rows)
+    }
+}
+
+impl DatabaseDriver for WarehouseBackend {
+    fn test_connection(&self) -> BoxFuture<'_, anyhow::Result<()>> {
+        Box::pin(async move {
+            self.statements.execute("SELECT 1", &[]).await?;
+            Ok(())
+        })
+    }
+
+    fn server_version(&self) -> BoxFuture<'_, anyhow::Result<String>> {
+        Box::pin(async move {
+            let response = self.statements.execute("SELECT version()", &[]).await?;
+            response
+                .first_value()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow::anyhow!("version() returned no row"))
+        })
+    }
+
+    fn execute_attempt<'a>(
+        &'a self,
+        ctx: &'a QueryContext,
+        query_type: &'a QueryType,
+        seed: u64,
+        _attempt: u32,
+    ) -> BoxFuture<'a, QueryAttempt> {
+        Box::pin(self.attempt(ctx, query_type, seed))
+    }
+}
+
+/// `num_affected_rows` of an INSERT or UPDATE, reported as its first column
+fn affected_rows(response: &crate::databricks::StatementResponse) -> u64 {
+    response
+        .first_value()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_default()
+}
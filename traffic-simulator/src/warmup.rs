@@ -0,0 +1,147 @@
+use crate::plugin::WasmPlugin;
+use crate::recorder::MetricsRecorder;
+use crate::stat_statements::ObservedWorkload;
+use crate::worker::WorkerPool;
+use crate::ycsb::YcsbWorkload;
+use crate::{execute_operational_query_with_timing, Args, Backend, LatencySummary, QueryContext};
+use deadpool_postgres::Pool;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Warmup queries issued per connection
+const QUERIES_PER_CONNECTION: usize = 5;
+
+/// How the warmup went; its queries are excluded from the run's results unless
+/// --include-warmup is given
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WarmupReport {
+    pub queries: usize,
+    pub failed_queries: usize,
+    pub duration_ms: f64,
+    /// Time until the pool held every connection; `None` when it never filled (or
+    /// for the SQLite backend)
+    pub pool_fill_ms: Option<f64>,
+    /// The first query, run on its own before the rest
+    pub first_query_latency_ms: f64,
+    pub latency: LatencySummary,
+    /// Whether the warmup's queries and time are counted in the results
+    pub included: bool,
+}
+
+/// Run the warmup queries and return their report and metrics
+pub async fn run_warmup(
+    backend: &Backend,
+    args: &Args,
+    plugin: Option<Arc<WasmPlugin>>,
+    ycsb: Option<Arc<YcsbWorkload>>,
+    observed: Option<Arc<ObservedWorkload>>,
+) -> anyhow::Result<(WarmupReport, MetricsRecorder)> {
+    let warmup_queries = args.connections * QUERIES_PER_CONNECTION;
+    let mut ctx = QueryContext::new(args, plugin, ycsb, None, None, None, None).await?;
+    ctx.observed = observed;
+    let ctx = Arc::new(ctx);
+    let start = Instant::now();
+    let pool_fill = match backend {
+        Backend::Postgres(pool) => Some(tokio::spawn(wait_for_full_pool(pool.clone(), start))),
+        Backend::Sqlite(_) | Backend::Mysql(_) | Backend::DatabricksSql(_) => None,
+    };
+
+    // The first query runs alone, so its latency is that of a cold start
+    let first_query = execute_operational_query_with_timing(backend, &ctx, 0).await;
+    let first_query_latency_ms = first_query
+        .first()
+        .map_or(0.0, |metric| metric.latency.as_secs_f64() * 1000.0);
+
+    let workers = WorkerPool::start(backend, &ctx, args.connections, None);
+    for seed in 1..warmup_queries as u64 {
+        workers.submit(seed).await;
+    }
+    let mut recorder = workers.finish().await;
+    recorder.extend(first_query);
+    let duration = start.elapsed();
+
+    let pool_fill_ms = match pool_fill {
+        Some(task) if task.is_finished() => task.await.ok(),
+        Some(task) => {
+            task.abort();
+            None
+        }
+        None => None,
+    }
+    .map(|fill| fill.as_secs_f64() * 1000.0);
+
+    let report = WarmupReport {
+        queries: recorder.total_queries,
+        failed_queries: recorder.total_queries - recorder.successful_queries,
+        duration_ms: duration.as_secs_f64() * 1000.0,
+        pool_fill_ms,
+        first_query_latency_ms,
+        latency: recorder.latency.summary(),
+        included: args.include_warmup,
+    };
+    if !args.disable_logging {
+        info!(
+            "🏁 Warmup: {} queries in {:.1}s, first query {:.2}ms{}; {} the results",
+            report.queries,
+            duration.as_secs_f64(),
+            report.first_query_latency_ms,
+            report
+                .pool_fill_ms
+                .map(|ms| format!(", pool filled in {:.0}ms", ms))
+                .unwrap_or_default(),
+            if report.included {
+                "included in"
+            } else {
+                "excluded from"
+            }
+        );
+    }
+
+    tokio::time::sleep(Duration::from_secs(1)).await; // Brief pause after warmup
+    Ok((report, recorder))
+}
+
+/// Time from `start` until every connection of `pool` is open
+async fn wait_for_full_pool(pool: Pool, start: Instant) -> Duration {
+    loop {
+        let status = pool.status();
+        if status.size >= status.max_size {
+            return start.elapsed();
+        }
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+}
+
+pub fn display_warmup(report: &WarmupReport) {
+    println!(
+        "🏁 Warmup ({} the results below):",
+        if report.included {
+            "included in"
+        } else {
+            "excluded from"
+        }
+    );
+    println!(
+        "   Queries:                {:>7} ({} failed) in {:.1}s",
+        report.queries,
+        report.failed_queries,
+        report.duration_ms / 1000.0
+    );
+    println!(
+        "   First Query:            {:>7.2}ms",
+        report.first_query_latency_ms
+    );
+    if let Some(ms) = report.pool_fill_ms {
+        println!("   Pool Filled After:      {:>7.0}ms", ms);
+    }
+    if report.latency.count > 0 {
+        println!(
+            "   Latency (ms):           avg {:.2}  p50 {:.2}  p99 {:.2}",
+            report.latency.average_ms, report.latency.p50_ms, report.latency.p99_ms
+        );
+    }
+    println!();
+}
@@ -0,0 +1,157 @@
+use crate::ramp::Ramp;
+use crate::recorder::MetricsRecorder;
+use crate::{execute_operational_query_with_timing, Backend, QueryContext, QueryMetric};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch, Mutex};
+use tokio::task::JoinHandle;
+
+/// Long-lived query workers pulling seeds from a bounded work queue. Replaces a
+/// spawned task per query: submitting only waits when every worker is busy and the
+/// queue is full, and finished queries stream to a single collector instead of
+/// being joined in batches.
+pub struct WorkerPool {
+    queue: mpsc::Sender<(u64, usize)>,
+    ctx: Arc<QueryContext>,
+    workers: Vec<JoinHandle<()>>,
+    collector: JoinHandle<(MetricsRecorder, BTreeMap<usize, MetricsRecorder>)>,
+    /// Phase that queries submitted from now on are attributed to
+    phase: AtomicUsize,
+    concurrency: Arc<Concurrency>,
+}
+
+/// Queries the workers have in flight, each on a connection of its own, and the
+/// ceiling on them
+#[derive(Debug)]
+struct Concurrency {
+    /// Workers with an index below this take work; the rest idle
+    limit: watch::Sender<usize>,
+    active: AtomicUsize,
+    /// Most queries in flight at once since the last `take_peak_concurrency`
+    peak: AtomicUsize,
+}
+
+impl WorkerPool {
+    /// Start `workers` workers with room for as many queued queries again. With a
+    /// ramp, workers join over its ramp-up and leave over its ramp-down.
+    pub fn start(
+        backend: &Backend,
+        ctx: &Arc<QueryContext>,
+        workers: usize,
+        ramp: Option<Ramp>,
+    ) -> Self {
+        let start = Instant::now();
+        let workers = workers.max(1);
+        let (queue, receiver) = mpsc::channel::<(u64, usize)>(workers);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let (metrics, mut finished) = mpsc::unbounded_channel::<(usize, Vec<QueryMetric>)>();
+        let concurrency = Arc::new(Concurrency {
+            limit: watch::Sender::new(workers),
+            active: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        });
+
+        let workers = (0..workers)
+            .map(|index| {
+                let backend = backend.clone();
+                let ctx = Arc::clone(ctx);
+                let receiver = Arc::clone(&receiver);
+                let metrics = metrics.clone();
+                let concurrency = Arc::clone(&concurrency);
+                let mut limit = concurrency.limit.subscribe();
+                let (joins, leaves) = ramp.map_or((Duration::ZERO, Duration::MAX), |ramp| {
+                    ramp.worker_window(index, workers)
+                });
+                tokio::spawn(async move {
+                    tokio::time::sleep_until((start + joins).into()).await;
+                    while start.elapsed() < leaves {
+                        // A worker above the ceiling idles until it rises again, and
+                        // checks once more when work arrives, as it may have dropped
+                        // meanwhile. Every worker holds the sender, so waiting cannot fail.
+                        let _ = limit.wait_for(|limit| index < *limit).await;
+                        // Only the receive holds the lock, never the query
+                        let work = receiver.lock().await.recv().await;
+                        let Some((seed, phase)) = work else { break };
+                        let _ = limit.wait_for(|limit| index < *limit).await;
+                        let active = concurrency.active.fetch_add(1, Ordering::Relaxed) + 1;
+                        concurrency.peak.fetch_max(active, Ordering::Relaxed);
+                        let query_metrics =
+                            execute_operational_query_with_timing(&backend, &ctx, seed).await;
+                        concurrency.active.fetch_sub(1, Ordering::Relaxed);
+                        ctx.pending_work.task_finished();
+                        if metrics.send((phase, query_metrics)).is_err() {
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let collector = tokio::spawn(async move {
+            let mut recorder = MetricsRecorder::default();
+            let mut phases = BTreeMap::<usize, MetricsRecorder>::new();
+            while let Some((phase, query_metrics)) = finished.recv().await {
+                let phase_recorder = phases.entry(phase).or_default();
+                for metric in &query_metrics {
+                    phase_recorder.record(metric);
+                    recorder.record(metric);
+                }
+            }
+            (recorder, phases)
+        });
+
+        Self {
+            queue,
+            ctx: Arc::clone(ctx),
+            workers,
+            collector,
+            phase: AtomicUsize::new(0),
+            concurrency,
+        }
+    }
+
+    /// Let at most `connections` of the workers run queries at once, each holding
+    /// one connection; workers over it finish their query and then idle
+    pub fn set_concurrency(&self, connections: usize) {
+        self.concurrency.limit.send_replace(connections.max(1));
+    }
+
+    /// Most queries in flight at once since the last call
+    pub fn take_peak_concurrency(&self) -> usize {
+        let active = self.concurrency.active.load(Ordering::Relaxed);
+        self.concurrency.peak.swap(active, Ordering::Relaxed)
+    }
+
+    /// Attribute queries submitted from now on to `phase`
+    pub fn set_phase(&self, phase: usize) {
+        self.phase.store(phase, Ordering::Relaxed);
+    }
+
+    /// Queue a query, waiting for a free slot when the workers are behind
+    pub async fn submit(&self, seed: u64) {
+        self.ctx.pending_work.task_spawned();
+        let phase = self.phase.load(Ordering::Relaxed);
+        if self.queue.send((seed, phase)).await.is_err() {
+            // Every worker has exited, so nothing will pick the query up
+            self.ctx.pending_work.task_finished();
+        }
+    }
+
+    /// Wait for queued and in-flight queries to finish and hand back their metrics
+    pub async fn finish(self) -> MetricsRecorder {
+        self.finish_by_phase().await.0
+    }
+
+    /// `finish`, also handing back the metrics of each phase set with `set_phase`
+    pub async fn finish_by_phase(self) -> (MetricsRecorder, BTreeMap<usize, MetricsRecorder>) {
+        // Idle workers must see the closed queue too
+        self.set_concurrency(usize::MAX);
+        drop(self.queue);
+        for worker in self.workers {
+            let _ = worker.await;
+        }
+        self.collector.await.unwrap_or_default()
+    }
+}
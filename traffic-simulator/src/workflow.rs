@@ -0,0 +1,355 @@
+use crate::recorder::LatencyHistogram;
+use crate::sampling;
+use crate::writes::{WriteCounts, WriteKind};
+use crate::{LatencySummary, QueryContext};
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand_distr::{Distribution, Exp};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio_postgres::Row;
+
+/// "workf", see [`crate::sampling`]
+const WORKFLOW_SEED_SALT: u64 = 0x776f_726b_6600_0000;
+
+/// Orders followed at once; past this, the order due soonest is advanced early
+/// rather than a new one created
+const MAX_IN_FLIGHT: usize = 100_000;
+
+/// Share of reviews that adjust the ordered quantity rather than only look
+const ADJUST_PROBABILITY: f64 = 0.3;
+
+/// Share of reviewed orders approved; the rest are cancelled
+const APPROVE_PROBABILITY: f64 = 0.85;
+
+/// Share of approved orders fulfilled; the rest are cancelled
+const FULFILL_PROBABILITY: f64 = 0.95;
+
+/// Step an order in flight waits for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Step {
+    /// A reviewer reads the pending order and sometimes corrects its quantity
+    Review,
+    /// The order is approved or cancelled
+    Approval,
+    /// The approved order is fulfilled or cancelled
+    Fulfillment,
+}
+
+impl Step {
+    /// Mean wait before this step, relative to --workflow-dwell
+    fn dwell_factor(self) -> f64 {
+        match self {
+            Step::Review => 0.5,
+            Step::Approval => 1.0,
+            Step::Fulfillment => 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct InFlight {
+    due: Instant,
+    order_id: i32,
+    step: Step,
+    created: Instant,
+}
+
+/// Orders `--query-type workflow` has created and not yet taken to a final state,
+/// due soonest first
+#[derive(Debug)]
+pub struct WorkflowTracker {
+    dwell: Duration,
+    in_flight: Mutex<BinaryHeap<Reverse<InFlight>>>,
+    stats: Mutex<WorkflowStats>,
+}
+
+/// Mergeable lifecycle counts, handed from shards to the parent process
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct WorkflowStats {
+    created: u64,
+    reviewed: u64,
+    adjusted: u64,
+    approved: u64,
+    fulfilled: u64,
+    cancelled: u64,
+    abandoned: u64,
+    in_flight: u64,
+    /// Milliseconds from creation until fulfillment or cancellation
+    lifecycle: LatencyHistogram,
+}
+
+/// Order lifecycles driven by `--query-type workflow`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WorkflowReport {
+    /// Orders inserted as pending_review
+    created: u64,
+    /// Pending orders a reviewer looked at
+    reviewed: u64,
+    /// Reviews that changed the ordered quantity
+    quantity_adjustments: u64,
+    approved: u64,
+    fulfilled: u64,
+    /// Orders cancelled at approval or fulfillment
+    cancelled: u64,
+    /// Orders dropped because something else changed their status first
+    abandoned: u64,
+    /// Orders still pending_review or approved when the run ended
+    in_flight: u64,
+    /// Creation to fulfillment or cancellation
+    lifecycle: LatencySummary,
+}
+
+impl WorkflowTracker {
+    pub fn new(dwell: Duration) -> Self {
+        Self {
+            dwell,
+            in_flight: Mutex::new(BinaryHeap::new()),
+            stats: Mutex::new(WorkflowStats::default()),
+        }
+    }
+
+    /// The order whose next step is due, if any
+    fn take_due(&self) -> Option<InFlight> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let Reverse(next) = in_flight.peek()?;
+        if next.due <= Instant::now() || in_flight.len() >= MAX_IN_FLIGHT {
+            in_flight.pop().map(|Reverse(order)| order)
+        } else {
+            None
+        }
+    }
+
+    /// Follow `order` until `step`, after an exponential dwell drawn from `rng`
+    fn schedule(&self, order_id: i32, created: Instant, step: Step, rng: &mut StdRng) {
+        let mean = self.dwell.as_secs_f64() * step.dwell_factor();
+        let dwell = Exp::new(1.0 / mean)
+            .map(|exp| Duration::from_secs_f64(exp.sample(rng)))
+            .unwrap_or(self.dwell);
+        self.in_flight.lock().unwrap().push(Reverse(InFlight {
+            due: Instant::now() + dwell,
+            order_id,
+            step,
+            created,
+        }));
+    }
+
+    pub fn stats(&self) -> WorkflowStats {
+        let mut stats = self.stats.lock().unwrap().clone();
+        stats.in_flight = self.in_flight.lock().unwrap().len() as u64;
+        stats
+    }
+}
+
+impl WorkflowStats {
+    pub fn merge(&mut self, other: &WorkflowStats) {
+        self.created += other.created;
+        self.reviewed += other.reviewed;
+        self.adjusted += other.adjusted;
+        self.approved += other.approved;
+        self.fulfilled += other.fulfilled;
+        self.cancelled += other.cancelled;
+        self.abandoned += other.abandoned;
+        self.in_flight += other.in_flight;
+        self.lifecycle.merge(&other.lifecycle);
+    }
+
+    /// `None` when the run created no workflow orders
+    pub fn report(&self) -> Option<WorkflowReport> {
+        (self.created > 0).then(|| WorkflowReport {
+            created: self.created,
+            reviewed: self.reviewed,
+            quantity_adjustments: self.adjusted,
+            approved: self.approved,
+            fulfilled: self.fulfilled,
+            cancelled: self.cancelled,
+            abandoned: self.abandoned,
+            in_flight: self.in_flight,
+            lifecycle: self.lifecycle.summary(),
+        })
+    }
+}
+
+/// Advance the order whose next step is due, or create a new one when none is. A
+/// failed step puts its order back, due at once, for the retry or the next request.
+pub async fn execute_workflow_step(
+    client: &deadpool_postgres::Client,
+    ctx: &QueryContext,
+    seed: u64,
+    attempt: u32,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<Vec<Row>> {
+    let tracker = &ctx.workflow;
+    let mut rng = sampling::attempt_rng(seed, WORKFLOW_SEED_SALT, attempt);
+
+    let Some(order) = tracker.take_due() else {
+        let stores = ctx.stores_for(seed).map(|stores| stores.as_ref());
+        let rows =
+            crate::execute_operational_insert_query(client, ctx, seed, attempt, stores, writes)
+                .await?;
+        if let Some(order_id) = rows.first().and_then(|row| row.try_get("order_id").ok()) {
+            tracker.stats.lock().unwrap().created += 1;
+            tracker.schedule(order_id, Instant::now(), Step::Review, &mut rng);
+        }
+        return Ok(rows);
+    };
+
+    let result = execute_step(client, ctx, &order, &mut rng, writes).await;
+    let rows = match result {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracker.in_flight.lock().unwrap().push(Reverse(InFlight {
+                due: Instant::now(),
+                ..order
+            }));
+            return Err(e);
+        }
+    };
+
+    let status = rows
+        .first()
+        .and_then(|row| row.try_get::<_, String>("order_status").ok());
+    let next = {
+        let mut stats = tracker.stats.lock().unwrap();
+        match (order.step, status.as_deref()) {
+            (Step::Review, _) => {
+                stats.reviewed += 1;
+                Some(Step::Approval)
+            }
+            // Something else moved the order on first; stop following it
+            (_, None) => {
+                stats.abandoned += 1;
+                None
+            }
+            (_, Some(status)) => {
+                match status {
+                    "approved" => stats.approved += 1,
+                    "fulfilled" => stats.fulfilled += 1,
+                    _ => stats.cancelled += 1,
+                }
+                let next = (status == "approved").then_some(Step::Fulfillment);
+                if next.is_none() {
+                    stats.lifecycle.record(order.created.elapsed());
+                }
+                next
+            }
+        }
+    };
+    if let Some(step) = next {
+        tracker.schedule(order.order_id, order.created, step, &mut rng);
+    }
+
+    Ok(rows)
+}
+
+/// Run `order`'s due step. Status changes only apply to an order still in the
+/// status the workflow left it in, and return its new status.
+async fn execute_step(
+    client: &deadpool_postgres::Client,
+    ctx: &QueryContext,
+    order: &InFlight,
+    rng: &mut StdRng,
+    writes: &mut WriteCounts,
+) -> anyhow::Result<Vec<Row>> {
+    let statements = &ctx.statements;
+    let tag = ctx.run_tag.update_set();
+    match order.step {
+        Step::Review if rng.gen_bool(ADJUST_PROBABILITY) => {
+            let new_quantity = rng.gen_range(1..=25i32);
+            let rows = statements
+                .query(
+                    client,
+                    &format!(
+                        "UPDATE orders SET quantity_cases = $1{} WHERE order_id = $2 AND order_status = 'pending_review' RETURNING order_id",
+                        tag
+                    ),
+                    &[&new_quantity, &order.order_id],
+                )
+                .await?;
+            writes.record(WriteKind::Update, rows.len() as u64);
+            if !rows.is_empty() {
+                ctx.workflow.stats.lock().unwrap().adjusted += 1;
+            }
+            Ok(rows)
+        }
+        Step::Review => Ok(statements
+            .query(
+                client,
+                "SELECT order_id, order_number, order_status, quantity_cases FROM orders WHERE order_id = $1",
+                &[&order.order_id],
+            )
+            .await?),
+        Step::Approval => {
+            let set = if rng.gen_bool(APPROVE_PROBABILITY) {
+                "order_status = 'approved', approved_by = $2, approved_date = CURRENT_TIMESTAMP"
+            } else {
+                "order_status = 'cancelled', approved_by = $2"
+            };
+            let approved_by = rng.gen_range(1..=5i32);
+            let rows = statements
+                .query(
+                    client,
+                    &format!(
+                        "UPDATE orders SET {}{} WHERE order_id = $1 AND order_status = 'pending_review' RETURNING order_id, order_status",
+                        set, tag
+                    ),
+                    &[&order.order_id, &approved_by],
+                )
+                .await?;
+            writes.record(WriteKind::Update, rows.len() as u64);
+            Ok(rows)
+        }
+        Step::Fulfillment => {
+            let set = if rng.gen_bool(FULFILL_PROBABILITY) {
+                "order_status = 'fulfilled', fulfilled_date = CURRENT_TIMESTAMP"
+            } else {
+                "order_status = 'cancelled'"
+            };
+            let rows = statements
+                .query(
+                    client,
+                    &format!(
+                        "UPDATE orders SET {}{} WHERE order_id = $1 AND order_status = 'approved' RETURNING order_id, order_status",
+                        set, tag
+                    ),
+                    &[&order.order_id],
+                )
+                .await?;
+            writes.record(WriteKind::Update, rows.len() as u64);
+            Ok(rows)
+        }
+    }
+}
+
+pub fn display_workflow(report: &WorkflowReport) {
+    println!("\n🔄 Order Workflows:");
+    println!("   Orders Created:         {:>8}", report.created);
+    println!(
+        "   Reviewed:               {:>8} ({} quantity adjustments)",
+        report.reviewed, report.quantity_adjustments
+    );
+    println!("   Approved:               {:>8}", report.approved);
+    println!("   Fulfilled:              {:>8}", report.fulfilled);
+    println!("   Cancelled:              {:>8}", report.cancelled);
+    println!("   Still In Flight:        {:>8}", report.in_flight);
+    if report.abandoned > 0 {
+        println!(
+            "   ⚠️  Abandoned:            {:>8} (status changed by another request)",
+            report.abandoned
+        );
+    }
+    if report.lifecycle.count > 0 {
+        println!(
+            "   Lifecycle (s):          avg {:.1}  p50 {:.1}  p95 {:.1}  p99 {:.1}  max {:.1}",
+            report.lifecycle.average_ms / 1000.0,
+            report.lifecycle.p50_ms / 1000.0,
+            report.lifecycle.p95_ms / 1000.0,
+            report.lifecycle.p99_ms / 1000.0,
+            report.lifecycle.max_ms / 1000.0
+        );
+    }
+}
@@ -0,0 +1,430 @@
+use crate::query_types::{QueryTypeStats, QueryTypeTally};
+use crate::recorder::MetricsRecorder;
+use crate::shape::{LoadShape, ShapeKind};
+use crate::{
+    calculate_operational_result, execute_query_from, Args, Backend, QueryContext, QueryType,
+    SimulationResult,
+};
+use futures::future::join_all;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Rate a shaped or ramped workload never drops below while it is active, so a
+/// curve starting at zero does not stall its schedule
+const MIN_ACTIVE_QPS: f64 = 1.0;
+
+/// `--workloads`: named workloads that run side by side against the same pool,
+/// each open-loop at its own rate
+#[derive(Debug)]
+pub struct WorkloadSet {
+    workloads: Vec<NamedWorkload>,
+}
+
+#[derive(Debug)]
+struct NamedWorkload {
+    name: String,
+    query_type: QueryType,
+    rate: Rate,
+}
+
+/// How a workload's target QPS varies over the run
+#[derive(Debug)]
+enum Rate {
+    Constant(f64),
+    Shape(LoadShape),
+    /// `qps` (possibly zero) between spikes of `spike_qps` lasting `length`, one
+    /// every `every` from `start` into the run
+    Spikes {
+        qps: f64,
+        start: Duration,
+        every: Duration,
+        length: Duration,
+        spike_qps: f64,
+    },
+}
+
+/// One entry of the workloads file as written
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkloadEntry {
+    name: String,
+    query_type: QueryType,
+    /// Constant rate, or the rate between spikes
+    qps: Option<f64>,
+    shape: Option<ShapeKind>,
+    min_qps: Option<f64>,
+    max_qps: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    period: Option<Duration>,
+    spikes: Option<SpikeEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpikeEntry {
+    #[serde(deserialize_with = "deserialize_duration")]
+    every: Duration,
+    #[serde(rename = "for", deserialize_with = "deserialize_duration")]
+    length: Duration,
+    qps: f64,
+    /// When the first spike starts; right away by default
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    start: Option<Duration>,
+}
+
+fn deserialize_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    crate::parse_duration(&value).map_err(serde::de::Error::custom)
+}
+
+fn deserialize_optional_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    deserialize_duration(deserializer).map(Some)
+}
+
+/// Outcome of one workload's requests
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WorkloadStats {
+    pub name: String,
+    pub query_type: QueryType,
+    /// The workload's rate as configured, e.g. `500 queries/second`
+    pub pattern: String,
+    #[serde(flatten)]
+    pub stats: QueryTypeStats,
+    pub queries_per_second: f64,
+    /// Share of all requests
+    pub share_percent: f64,
+}
+
+impl WorkloadSet {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let invalid = |e: String| anyhow::anyhow!("Invalid workloads '{}': {}", path, e);
+        let entries: Vec<WorkloadEntry> =
+            serde_yaml::from_str(&contents).map_err(|e| invalid(e.to_string()))?;
+        if entries.is_empty() {
+            return Err(invalid("no workloads defined".to_string()));
+        }
+
+        let mut workloads: Vec<NamedWorkload> = Vec::with_capacity(entries.len());
+        for entry in entries {
+            if workloads.iter().any(|workload| workload.name == entry.name) {
+                return Err(invalid(format!(
+                    "workload '{}' is defined twice",
+                    entry.name
+                )));
+            }
+            let rate = entry
+                .rate()
+                .map_err(|e| invalid(format!("workload '{}': {}", entry.name, e)))?;
+            workloads.push(NamedWorkload {
+                name: entry.name,
+                query_type: entry.query_type,
+                rate,
+            });
+        }
+        Ok(Self { workloads })
+    }
+
+    /// One line per workload, e.g. `dashboard reads: select at 500 queries/second`
+    pub fn describe(&self) -> Vec<String> {
+        self.workloads
+            .iter()
+            .map(|workload| {
+                format!(
+                    "{}: {:?} at {}",
+                    workload.name,
+                    workload.query_type,
+                    workload.rate.describe()
+                )
+            })
+            .collect()
+    }
+}
+
+impl WorkloadEntry {
+    fn rate(&self) -> Result<Rate, String> {
+        let positive = |name: &str, qps: f64| {
+            if qps.is_finite() && qps > 0.0 {
+                Ok(qps)
+            } else {
+                Err(format!("{} must be positive, got {}", name, qps))
+            }
+        };
+        match (self.shape, &self.spikes, self.qps) {
+            (Some(kind), None, None) => {
+                let (Some(min_qps), Some(max_qps)) = (self.min_qps, self.max_qps) else {
+                    return Err("shape requires min_qps and max_qps".to_string());
+                };
+                LoadShape::new(
+                    kind,
+                    self.period.unwrap_or(Duration::from_secs(60)),
+                    positive("min_qps", min_qps)?,
+                    positive("max_qps", max_qps)?,
+                )
+                .map(Rate::Shape)
+                .map_err(|e| e.to_string())
+            }
+            (None, Some(spikes), qps) if self.min_qps.is_none() && self.max_qps.is_none() => {
+                let qps = qps.unwrap_or(0.0);
+                if !qps.is_finite() || qps < 0.0 {
+                    return Err(format!(
+                        "qps between spikes must not be negative, got {}",
+                        qps
+                    ));
+                }
+                if spikes.length.is_zero() || spikes.length >= spikes.every {
+                    return Err(format!(
+                        "spikes must last longer than zero and less than their interval ({:?})",
+                        spikes.every
+                    ));
+                }
+                Ok(Rate::Spikes {
+                    qps,
+                    start: spikes.start.unwrap_or_default(),
+                    every: spikes.every,
+                    length: spikes.length,
+                    spike_qps: positive("spikes.qps", spikes.qps)?,
+                })
+            }
+            (None, None, Some(qps)) if self.min_qps.is_none() && self.max_qps.is_none() => {
+                Ok(Rate::Constant(positive("qps", qps)?))
+            }
+            _ => Err(
+                "set exactly one of qps, shape (with min_qps and max_qps) or spikes".to_string(),
+            ),
+        }
+    }
+}
+
+impl Rate {
+    /// Target QPS `elapsed` into the run
+    fn qps_at(&self, elapsed: Duration) -> f64 {
+        match self {
+            Rate::Constant(qps) => *qps,
+            Rate::Shape(shape) => shape.qps_at(elapsed),
+            Rate::Spikes {
+                qps,
+                start,
+                every,
+                length,
+                spike_qps,
+            } => {
+                let in_spike = elapsed
+                    .checked_sub(*start)
+                    .is_some_and(|since| since.as_nanos() % every.as_nanos() < length.as_nanos());
+                if in_spike {
+                    *spike_qps
+                } else {
+                    *qps
+                }
+            }
+        }
+    }
+
+    /// Start of the next spike after `elapsed`, for a workload idle between spikes
+    fn next_spike(&self, elapsed: Duration) -> Option<Duration> {
+        let Rate::Spikes { start, every, .. } = self else {
+            return None;
+        };
+        let Some(since) = elapsed.checked_sub(*start) else {
+            return Some(*start);
+        };
+        let cycles = since.as_nanos() / every.as_nanos() + 1;
+        Some(*start + Duration::from_nanos((cycles * every.as_nanos()) as u64))
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Rate::Constant(qps) => format!("{} queries/second", qps),
+            Rate::Shape(shape) => shape.describe(),
+            Rate::Spikes {
+                qps,
+                every,
+                length,
+                spike_qps,
+                ..
+            } => format!(
+                "{} queries/second with spikes to {} for {:?} every {:?}",
+                qps, spike_qps, length, every
+            ),
+        }
+    }
+}
+
+/// Run every workload open-loop at its own rate for --duration. They share the
+/// connection limit, so a saturated pool delays all of them alike; latency is
+/// measured from each query's scheduled arrival as with --target-qps.
+pub async fn run_workload_simulation(
+    backend: &Backend,
+    args: &Args,
+    ctx: &Arc<QueryContext>,
+    workloads: &WorkloadSet,
+    baseline_latency: f64,
+) -> anyhow::Result<SimulationResult> {
+    let start_time = Instant::now();
+    let end_time = start_time + Duration::from_secs(args.duration);
+    let semaphore = Arc::new(Semaphore::new(args.connections));
+
+    let runs = workloads
+        .workloads
+        .iter()
+        .enumerate()
+        .map(|(index, workload)| {
+            run_workload(
+                backend,
+                ctx,
+                &semaphore,
+                workload,
+                index,
+                (start_time, end_time),
+            )
+        });
+    let runs = join_all(runs).await;
+
+    let mut recorder = MetricsRecorder::default();
+    for (workload_recorder, _) in &runs {
+        recorder.merge(workload_recorder);
+    }
+    let (recorder, total_duration) = ctx.with_warmup(recorder, start_time.elapsed());
+    let mut result = calculate_operational_result(
+        recorder,
+        total_duration,
+        args.connections,
+        baseline_latency,
+        args.robust_stats,
+    )?;
+
+    let duration_seconds = total_duration.as_secs_f64();
+    let total: usize = runs.iter().map(|(_, tally)| tally.stats().queries).sum();
+    let stats = workloads
+        .workloads
+        .iter()
+        .zip(&runs)
+        .map(|(workload, (_, tally))| {
+            let stats = tally.stats();
+            WorkloadStats {
+                name: workload.name.clone(),
+                query_type: workload.query_type.clone(),
+                pattern: workload.rate.describe(),
+                queries_per_second: (stats.queries - stats.failed_queries) as f64
+                    / duration_seconds,
+                share_percent: stats.queries as f64 / total.max(1) as f64 * 100.0,
+                stats,
+            }
+        })
+        .collect();
+    result.workloads = Some(stats);
+    Ok(result)
+}
+
+/// Issue one workload's queries until the run ends, returning their metrics and
+/// the workload's own tally
+async fn run_workload(
+    backend: &Backend,
+    ctx: &Arc<QueryContext>,
+    semaphore: &Arc<Semaphore>,
+    workload: &NamedWorkload,
+    index: usize,
+    (start_time, end_time): (Instant, Instant),
+) -> (MetricsRecorder, QueryTypeTally) {
+    // Workloads draw from separate seed ranges so they never generate the same rows
+    let seed_base = (index as u64) << 40;
+    let query_type = Arc::new(workload.query_type.clone());
+    let mut tasks = JoinSet::new();
+    let mut recorder = MetricsRecorder::default();
+    let mut tally = QueryTypeTally::default();
+    let mut collect = |metrics: Vec<_>| {
+        for metric in &metrics {
+            tally.record(metric);
+        }
+        recorder.extend(metrics);
+    };
+
+    let mut elapsed = Duration::ZERO;
+    let mut query_count = 0u64;
+    loop {
+        // Arrivals due while --control-port paused the run are skipped, not sent in
+        // a burst on resume
+        if let Some(control) = &ctx.control {
+            if control.wait_while_paused().await {
+                elapsed = elapsed.max(start_time.elapsed());
+            }
+        }
+        let scheduled = start_time + elapsed;
+        if scheduled >= end_time || ctx.aborted() {
+            break;
+        }
+        tokio::time::sleep_until(scheduled.into()).await;
+
+        let target_qps = workload.rate.qps_at(elapsed);
+        if target_qps <= 0.0 {
+            match workload.rate.next_spike(elapsed) {
+                Some(next) => elapsed = next,
+                None => break,
+            }
+            continue;
+        }
+
+        let backend = backend.clone();
+        let semaphore = Arc::clone(semaphore);
+        let task_ctx = Arc::clone(ctx);
+        let query_type = Arc::clone(&query_type);
+        let seed = seed_base | query_count;
+        ctx.pending_work.task_spawned();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire().await.unwrap();
+            let metrics =
+                execute_query_from(&backend, &task_ctx, &query_type, seed, scheduled).await;
+            task_ctx.pending_work.task_finished();
+            metrics
+        });
+        query_count += 1;
+
+        while let Some(result) = tasks.try_join_next() {
+            if let Ok(query_metrics) = result {
+                collect(query_metrics);
+            }
+        }
+
+        // Derive the next arrival from this one's scheduled time, so timer drift
+        // does not accumulate
+        let qps = match ctx.ramp {
+            Some(ramp) => (target_qps * ramp.factor(elapsed)).max(MIN_ACTIVE_QPS),
+            None => target_qps,
+        };
+        elapsed += Duration::from_secs_f64(1.0 / qps);
+    }
+
+    // Queries still in flight when the schedule ends are waited for, not dropped
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(query_metrics) = result {
+            collect(query_metrics);
+        }
+    }
+    (recorder, tally)
+}
+
+pub fn display_workloads(workloads: &[WorkloadStats]) {
+    println!("\n🧩 Workloads (latency in ms):");
+    println!("   Workload              Type        Share       QPS    Errors       p50       p95       p99");
+    for workload in workloads {
+        println!(
+            "   {:<22}{:<10}{:>7.1}%{:>10.1}{:>9.2}%{:>10.2}{:>10.2}{:>10.2}",
+            workload.name,
+            format!("{:?}", workload.query_type).to_uppercase(),
+            workload.share_percent,
+            workload.queries_per_second,
+            workload.stats.error_rate_percent,
+            workload.stats.latency.p50_ms,
+            workload.stats.latency.p95_ms,
+            workload.stats.latency.p99_ms
+        );
+        println!("     {}", workload.pattern);
+    }
+}
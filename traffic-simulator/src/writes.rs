@@ -0,0 +1,135 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Statement kinds whose affected-row counts are tracked
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum WriteKind {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl WriteKind {
+    /// Classify a statement by its leading keyword (after any `WITH` clause the
+    /// first write keyword wins); `None` for reads
+    pub fn of_sql(sql: &str) -> Option<Self> {
+        let upper = sql.trim_start().to_ascii_uppercase();
+        let first = upper.split_whitespace().next()?;
+        match first {
+            "INSERT" => Some(WriteKind::Insert),
+            "UPDATE" => Some(WriteKind::Update),
+            "DELETE" => Some(WriteKind::Delete),
+            "WITH" => upper.split_whitespace().find_map(|word| match word {
+                "INSERT" => Some(WriteKind::Insert),
+                "UPDATE" => Some(WriteKind::Update),
+                "DELETE" => Some(WriteKind::Delete),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Rows affected by each write statement of one request
+#[derive(Debug, Default)]
+pub struct WriteCounts(Vec<(WriteKind, u64)>);
+
+impl WriteCounts {
+    pub fn record(&mut self, kind: WriteKind, rows: u64) {
+        self.0.push((kind, rows));
+    }
+}
+
+/// Distribution of rows affected by one kind of write statement
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AffectedRowStats {
+    pub statements: u64,
+    pub rows: u64,
+    /// Statements that matched no rows
+    pub no_op: u64,
+    pub max_rows: u64,
+    /// Statement counts keyed by the upper bound of power-of-two row buckets:
+    /// `0`, `1`, `2`, `4` (3-4 rows), `8` (5-8 rows), ...
+    pub histogram: BTreeMap<u64, u64>,
+}
+
+impl AffectedRowStats {
+    fn add(&mut self, rows: u64) {
+        self.statements += 1;
+        self.rows += rows;
+        self.max_rows = self.max_rows.max(rows);
+        if rows == 0 {
+            self.no_op += 1;
+        }
+        *self.histogram.entry(bucket_bound(rows)).or_insert(0) += 1;
+    }
+
+    pub fn merge(&mut self, other: &AffectedRowStats) {
+        self.statements += other.statements;
+        self.rows += other.rows;
+        self.no_op += other.no_op;
+        self.max_rows = self.max_rows.max(other.max_rows);
+        for (bound, count) in &other.histogram {
+            *self.histogram.entry(*bound).or_insert(0) += count;
+        }
+    }
+
+    pub fn average_rows(&self) -> f64 {
+        if self.statements == 0 {
+            0.0
+        } else {
+            self.rows as f64 / self.statements as f64
+        }
+    }
+}
+
+fn bucket_bound(rows: u64) -> u64 {
+    if rows == 0 {
+        0
+    } else {
+        rows.next_power_of_two()
+    }
+}
+
+/// Add the writes of one successful request to the per-kind distributions
+pub fn tally(summary: &mut BTreeMap<WriteKind, AffectedRowStats>, counts: &WriteCounts) {
+    for (kind, rows) in &counts.0 {
+        summary.entry(*kind).or_default().add(*rows);
+    }
+}
+
+pub fn display_affected_rows(summary: &BTreeMap<WriteKind, AffectedRowStats>) {
+    println!("\n✍️  Affected Rows:");
+    println!("   Statement   Executed       Rows   Avg Rows   Max Rows    No-op");
+    for (kind, stats) in summary {
+        println!(
+            "   {:<10}{:>10}{:>11}{:>11.2}{:>11}{:>9}",
+            format!("{:?}", kind).to_uppercase(),
+            stats.statements,
+            stats.rows,
+            stats.average_rows(),
+            stats.max_rows,
+            stats.no_op
+        );
+    }
+    println!("   Rows per statement:");
+    for (kind, stats) in summary {
+        let buckets: Vec<String> = stats
+            .histogram
+            .iter()
+            .map(|(bound, count)| match *bound {
+                0..=2 => format!("{}: {}", bound, count),
+                bound => format!("{}-{}: {}", bound / 2 + 1, bound, count),
+            })
+            .collect();
+        println!(
+            "     {:<10}{}",
+            format!("{:?}", kind).to_uppercase(),
+            buckets.join("  ")
+        );
+    }
+}
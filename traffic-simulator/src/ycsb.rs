@@ -0,0 +1,314 @@
+use crate::prepared::Statements;
+use crate::writes::{WriteCounts, WriteKind};
+use deadpool_postgres::Pool;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Zipf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Row;
+use tracing::info;
+
+/// YCSB's default zipfian constant
+const ZIPFIAN_CONSTANT: f64 = 0.99;
+const FIELD_COUNT: usize = 10;
+const FIELD_LENGTH: usize = 100;
+const MAX_SCAN_LENGTH: i64 = 100;
+
+/// How keys are chosen for reads, updates and scans
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum RequestDistribution {
+    Uniform,
+    /// A few keys are hot; hot keys are scattered across the key space
+    Zipfian,
+    /// The most recently inserted keys are hot
+    Latest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operation {
+    Read,
+    Update,
+    Insert,
+    Scan,
+    ReadModifyWrite,
+}
+
+/// The core workload parameters of one YCSB preset
+#[derive(Debug, Clone, Copy)]
+struct Preset {
+    read: Operation,
+    read_proportion: f64,
+    write: Operation,
+    distribution: RequestDistribution,
+}
+
+/// Workload state shared by every query of a YCSB run
+#[derive(Debug)]
+pub struct YcsbWorkload {
+    preset: Preset,
+    record_count: u64,
+    /// Keys `0..key_count` exist; inserts claim the next one
+    key_count: AtomicU64,
+}
+
+impl YcsbWorkload {
+    /// Core workload `letter` (a–f), with the request distribution and read
+    /// proportion optionally overridden
+    pub fn new(
+        letter: char,
+        record_count: u64,
+        distribution: Option<RequestDistribution>,
+        read_proportion: Option<f64>,
+    ) -> Self {
+        use Operation::*;
+        use RequestDistribution::*;
+
+        let (read, read_proportion_default, write, distribution_default) = match letter {
+            'a' => (Read, 0.5, Update, Zipfian),
+            'b' => (Read, 0.95, Update, Zipfian),
+            'c' => (Read, 1.0, Update, Zipfian),
+            'd' => (Read, 0.95, Insert, Latest),
+            'e' => (Scan, 0.95, Insert, Zipfian),
+            'f' => (Read, 0.5, ReadModifyWrite, Zipfian),
+            _ => unreachable!("YCSB core workloads are a-f"),
+        };
+
+        Self {
+            preset: Preset {
+                read,
+                read_proportion: read_proportion.unwrap_or(read_proportion_default),
+                write,
+                distribution: distribution.unwrap_or(distribution_default),
+            },
+            record_count,
+            key_count: AtomicU64::new(record_count),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{:.0}% {:?} / {:.0}% {:?}, {:?} over {} records",
+            self.preset.read_proportion * 100.0,
+            self.preset.read,
+            (1.0 - self.preset.read_proportion) * 100.0,
+            self.preset.write,
+            self.preset.distribution,
+            self.record_count
+        )
+    }
+
+    /// Create and load `usertable` (the equivalent of `ycsb load`)
+    pub async fn load(&self, pool: &Pool, disable_logging: bool) -> anyhow::Result<()> {
+        let client = pool.get().await?;
+
+        if !disable_logging {
+            info!("📚 Loading {} YCSB records...", self.record_count);
+        }
+
+        let fields: Vec<String> = (0..FIELD_COUNT)
+            .map(|i| format!("field{i} text NOT NULL"))
+            .collect();
+        client
+            .batch_execute(&format!(
+                "DROP TABLE IF EXISTS usertable;
+                 CREATE TABLE usertable (ycsb_key varchar(64) PRIMARY KEY, {});",
+                fields.join(", ")
+            ))
+            .await?;
+
+        let values: Vec<String> = (0..FIELD_COUNT)
+            .map(|i| format!("substr(repeat(md5(n::text || '{i}'), 4), 1, {FIELD_LENGTH})"))
+            .collect();
+        client
+            .execute(
+                &format!(
+                    "INSERT INTO usertable
+                     SELECT 'user' || lpad(n::text, 12, '0'), {}
+                     FROM generate_series(0, $1::bigint - 1) AS n",
+                    values.join(", ")
+                ),
+                &[&(self.record_count as i64)],
+            )
+            .await?;
+        client.batch_execute("VACUUM ANALYZE usertable").await?;
+
+        if !disable_logging {
+            info!("✅ YCSB records loaded");
+        }
+
+        Ok(())
+    }
+
+    /// Check `usertable` holds the configured records, removing any rows inserted by
+    /// earlier runs so every run starts from the same key space
+    pub async fn verify(&self, pool: &Pool) -> anyhow::Result<()> {
+        let client = pool.get().await?;
+
+        client
+            .execute(
+                "DELETE FROM usertable WHERE ycsb_key > $1",
+                &[&key(self.record_count - 1)],
+            )
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!("YCSB usertable not found ({}); rerun with --ycsb-load", e)
+            })?;
+        let rows: i64 = client
+            .query_one("SELECT count(*) FROM usertable", &[])
+            .await?
+            .get(0);
+
+        if rows != self.record_count as i64 {
+            return Err(anyhow::anyhow!(
+                "YCSB usertable holds {} records, not {}; rerun with --ycsb-load",
+                rows,
+                self.record_count
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Run one operation drawn from the preset's mix
+    pub async fn execute_operation(
+        &self,
+        client: &deadpool_postgres::Client,
+        statements: &Statements,
+        seed: u64,
+        writes: &mut WriteCounts,
+    ) -> anyhow::Result<Vec<Row>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let operation = if rng.gen::<f64>() < self.preset.read_proportion {
+            self.preset.read
+        } else {
+            self.preset.write
+        };
+
+        let rows = match operation {
+            Operation::Read => {
+                statements
+                    .query(
+                        client,
+                        "SELECT * FROM usertable WHERE ycsb_key = $1",
+                        &[&self.choose_key(&mut rng)],
+                    )
+                    .await?
+            }
+            Operation::Update => {
+                let field = rng.gen_range(0..FIELD_COUNT);
+                let updated = statements
+                    .execute(
+                        client,
+                        &format!("UPDATE usertable SET field{field} = $1 WHERE ycsb_key = $2"),
+                        &[&random_field(&mut rng), &self.choose_key(&mut rng)],
+                    )
+                    .await?;
+                writes.record(WriteKind::Update, updated);
+                Vec::new()
+            }
+            Operation::Insert => {
+                let key = key(self.key_count.fetch_add(1, Ordering::Relaxed));
+                let values: Vec<String> =
+                    (0..FIELD_COUNT).map(|_| random_field(&mut rng)).collect();
+                let mut params: Vec<&(dyn ToSql + Sync)> = vec![&key];
+                params.extend(values.iter().map(|v| v as &(dyn ToSql + Sync)));
+                let placeholders: Vec<String> =
+                    (2..=FIELD_COUNT + 1).map(|i| format!("${i}")).collect();
+                let inserted = statements
+                    .execute(
+                        client,
+                        &format!(
+                            "INSERT INTO usertable VALUES ($1, {})",
+                            placeholders.join(", ")
+                        ),
+                        &params,
+                    )
+                    .await?;
+                writes.record(WriteKind::Insert, inserted);
+                Vec::new()
+            }
+            Operation::Scan => {
+                let length = rng.gen_range(1..=MAX_SCAN_LENGTH);
+                statements
+                    .query(
+                        client,
+                        "SELECT * FROM usertable WHERE ycsb_key >= $1 ORDER BY ycsb_key LIMIT $2",
+                        &[&self.choose_key(&mut rng), &length],
+                    )
+                    .await?
+            }
+            Operation::ReadModifyWrite => {
+                let key = self.choose_key(&mut rng);
+                let rows = statements
+                    .query(
+                        client,
+                        "SELECT * FROM usertable WHERE ycsb_key = $1",
+                        &[&key],
+                    )
+                    .await?;
+                let field = rng.gen_range(0..FIELD_COUNT);
+                let updated = statements
+                    .execute(
+                        client,
+                        &format!("UPDATE usertable SET field{field} = $1 WHERE ycsb_key = $2"),
+                        &[&random_field(&mut rng), &key],
+                    )
+                    .await?;
+                writes.record(WriteKind::Update, updated);
+                rows
+            }
+        };
+
+        Ok(rows)
+    }
+
+    /// Pick an existing key according to the request distribution
+    fn choose_key(&self, rng: &mut StdRng) -> String {
+        let key_count = self.key_count.load(Ordering::Relaxed).max(1);
+        let n = match self.preset.distribution {
+            RequestDistribution::Uniform => rng.gen_range(0..key_count),
+            RequestDistribution::Zipfian => {
+                // Scramble ranks so the hot keys are not all adjacent
+                fnv_hash(zipf_rank(key_count, rng)) % key_count
+            }
+            RequestDistribution::Latest => key_count - 1 - zipf_rank(key_count, rng),
+        };
+        key(n)
+    }
+}
+
+/// 0-based zipfian rank in `0..n`, rank 0 being the most popular
+fn zipf_rank(n: u64, rng: &mut StdRng) -> u64 {
+    Zipf::new(n, ZIPFIAN_CONSTANT)
+        .map(|zipf| zipf.sample(rng) as u64 - 1)
+        .unwrap_or(0)
+        .min(n - 1)
+}
+
+fn fnv_hash(value: u64) -> u64 {
+    value
+        .to_le_bytes()
+        .iter()
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// Parse a proportion between 0 and 1
+pub fn parse_proportion(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(p) if (0.0..=1.0).contains(&p) => Ok(p),
+        _ => Err(format!("'{}' is not a proportion between 0 and 1", value)),
+    }
+}
+
+fn key(n: u64) -> String {
+    format!("user{:012}", n)
+}
+
+fn random_field(rng: &mut StdRng) -> String {
+    (0..FIELD_LENGTH)
+        .map(|_| rng.gen_range(b'a'..=b'z') as char)
+        .collect()
+}